@@ -1,8 +1,15 @@
 extern crate grit;
 
-use clap::{value_t, App, Arg, SubCommand};
-use grit::commands;
-use std::path::PathBuf;
+use clap::{value_t, App, Arg, ArgGroup, SubCommand};
+use grit::commands::{
+    self, BranchAction, BranchError, CatFileMode, ConfigAction, ConfigOptions, ConfigScope,
+    ConfigType, DiffAlgorithm, DiffTarget, LogFormat, MergeBaseMode, StatusFormat, TagAction,
+};
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+    process,
+};
 
 fn main() {
     let matches = App::new("grit")
@@ -14,15 +21,382 @@ fn main() {
                 .about("Create an empty Git repository or reinitialize an existing one")
                 .arg(Arg::with_name("directory").help("Where the repository lives")),
         )
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Add file contents to the index")
+                .arg(
+                    Arg::with_name("pathspec")
+                        .multiple(true)
+                        .required(true)
+                        .help("Files to add content from"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("commit")
                 .about("Commit some code")
                 .arg(
                     Arg::with_name("message")
                         .short("m")
+                        .takes_value(true)
                         .help("A helpful message to accompany the commit"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("cat-file")
+                .about("Provide content or type and size information for repository objects")
+                .arg(
+                    Arg::with_name("type")
+                        .short("t")
+                        .help("Show the object's type"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .short("s")
+                        .help("Show the object's size"),
+                )
+                .arg(
+                    Arg::with_name("pretty")
+                        .short("p")
+                        .help("Pretty-print the object's content"),
+                )
+                .arg(
+                    Arg::with_name("exists")
+                        .short("e")
+                        .help("Exit with zero status if the object exists"),
+                )
+                .group(
+                    ArgGroup::with_name("mode")
+                        .args(&["type", "size", "pretty", "exists"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("object")
+                        .required(true)
+                        .help("The name of the object to show"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("hash-object")
+                .about("Compute object ID and optionally create a blob from a file")
+                .arg(
+                    Arg::with_name("write")
+                        .short("w")
+                        .help("Actually write the object into the object database"),
+                )
+                .arg(
+                    Arg::with_name("stdin")
+                        .long("stdin")
+                        .help("Read the object from standard input instead of from a file"),
+                )
+                .arg(Arg::with_name("file").multiple(true).help("Files to hash")),
+        )
+        .subcommand(
+            SubCommand::with_name("status")
+                .about("Show the working tree status")
+                .arg(
+                    Arg::with_name("short")
+                        .short("s")
+                        .long("short")
+                        .help("Give the output in the short format"),
+                )
+                .arg(
+                    Arg::with_name("porcelain")
+                        .long("porcelain")
+                        .help("Give the output in an easy-to-parse format for scripts"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Get and set repository or global options")
+                .arg(
+                    Arg::with_name("global")
+                        .long("global")
+                        .help("Use the config file in your home directory"),
+                )
+                .arg(
+                    Arg::with_name("system")
+                        .long("system")
+                        .help("Use the config file for every user on the machine"),
+                )
+                .arg(
+                    Arg::with_name("local")
+                        .long("local")
+                        .help("Use the repository's config file"),
+                )
+                .group(ArgGroup::with_name("scope").args(&["global", "system", "local"]))
+                .arg(
+                    Arg::with_name("get")
+                        .long("get")
+                        .help("Get the value for a key"),
+                )
+                .arg(
+                    Arg::with_name("get_all")
+                        .long("get-all")
+                        .help("Get all values for a multi-valued key"),
+                )
+                .arg(Arg::with_name("unset").long("unset").help("Remove a key"))
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .help("List all variables set in config files, along with their values"),
+                )
+                .group(ArgGroup::with_name("action").args(&["get", "get_all", "unset", "list"]))
+                .arg(
+                    Arg::with_name("show_origin")
+                        .long("show-origin")
+                        .help("Show which config file each value came from"),
+                )
+                .arg(
+                    Arg::with_name("type")
+                        .long("type")
+                        .takes_value(true)
+                        .help("Read and write values as bool, int or path"),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .required_unless("list")
+                        .conflicts_with("list")
+                        .help("The key, like user.name"),
+                )
+                .arg(
+                    Arg::with_name("value")
+                        .conflicts_with_all(&["get", "get_all", "unset"])
+                        .help("The value to set the key to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("branch")
+                .about("List, create, or delete branches")
+                .arg(
+                    Arg::with_name("move")
+                        .short("m")
+                        .help("Move/rename a branch"),
+                )
+                .arg(
+                    Arg::with_name("delete")
+                        .short("d")
+                        .help("Delete a fully merged branch"),
+                )
+                .arg(
+                    Arg::with_name("force_delete")
+                        .short("D")
+                        .help("Delete a branch even if it's not merged"),
+                )
+                .group(ArgGroup::with_name("action").args(&["move", "delete", "force_delete"]))
+                .arg(
+                    Arg::with_name("names")
+                        .multiple(true)
+                        .help("Branch names (and the start point, when creating)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tag")
+                .about("List, create, or delete tags")
+                .arg(
+                    Arg::with_name("annotate")
+                        .short("a")
+                        .help("Make an annotated tag object"),
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .short("m")
+                        .takes_value(true)
+                        .help("Use the given tag message (implies -a)"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .short("l")
+                        .long("list")
+                        .help("List tags, only those matching any of the patterns if given"),
+                )
+                .arg(
+                    Arg::with_name("delete")
+                        .short("d")
+                        .long("delete")
+                        .help("Delete tags"),
+                )
+                .group(ArgGroup::with_name("action").args(&["list", "delete", "annotate"]))
+                .arg(
+                    Arg::with_name("names")
+                        .multiple(true)
+                        .help("Tag names (and the object to tag, when creating), or patterns"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("checkout")
+                .about("Switch branches, or detach HEAD at another commit")
+                .arg(
+                    Arg::with_name("new_branch")
+                        .short("b")
+                        .takes_value(true)
+                        .help("Create a new branch and switch to it"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .help("The branch or commit to switch to (HEAD when creating a branch)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("switch")
+                .about("Switch branches")
+                .arg(
+                    Arg::with_name("create")
+                        .short("c")
+                        .long("create")
+                        .takes_value(true)
+                        .help("Create a new branch and switch to it"),
+                )
+                .arg(
+                    Arg::with_name("detach")
+                        .long("detach")
+                        .help("Switch to a commit for inspection, detaching HEAD"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .help("The branch to switch to (or start point when creating one)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("log")
+                .about("Show commit logs")
+                .arg(
+                    Arg::with_name("oneline")
+                        .long("oneline")
+                        .help("Show each commit's abbreviated ID and subject on one line"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .alias("pretty")
+                        .takes_value(true)
+                        .help("Show commits using a template, like '%h %s'"),
+                )
+                .arg(
+                    Arg::with_name("max_count")
+                        .short("n")
+                        .long("max-count")
+                        .takes_value(true)
+                        .help("Limit the number of commits to show"),
+                )
+                .arg(
+                    Arg::with_name("revisions")
+                        .multiple(true)
+                        .help("Show commits reachable from these (HEAD by default)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Join another line of history into the current branch")
+                .arg(
+                    Arg::with_name("message")
+                        .short("m")
+                        .takes_value(true)
+                        .help("The message for the merge commit"),
+                )
+                .arg(
+                    Arg::with_name("commit")
+                        .required(true)
+                        .help("The branch or commit to merge"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge-base")
+                .about("Find as good common ancestors as possible for a merge")
+                .arg(
+                    Arg::with_name("all")
+                        .short("a")
+                        .long("all")
+                        .help("Output all merge bases, not just one"),
+                )
+                .arg(
+                    Arg::with_name("octopus")
+                        .long("octopus")
+                        .help("Find the best common ancestors of all the commits at once"),
+                )
+                .arg(
+                    Arg::with_name("is_ancestor")
+                        .long("is-ancestor")
+                        .conflicts_with_all(&["all", "octopus"])
+                        .help("Check whether the first commit is an ancestor of the second"),
+                )
+                .arg(
+                    Arg::with_name("commits")
+                        .multiple(true)
+                        .required(true)
+                        .help("The commits to find common ancestors of"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pack-refs")
+                .about("Pack refs into a single file for efficient repository access")
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Pack every ref, not just tags and refs that are already packed"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rev-parse")
+                .about("Resolve revisions to the object IDs they name")
+                .arg(
+                    Arg::with_name("short")
+                        .long("short")
+                        .help("Show the shortest unique abbreviation of each object ID"),
+                )
+                .arg(
+                    Arg::with_name("revisions")
+                        .multiple(true)
+                        .required(true)
+                        .help("Revisions like HEAD~2, main^2, 1a2b3c4 or HEAD:README"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Show changes between the index, the workspace and commits")
+                .arg(
+                    Arg::with_name("cached")
+                        .long("cached")
+                        .alias("staged")
+                        .conflicts_with("revisions")
+                        .help("Show changes staged in the index since HEAD"),
+                )
+                .arg(
+                    Arg::with_name("patience")
+                        .long("patience")
+                        .help("Generate a diff using the patience diff algorithm"),
+                )
+                .arg(
+                    Arg::with_name("histogram")
+                        .long("histogram")
+                        .help("Generate a diff using the histogram diff algorithm"),
+                )
+                .arg(
+                    Arg::with_name("diff_algorithm")
+                        .long("diff-algorithm")
+                        .takes_value(true)
+                        .possible_values(&["myers", "default", "minimal", "patience", "histogram"])
+                        .help("Choose a diff algorithm"),
+                )
+                .group(ArgGroup::with_name("algorithm").args(&[
+                    "patience",
+                    "histogram",
+                    "diff_algorithm",
+                ]))
+                .arg(
+                    Arg::with_name("unified")
+                        .short("U")
+                        .long("unified")
+                        .takes_value(true)
+                        .help("Show this many lines of context around each change"),
+                )
+                .arg(
+                    Arg::with_name("revisions")
+                        .number_of_values(2)
+                        .multiple(true)
+                        .help("Two revisions to compare"),
+                ),
+        )
         .get_matches();
 
     if let Some(init) = matches.subcommand_matches("init") {
@@ -32,11 +406,354 @@ fn main() {
         };
     }
 
+    if let Some(add) = matches.subcommand_matches("add") {
+        let paths: Vec<PathBuf> = add
+            .values_of("pathspec")
+            .unwrap()
+            .map(PathBuf::from)
+            .collect();
+        if let Err(error) = commands::add(&paths) {
+            fail(&error);
+        }
+    }
+
     if let Some(commit) = matches.subcommand_matches("commit") {
         let message = match value_t!(commit, "message", String) {
             Ok(message) => message,
             Err(_) => "".to_string(),
         };
-        commands::commit(&message).unwrap();
+        if let Err(error) = commands::commit(&message) {
+            fail(&error);
+        }
     }
+
+    if let Some(cat_file) = matches.subcommand_matches("cat-file") {
+        let mode = if cat_file.is_present("type") {
+            CatFileMode::Type
+        } else if cat_file.is_present("size") {
+            CatFileMode::Size
+        } else if cat_file.is_present("exists") {
+            CatFileMode::Exists
+        } else {
+            CatFileMode::Pretty
+        };
+        let object = cat_file.value_of("object").unwrap();
+        match commands::cat_file(mode, object) {
+            Ok(output) => io::stdout().write_all(&output).unwrap(),
+            Err(_) if mode == CatFileMode::Exists => process::exit(1),
+            Err(error) => fail(&error),
+        }
+    }
+
+    if let Some(hash_object) = matches.subcommand_matches("hash-object") {
+        let files: Vec<PathBuf> = match hash_object.values_of("file") {
+            Some(files) => files.map(PathBuf::from).collect(),
+            None => vec![],
+        };
+        let stdin = if hash_object.is_present("stdin") {
+            let mut content = Vec::new();
+            io::stdin().read_to_end(&mut content).unwrap();
+            Some(content)
+        } else {
+            None
+        };
+        match commands::hash_object(&files, stdin.as_deref(), hash_object.is_present("write")) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    // Without either flag, this still gives the short format, since there's no long one
+    if let Some(status) = matches.subcommand_matches("status") {
+        let format = match status.is_present("porcelain") {
+            true => StatusFormat::Porcelain,
+            false => StatusFormat::Short,
+        };
+        match commands::status(format) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    // Like git, this exits with its own statuses, some of them without saying anything
+    if let Some(config) = matches.subcommand_matches("config") {
+        let scope = if config.is_present("global") {
+            Some(ConfigScope::Global)
+        } else if config.is_present("system") {
+            Some(ConfigScope::System)
+        } else if config.is_present("local") {
+            Some(ConfigScope::Local)
+        } else {
+            None
+        };
+        let kind = config.value_of("type").map(|name| {
+            ConfigType::from_name(name)
+                .unwrap_or_else(|| fail(&format!("unrecognized --type argument, {}", name)))
+        });
+        let key = config.value_of("key").unwrap_or_default().to_string();
+        let action = match config.value_of("value") {
+            Some(value) => ConfigAction::Set {
+                key,
+                value: value.to_string(),
+            },
+            None if config.is_present("list") => ConfigAction::List,
+            None if config.is_present("get_all") => ConfigAction::GetAll { key },
+            None if config.is_present("unset") => ConfigAction::Unset { key },
+            None => ConfigAction::Get { key },
+        };
+        let options = ConfigOptions {
+            scope,
+            kind,
+            show_origin: config.is_present("show_origin"),
+        };
+        match commands::config(action, options) {
+            Ok(output) => print!("{}", output),
+            Err(error) => {
+                if !error.message.is_empty() {
+                    eprintln!("{}", error.message);
+                }
+                process::exit(error.status);
+            }
+        }
+    }
+
+    if let Some(branch) = matches.subcommand_matches("branch") {
+        let mut names: Vec<String> = match branch.values_of("names") {
+            Some(names) => names.map(String::from).collect(),
+            None => vec![],
+        };
+        let action = if branch.is_present("move") {
+            match names.len() {
+                1 => BranchAction::Rename {
+                    old: None,
+                    new: names.remove(0),
+                },
+                2 => BranchAction::Rename {
+                    old: Some(names.remove(0)),
+                    new: names.remove(0),
+                },
+                _ => fail("branch -m takes one or two branch names"),
+            }
+        } else if branch.is_present("delete") || branch.is_present("force_delete") {
+            BranchAction::Delete {
+                names,
+                force: branch.is_present("force_delete"),
+            }
+        } else {
+            match names.len() {
+                0 => BranchAction::List,
+                1 | 2 => BranchAction::Create {
+                    name: names.remove(0),
+                    start_point: names.pop(),
+                },
+                _ => fail("too many arguments for branch"),
+            }
+        };
+        // Like git, deleting reports each branch it couldn't delete and exits with 1
+        match commands::branch(action) {
+            Ok(output) => print!("{}", output),
+            Err(BranchError::Fatal(error)) => fail(&error),
+            Err(BranchError::NotDeleted { output, errors }) => {
+                print!("{}", output);
+                for error in errors {
+                    eprintln!("error: {}", error);
+                }
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(tag) = matches.subcommand_matches("tag") {
+        let mut names: Vec<String> = match tag.values_of("names") {
+            Some(names) => names.map(String::from).collect(),
+            None => vec![],
+        };
+        let action = if tag.is_present("delete") {
+            TagAction::Delete { names }
+        } else if tag.is_present("list") || names.is_empty() {
+            // Like git, creation options don't make sense when listing, named or not
+            if tag.is_present("annotate") || tag.is_present("message") {
+                fail_with_usage(tag.usage());
+            }
+            TagAction::List { patterns: names }
+        } else {
+            let message = tag.value_of("message").map(String::from);
+            if tag.is_present("annotate") && message.is_none() {
+                fail("no tag message given; use -m");
+            }
+            match names.len() {
+                1 | 2 => TagAction::Create {
+                    name: names.remove(0),
+                    target: names.pop(),
+                    message,
+                },
+                _ => fail("too many arguments for tag"),
+            }
+        };
+        match commands::tag(action) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    // Like git, these print their progress to stderr
+    if let Some(checkout) = matches.subcommand_matches("checkout") {
+        let new_branch = checkout.value_of("new_branch");
+        let target = match (checkout.value_of("target"), new_branch) {
+            (Some(target), _) => target,
+            (None, Some(_)) => "HEAD",
+            (None, None) => fail("you must specify a branch or commit to check out"),
+        };
+        match commands::checkout(target, new_branch) {
+            Ok(output) => eprint!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    if let Some(switch) = matches.subcommand_matches("switch") {
+        let new_branch = switch.value_of("create");
+        let target = match (switch.value_of("target"), new_branch) {
+            (Some(target), _) => target,
+            (None, Some(_)) => "HEAD",
+            (None, None) => fail("missing branch or commit argument"),
+        };
+        match commands::switch(target, new_branch, switch.is_present("detach")) {
+            Ok(output) => eprint!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    if let Some(log) = matches.subcommand_matches("log") {
+        let revisions: Vec<String> = match log.values_of("revisions") {
+            Some(revisions) => revisions.map(String::from).collect(),
+            None => vec![],
+        };
+        let format = match (log.is_present("oneline"), log.value_of("format")) {
+            (_, Some("oneline")) | (true, None) => LogFormat::Oneline,
+            (_, Some("medium")) | (false, None) => LogFormat::Medium,
+            (_, Some(template)) => LogFormat::Custom(
+                template
+                    .trim_start_matches("format:")
+                    .trim_start_matches("tformat:")
+                    .to_string(),
+            ),
+        };
+        let max_count = match log.value_of("max_count") {
+            Some(_) => match value_t!(log, "max_count", usize) {
+                Ok(max_count) => Some(max_count),
+                Err(_) => fail("-n expects a number"),
+            },
+            None => None,
+        };
+        match commands::log(&revisions, format, max_count) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    // Like git, a merge that stops for conflicts exits with 1
+    if let Some(merge) = matches.subcommand_matches("merge") {
+        let commit = merge.value_of("commit").unwrap();
+        match commands::merge(commit, merge.value_of("message")) {
+            Ok(outcome) => {
+                print!("{}", outcome.output);
+                if !outcome.clean {
+                    process::exit(1);
+                }
+            }
+            Err(error) => fail(&error),
+        }
+    }
+
+    // Finding nothing isn't an error, but like git, it exits with 1
+    if let Some(merge_base) = matches.subcommand_matches("merge-base") {
+        let commits: Vec<String> = merge_base
+            .values_of("commits")
+            .unwrap()
+            .map(String::from)
+            .collect();
+        if merge_base.is_present("is_ancestor") {
+            if commits.len() != 2 {
+                fail("--is-ancestor takes exactly two commits");
+            }
+            match commands::is_ancestor(&commits[0], &commits[1]) {
+                Ok(true) => {}
+                Ok(false) => process::exit(1),
+                Err(error) => fail(&error),
+            }
+        } else {
+            let mode = match merge_base.is_present("octopus") {
+                true => MergeBaseMode::Octopus,
+                false => MergeBaseMode::Default,
+            };
+            match commands::merge_base(&commits, mode, merge_base.is_present("all")) {
+                Ok(output) if output.is_empty() => process::exit(1),
+                Ok(output) => print!("{}", output),
+                Err(error) => fail(&error),
+            }
+        }
+    }
+
+    if let Some(pack_refs) = matches.subcommand_matches("pack-refs") {
+        match commands::pack_refs(pack_refs.is_present("all")) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    if let Some(rev_parse) = matches.subcommand_matches("rev-parse") {
+        let revisions: Vec<String> = rev_parse
+            .values_of("revisions")
+            .unwrap()
+            .map(String::from)
+            .collect();
+        match commands::rev_parse(&revisions, rev_parse.is_present("short")) {
+            Ok(output) => print!("{}", output),
+            Err(error) => fail(&error),
+        }
+    }
+
+    if let Some(diff) = matches.subcommand_matches("diff") {
+        let target = match (diff.is_present("cached"), diff.values_of("revisions")) {
+            (_, Some(mut revisions)) => {
+                let old = revisions.next().unwrap().to_string();
+                let new = revisions.next().unwrap().to_string();
+                DiffTarget::Revisions(old, new)
+            }
+            (true, None) => DiffTarget::Cached,
+            (false, None) => DiffTarget::Workspace,
+        };
+        let context = match diff.value_of("unified") {
+            Some(_) => match value_t!(diff, "unified", usize) {
+                Ok(context) => context,
+                Err(_) => fail("-U expects a number"),
+            },
+            None => 3,
+        };
+        let algorithm = if diff.is_present("patience") {
+            Some(DiffAlgorithm::Patience)
+        } else if diff.is_present("histogram") {
+            Some(DiffAlgorithm::Histogram)
+        } else {
+            diff.value_of("diff_algorithm")
+                .and_then(DiffAlgorithm::from_name)
+        };
+        match commands::diff(target, context, algorithm) {
+            Ok(output) => io::stdout().write_all(&output).unwrap(),
+            Err(error) => fail(&error),
+        }
+    }
+}
+
+// Report a fatal error the way git does, and bail out
+fn fail(error: &str) -> ! {
+    eprintln!("fatal: {}", error);
+    process::exit(128);
+}
+
+// Show how a command is used and bail out, the way git does when given options that don't go
+// together
+fn fail_with_usage(usage: &str) -> ! {
+    eprintln!("{}", usage);
+    process::exit(129);
 }
@@ -0,0 +1,63 @@
+use crate::{
+    index::{Entry, Index},
+    objects::{self, Blob, Object},
+    workspace,
+};
+use std::path::PathBuf;
+
+/// Add file contents to the index
+pub fn run(paths: &[PathBuf]) -> Result<(), String> {
+    let mut index =
+        Index::load_for_update().map_err(|error| format!("Couldn't lock the index: {}", error))?;
+
+    // Work out every file involved before touching anything, so a bad pathspec changes nothing
+    let mut pathspecs = Vec::new();
+    for path in paths {
+        let pathspec = workspace::normalize(path).map_err(|error| error.to_string())?;
+        if !workspace::exists(&pathspec) && !is_tracked(&index, &pathspec) {
+            return Err(format!(
+                "pathspec '{}' did not match any files",
+                path.display()
+            ));
+        }
+        let files = match workspace::exists(&pathspec) {
+            true => workspace::list_files(&pathspec).map_err(|error| error.to_string())?,
+            false => vec![],
+        };
+        pathspecs.push((pathspec, files));
+    }
+
+    for (pathspec, files) in pathspecs {
+        // Anything tracked under the pathspec that's gone from the workspace is removed
+        let removed: Vec<String> = index
+            .entries()
+            .map(|entry| entry.path.to_owned())
+            .filter(|path| is_within(path, &pathspec) && !workspace::exists(path))
+            .collect();
+        for path in removed {
+            index.remove(&path);
+        }
+
+        for file in files {
+            let metadata = workspace::stat(&file).map_err(|error| error.to_string())?;
+            let blob = Blob::from_file(workspace::root().join(&file));
+            objects::write(&blob).map_err(|error| error.to_string())?;
+            index.add(Entry::new(&file, &blob.id(), &metadata));
+        }
+    }
+
+    index
+        .write_updates()
+        .map_err(|error| format!("Couldn't write the index: {}", error))
+}
+
+fn is_tracked(index: &Index, pathspec: &str) -> bool {
+    index
+        .entries()
+        .any(|entry| is_within(&entry.path, pathspec))
+}
+
+// Whether a path is the pathspec itself or somewhere inside it
+fn is_within(path: &str, pathspec: &str) -> bool {
+    pathspec.is_empty() || path == pathspec || path.starts_with(&format!("{}/", pathspec))
+}
@@ -0,0 +1,175 @@
+use crate::{history, refs, revision};
+use std::fmt;
+
+/// What to do with branches
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Show every branch, marking the current one
+    List,
+    /// Start a new branch at HEAD, or at another revision
+    Create {
+        name: String,
+        start_point: Option<String>,
+    },
+    /// Rename a branch, or the current branch when `old` isn't given (`-m`)
+    Rename { old: Option<String>, new: String },
+    /// Delete branches, refusing ones that aren't merged into HEAD unless forced (`-d`, `-D`)
+    Delete { names: Vec<String>, force: bool },
+}
+
+/// Why a branch command failed, as git reports it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Nothing was done (`fatal:`)
+    Fatal(String),
+    /// Some of the branches named couldn't be deleted: the lines for those that were, and why
+    /// each of the rest wasn't (`error:`)
+    NotDeleted { output: String, errors: Vec<String> },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Fatal(message) => write!(f, "{}", message),
+            Error::NotDeleted { errors, .. } => write!(f, "{}", errors.join("\n")),
+        }
+    }
+}
+
+/// List, create, rename or delete branches. Deleting carries on past branches it can't
+/// delete, like git.
+pub fn run(action: Action) -> Result<String, Error> {
+    match action {
+        Action::List => Ok(list()),
+        Action::Create { name, start_point } => {
+            create(&name, start_point.as_deref()).map_err(Error::Fatal)
+        }
+        Action::Rename { old, new } => rename(old, &new).map_err(Error::Fatal),
+        Action::Delete { names, force } => delete(&names, force),
+    }
+}
+
+fn list() -> String {
+    let current = refs::current_branch();
+    let mut output = String::new();
+
+    if current.is_none() {
+        if let Some(head) = refs::read_head() {
+            output.push_str(&format!(
+                "* (HEAD detached at {})\n",
+                revision::abbreviate(&head)
+            ));
+        }
+    }
+    for (name, _) in refs::list("refs/heads/") {
+        let name = branch_name(&name);
+        let marker = match Some(name) == current.as_deref() {
+            true => '*',
+            false => ' ',
+        };
+        output.push_str(&format!("{} {}\n", marker, name));
+    }
+    output
+}
+
+fn create(name: &str, start_point: Option<&str>) -> Result<String, String> {
+    if !refs::is_valid_name(name) {
+        return Err(format!("'{}' is not a valid branch name.", name));
+    }
+    let reference = format!("refs/heads/{}", name);
+    if refs::resolve(&reference).is_some() {
+        return Err(format!("A branch named '{}' already exists.", name));
+    }
+
+    let start_point = start_point.unwrap_or("HEAD");
+    let oid = revision::resolve_commit(start_point).map_err(|error| match error {
+        revision::Error::Unknown(_) => format!("Not a valid object name: '{}'.", start_point),
+        other => other.to_string(),
+    })?;
+
+    refs::update_ref(&reference, &oid).map_err(|error| error.to_string())?;
+    Ok(String::new())
+}
+
+fn rename(old: Option<String>, new: &str) -> Result<String, String> {
+    let old = match old.or_else(refs::current_branch) {
+        Some(old) => old,
+        None => {
+            return Err(String::from(
+                "cannot rename the current branch while not on any",
+            ))
+        }
+    };
+    let old_reference = format!("refs/heads/{}", old);
+    let new_reference = format!("refs/heads/{}", new);
+
+    if !refs::is_valid_name(new) {
+        return Err(format!("'{}' is not a valid branch name.", new));
+    }
+    if refs::resolve(&new_reference).is_some() {
+        return Err(format!("A branch named '{}' already exists.", new));
+    }
+
+    match refs::resolve(&old_reference) {
+        Some(oid) => {
+            refs::update_ref(&new_reference, &oid).map_err(|error| error.to_string())?;
+            refs::delete_ref(&old_reference).map_err(|error| error.to_string())?;
+        }
+        // Renaming a branch that has no commits yet only means pointing HEAD elsewhere
+        None if refs::current_branch().as_deref() == Some(old.as_str()) => {}
+        None => return Err(format!("no branch named '{}'", old)),
+    }
+
+    if refs::current_branch().as_deref() == Some(old.as_str()) {
+        refs::set_head_to_branch(new).map_err(|error| error.to_string())?;
+    }
+    Ok(String::new())
+}
+
+fn delete(names: &[String], force: bool) -> Result<String, Error> {
+    let mut output = String::new();
+    let mut errors = Vec::new();
+    for name in names {
+        match delete_one(name, force) {
+            Ok(oid) => output.push_str(&format!(
+                "Deleted branch {} (was {}).\n",
+                name,
+                revision::abbreviate(&oid)
+            )),
+            Err(error) => errors.push(error),
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(output),
+        false => Err(Error::NotDeleted { output, errors }),
+    }
+}
+
+// Delete one branch, giving back the commit it pointed at
+fn delete_one(name: &str, force: bool) -> Result<String, String> {
+    let reference = format!("refs/heads/{}", name);
+    let oid = refs::resolve(&reference).ok_or_else(|| format!("branch '{}' not found.", name))?;
+
+    if refs::current_branch().as_deref() == Some(name) {
+        return Err(format!("Cannot delete branch '{}' checked out", name));
+    }
+    if !force {
+        let merged = match refs::read_head() {
+            Some(head) => history::is_ancestor(&oid, &head).map_err(|e| e.to_string())?,
+            None => false,
+        };
+        if !merged {
+            return Err(format!(
+                "The branch '{}' is not fully merged.\nIf you are sure you want to delete it, run 'git branch -D {}'.",
+                name, name
+            ));
+        }
+    }
+
+    refs::delete_ref(&reference).map_err(|error| error.to_string())?;
+    Ok(oid)
+}
+
+fn branch_name(reference: &str) -> &str {
+    reference.trim_start_matches("refs/heads/")
+}
@@ -0,0 +1,52 @@
+use crate::{
+    objects::{self, Object, ParsedObject},
+    revision,
+};
+
+/// What to show about an object
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// The object's type (`-t`)
+    Type,
+    /// The object's size in bytes (`-s`)
+    Size,
+    /// The object's content, formatted for humans (`-p`)
+    Pretty,
+    /// Nothing at all, just whether the object exists (`-e`)
+    Exists,
+}
+
+/// Provide content, type or size information for an object in the database
+pub fn run(mode: Mode, name: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Not a valid object name {}", name);
+    let oid = revision::resolve(name).map_err(|error| match error {
+        revision::Error::Ambiguous { .. } => error.to_string(),
+        _ => invalid(),
+    })?;
+    let object = objects::read(&oid).map_err(|_| invalid())?;
+
+    let output = match mode {
+        Mode::Type => format!("{}\n", object.kind().name()).into_bytes(),
+        Mode::Size => format!("{}\n", object.content().len()).into_bytes(),
+        Mode::Exists => Vec::new(),
+        Mode::Pretty => match object {
+            ParsedObject::Tree(tree) => tree
+                .entries
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{:0>6} {} {}\t{}\n",
+                        entry.mode,
+                        entry.kind().name(),
+                        entry.oid,
+                        entry.name
+                    )
+                })
+                .collect::<String>()
+                .into_bytes(),
+            other => other.content().to_owned(),
+        },
+    };
+
+    Ok(output)
+}
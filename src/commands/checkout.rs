@@ -0,0 +1,121 @@
+use crate::{
+    index::Index,
+    migration::Migration,
+    objects::{self, Commit},
+    refs, revision,
+};
+use std::collections::BTreeMap;
+
+/// Switch branches, or detach HEAD at any other revision, updating the workspace and index
+pub fn run(target: &str, new_branch: Option<&str>) -> Result<String, String> {
+    match new_branch {
+        Some(name) => create_and_switch(name, target),
+        None if is_branch(target) => switch_to(target, Some(target)),
+        None => switch_to(target, None),
+    }
+}
+
+/// Switch branches the way `git switch` does, which only detaches HEAD when asked to
+pub fn switch(target: &str, new_branch: Option<&str>, detach: bool) -> Result<String, String> {
+    match new_branch {
+        Some(name) => create_and_switch(name, target),
+        None if detach => switch_to(target, None),
+        None if is_branch(target) => switch_to(target, Some(target)),
+        None => Err(format!("a branch is expected, got '{}'", target)),
+    }
+}
+
+fn create_and_switch(name: &str, start_point: &str) -> Result<String, String> {
+    if !refs::is_valid_name(name) {
+        return Err(format!("'{}' is not a valid branch name.", name));
+    }
+    let reference = format!("refs/heads/{}", name);
+    if refs::resolve(&reference).is_some() {
+        return Err(format!("A branch named '{}' already exists.", name));
+    }
+
+    // Without any commits yet, the new branch is as unborn as the current one
+    if start_point == "HEAD" && refs::read_head().is_none() {
+        refs::set_head_to_branch(name).map_err(|error| error.to_string())?;
+        return Ok(format!("Switched to a new branch '{}'\n", name));
+    }
+    let oid = resolve_commit(start_point)?;
+
+    migrate_to(&oid)?;
+    refs::update_ref(&reference, &oid).map_err(|error| error.to_string())?;
+    refs::set_head_to_branch(name).map_err(|error| error.to_string())?;
+    Ok(format!("Switched to a new branch '{}'\n", name))
+}
+
+// Move to a revision, leaving HEAD pointing at a branch or detached at the commit
+fn switch_to(target: &str, branch: Option<&str>) -> Result<String, String> {
+    let oid = resolve_commit(target)?;
+    let previous_head = refs::read_head();
+    let was_detached = refs::current_branch().is_none();
+
+    if branch.is_some() && branch.map(String::from) == refs::current_branch() {
+        return Ok(format!("Already on '{}'\n", target));
+    }
+
+    migrate_to(&oid)?;
+
+    let mut output = String::new();
+    if let Some(previous) = previous_head.filter(|previous| was_detached && *previous != oid) {
+        output.push_str(&format!(
+            "Previous HEAD position was {}\n",
+            describe(&previous)?
+        ));
+    }
+    match branch {
+        Some(branch) => {
+            refs::set_head_to_branch(branch).map_err(|error| error.to_string())?;
+            output.push_str(&format!("Switched to branch '{}'\n", branch));
+        }
+        None => {
+            refs::update_ref("HEAD", &oid).map_err(|error| error.to_string())?;
+            output.push_str(&format!("HEAD is now at {}\n", describe(&oid)?));
+        }
+    }
+    Ok(output)
+}
+
+// Bring the workspace and index from HEAD's tree to the tree of another commit
+fn migrate_to(oid: &str) -> Result<(), String> {
+    let mut index = Index::load_for_update().map_err(|error| error.to_string())?;
+
+    let current_tree = match refs::read_head() {
+        Some(head) => flatten_commit(&head)?,
+        None => BTreeMap::new(),
+    };
+    let target_tree = flatten_commit(oid)?;
+
+    Migration::new(&mut index, &current_tree, &target_tree).apply("checkout")?;
+    index.write_updates().map_err(|error| error.to_string())
+}
+
+fn flatten_commit(oid: &str) -> Result<BTreeMap<String, objects::Entry>, String> {
+    let commit = read_commit(oid)?;
+    objects::flatten_tree(&commit.tree).map_err(|error| error.to_string())
+}
+
+fn resolve_commit(target: &str) -> Result<String, String> {
+    revision::resolve_commit(target).map_err(|error| match error {
+        revision::Error::Unknown(_) => format!("invalid reference: {}", target),
+        other => other.to_string(),
+    })
+}
+
+fn read_commit(oid: &str) -> Result<Commit, String> {
+    objects::read_commit(oid).map_err(|error| error.to_string())
+}
+
+fn is_branch(name: &str) -> bool {
+    refs::is_valid_name(name) && refs::resolve(&format!("refs/heads/{}", name)).is_some()
+}
+
+// A commit's abbreviated ID and subject, like `1a2b3c4 Fix the thing`
+fn describe(oid: &str) -> Result<String, String> {
+    let commit = read_commit(oid)?;
+    let subject = commit.message.lines().next().unwrap_or("");
+    Ok(format!("{} {}", revision::abbreviate(oid), subject))
+}
@@ -1,40 +1,58 @@
 use crate::{
-    ignore::Ignore,
-    objects::{self, Blob, Commit, Object, Tree},
+    index::Index,
+    merge,
+    objects::{self, Commit, Entry, Object, Tree},
     refs,
 };
-use std::{env, fs, path::PathBuf};
 
-/// Record changes to the repository
+/// Record changes to the repository, concluding a merge if one is waiting to be committed
 pub fn run(message: &str) -> Result<String, String> {
-    let current_dir = env::current_dir().expect("Couldn't determine current directory");
-    let ignore: Ignore = Default::default();
-    let files_to_commit = list_files(&current_dir, &ignore);
+    let index = Index::load().map_err(|error| format!("Couldn't read the index: {}", error))?;
+    if index.entries().any(|entry| entry.stage() != 0) {
+        return Err(String::from(
+            "Committing is not possible because you have unmerged files.",
+        ));
+    }
 
-    let blobs: Vec<Blob> = files_to_commit
-        .iter()
-        .map(|file| {
-            let blob = Blob::from_file(file);
-            objects::write(&blob).expect("Couldn't write blob to git database");
-            blob
+    let entries: Vec<Entry> = index
+        .entries()
+        .map(|entry| Entry {
+            mode: entry.tree_mode(),
+            name: entry.path.to_owned(),
+            oid: entry.oid.to_owned(),
         })
         .collect();
 
-    let tree = Tree::from_blobs(blobs);
-    objects::write(&tree).expect("Couldn't write tree to git database");
+    let tree = Tree::build(entries);
+    objects::write_tree(&tree).expect("Couldn't write tree to git database");
 
-    let commit = Commit::new(tree.id(), message);
+    let merge_head = merge::pending();
+    let parents = refs::read_head()
+        .into_iter()
+        .chain(merge_head.clone())
+        .collect();
+    let message = match (message.is_empty(), merge::pending_message()) {
+        (true, Some(merge_message)) => strip_comments(&merge_message),
+        _ => message.to_string(),
+    };
+    let commit = Commit::new(tree.id(), parents, &message).map_err(|error| error.to_string())?;
     objects::write(&commit).expect("Couldn't write the commit to git database");
 
     refs::update_head(&commit.id());
+    if merge_head.is_some() {
+        merge::conclude().map_err(|error| error.to_string())?;
+    }
 
     Ok(commit.id())
 }
 
-fn list_files(workspace: &PathBuf, ignore: &Ignore) -> Vec<PathBuf> {
-    let workspace_files = fs::read_dir(workspace)
-        .expect("Could not read files in the workspace")
-        .map(|dir_entry| dir_entry.expect("Can't get dir_entry").path())
-        .collect();
-    ignore.ignore_items(workspace_files)
+// Drop the `#` lines git leaves in prepared messages as hints, and any blank lines at the end
+fn strip_comments(message: &str) -> String {
+    message
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
 }
@@ -0,0 +1,241 @@
+use crate::config::{self, Edit, KeyError, Origin, Scope, Setting};
+use std::io;
+
+/// What to do with config
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Show the value that wins for a key, the last one read (`--get`)
+    Get { key: String },
+    /// Show every value for a key, in the order they're read (`--get-all`)
+    GetAll { key: String },
+    /// Set a key, replacing its one value if it has one
+    Set { key: String, value: String },
+    /// Remove a key that has one value (`--unset`)
+    Unset { key: String },
+    /// Show every setting (`--list`)
+    List,
+}
+
+/// How to read or write values (`--type`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    /// `true` or `false`, however the value says it
+    Bool,
+    /// A number, scaled by any `k`, `m` or `g` suffix
+    Int,
+    /// A path, with a leading `~` expanded to the home directory
+    Path,
+}
+
+/// Where and how config is read and written
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Options {
+    /// Only this scope's file, rather than every file for reading and the repository's for
+    /// writing
+    pub scope: Option<Scope>,
+    pub kind: Option<Type>,
+    /// Say which file each value came from (`--show-origin`)
+    pub show_origin: bool,
+}
+
+/// Why config couldn't be read or written, as git reports it: a message for stderr (which may
+/// be empty) and the exit status
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub message: String,
+    pub status: i32,
+}
+
+impl Error {
+    fn fatal(message: String) -> Error {
+        Error {
+            message: format!("fatal: {}", message),
+            status: 128,
+        }
+    }
+
+    fn quiet(status: i32) -> Error {
+        Error {
+            message: String::new(),
+            status,
+        }
+    }
+}
+
+impl Type {
+    /// The type for a `--type` argument
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "bool" => Some(Type::Bool),
+            "int" => Some(Type::Int),
+            "path" => Some(Type::Path),
+            _ => None,
+        }
+    }
+}
+
+/// Get, set, unset or list config. Writes edit the file in place, so everything else in it
+/// stays exactly as it was.
+pub fn run(action: Action, options: Options) -> Result<String, Error> {
+    match action {
+        Action::Get { key } => get(&key, options, false),
+        Action::GetAll { key } => get(&key, options, true),
+        Action::Set { key, value } => set(&key, Some(&value), options),
+        Action::Unset { key } => set(&key, None, options),
+        Action::List => list(options),
+    }
+}
+
+fn get(key: &str, options: Options, all: bool) -> Result<String, Error> {
+    let key = config::parse_key(key).map_err(|error| key_error(error, false))?;
+    let settings = match config::settings(options.scope) {
+        Ok(settings) => settings,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Err(Error::quiet(1)),
+        Err(error) => return Err(Error::fatal(error.to_string())),
+    };
+
+    let mut matching: Vec<&Setting> = settings.iter().filter(|s| s.key == key).collect();
+    if !all && matching.len() > 1 {
+        matching.drain(..matching.len() - 1);
+    }
+    if matching.is_empty() {
+        return Err(Error::quiet(1));
+    }
+
+    let mut output = String::new();
+    for setting in matching {
+        if options.show_origin {
+            output.push_str(&format!("{}\t", setting.origin));
+        }
+        output.push_str(&format!("{}\n", typed_value(setting, options.kind)?));
+    }
+    Ok(output)
+}
+
+fn set(key: &str, value: Option<&str>, options: Options) -> Result<String, Error> {
+    config::parse_key(key).map_err(|error| key_error(error, true))?;
+    let value = match (value, options.kind) {
+        (Some(value), Some(kind)) => Some(normalize(key, value, kind)?),
+        (value, _) => value.map(String::from),
+    };
+
+    let path = options
+        .scope
+        .unwrap_or(Scope::Local)
+        .path()
+        .map_err(|error| Error::fatal(error.to_string()))?;
+    let edit = config::write(&path, key, value.as_deref())
+        .map_err(|error| Error::fatal(error.to_string()))?;
+    match edit {
+        Edit::Written => Ok(String::new()),
+        Edit::Missing => Err(Error::quiet(5)),
+        Edit::MultipleValues => {
+            let mut message = format!(
+                "warning: {} has multiple values",
+                config::parse_key(key).unwrap_or_default()
+            );
+            if value.is_some() {
+                message.push_str(&format!(
+                    "\nerror: cannot overwrite multiple values with a single value\n       \
+                     Use a regexp, --add or --replace-all to change {}.",
+                    key
+                ));
+            }
+            Err(Error { message, status: 5 })
+        }
+    }
+}
+
+fn list(options: Options) -> Result<String, Error> {
+    let settings = config::settings(options.scope).map_err(|error| {
+        match options.scope.map(|scope| scope.path()) {
+            Some(Ok(path)) if error.kind() == io::ErrorKind::NotFound => Error::fatal(format!(
+                "unable to read config file '{}': No such file or directory",
+                path.display()
+            )),
+            _ => Error::fatal(error.to_string()),
+        }
+    })?;
+
+    let mut output = String::new();
+    for setting in &settings {
+        if options.show_origin {
+            output.push_str(&format!("{}\t", setting.origin));
+        }
+        match &setting.value {
+            Some(value) => output.push_str(&format!("{}={}\n", setting.key, value)),
+            None => output.push_str(&format!("{}\n", setting.key)),
+        }
+    }
+    Ok(output)
+}
+
+// Git exits with 2 when a key it's asked to write is missing a part, but 1 otherwise
+fn key_error(error: KeyError, writing: bool) -> Error {
+    let status = match error {
+        KeyError::NoSection(_) | KeyError::NoName(_) if writing => 2,
+        _ => 1,
+    };
+    Error {
+        message: format!("error: {}", error),
+        status,
+    }
+}
+
+// A value the way `--type` says to show it, where a bare key is true but has no value as such
+fn typed_value(setting: &Setting, kind: Option<Type>) -> Result<String, Error> {
+    let value = setting.value.as_deref();
+    match kind {
+        None => Ok(value.unwrap_or_default().to_string()),
+        Some(Type::Bool) => bool_value(&setting.key, value),
+        Some(Type::Int) => {
+            let text = value.unwrap_or_default();
+            let within = match &setting.origin {
+                Origin::File(path) => format!(" in file {}", path.display()),
+                Origin::CommandLine => String::new(),
+            };
+            config::parse_int(text)
+                .map(|n| n.to_string())
+                .map_err(|reason| {
+                    Error::fatal(format!(
+                        "bad numeric config value '{}' for '{}'{}: {}",
+                        text, setting.key, within, reason
+                    ))
+                })
+        }
+        Some(Type::Path) => match value {
+            Some(value) => Ok(config::expand_home(value).display().to_string()),
+            None => Err(Error {
+                message: format!("error: missing value for '{}'", setting.key),
+                status: 128,
+            }),
+        },
+    }
+}
+
+// A value as it's written with `--type`, which is how git would show it, except for paths
+fn normalize(key: &str, value: &str, kind: Type) -> Result<String, Error> {
+    match kind {
+        Type::Bool => bool_value(key, Some(value)),
+        Type::Int => config::parse_int(value)
+            .map(|number| number.to_string())
+            .map_err(|reason| {
+                Error::fatal(format!(
+                    "bad numeric config value '{}' for '{}': {}",
+                    value, key, reason
+                ))
+            }),
+        Type::Path => Ok(value.to_string()),
+    }
+}
+
+fn bool_value(key: &str, value: Option<&str>) -> Result<String, Error> {
+    match config::parse_bool(value) {
+        Some(boolean) => Ok(boolean.to_string()),
+        None => Err(Error::fatal(format!(
+            "bad boolean config value '{}' for '{}'",
+            value.unwrap_or_default(),
+            key
+        ))),
+    }
+}
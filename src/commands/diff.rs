@@ -0,0 +1,222 @@
+use crate::{
+    config::Config,
+    diff::{self, Algorithm},
+    index::Index,
+    objects::{self, Blob, Entry, Object, ParsedObject},
+    revision,
+    status::{Change, Status},
+    workspace,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Which two versions of the repository to compare
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// The index against the workspace
+    Workspace,
+    /// HEAD against the index (`--cached`)
+    Cached,
+    /// One revision's tree against another's
+    Revisions(String, String),
+}
+
+/// Show changes between two versions of the repository as a git-style patch, with
+/// `context` unchanged lines around each hunk. Without an algorithm, `diff.algorithm`
+/// from the config picks one.
+pub fn run(
+    target: Target,
+    context: usize,
+    algorithm: Option<Algorithm>,
+) -> Result<Vec<u8>, String> {
+    let algorithm = match algorithm {
+        Some(algorithm) => algorithm,
+        None => configured_algorithm()?,
+    };
+    let pairs = match target {
+        Target::Workspace => workspace_pairs()?,
+        Target::Cached => cached_pairs()?,
+        Target::Revisions(old, new) => revision_pairs(&old, &new)?,
+    };
+
+    let mut output = Vec::new();
+    for (path, (old, new)) in pairs {
+        output.extend(file_diff(
+            &path,
+            old.as_ref(),
+            new.as_ref(),
+            context,
+            algorithm,
+        ));
+    }
+    Ok(output)
+}
+
+fn configured_algorithm() -> Result<Algorithm, String> {
+    let name = Config::build()
+        .map_err(|error| error.to_string())?
+        .diff
+        .algorithm;
+    match name.as_str() {
+        "" => Ok(Algorithm::default()),
+        name => Algorithm::from_name(name)
+            .ok_or_else(|| format!("unknown value for config 'diff.algorithm': {}", name)),
+    }
+}
+
+// A file's content and how it's stored, on one side of a comparison
+struct Version {
+    oid: String,
+    mode: String,
+    content: Vec<u8>,
+}
+
+type Pairs = BTreeMap<String, (Option<Version>, Option<Version>)>;
+
+impl Version {
+    fn from_database(oid: &str, mode: &str) -> Result<Version, String> {
+        let content = match objects::read(oid).map_err(|error| error.to_string())? {
+            ParsedObject::Blob(blob) => blob.content,
+            other => other.content().to_owned(),
+        };
+        Ok(Version {
+            oid: oid.to_string(),
+            mode: mode.to_string(),
+            content,
+        })
+    }
+
+    fn from_entry(entry: &Entry) -> Result<Version, String> {
+        Version::from_database(&entry.oid, &entry.mode)
+    }
+
+    fn from_index(index: &Index, path: &str) -> Result<Version, String> {
+        let entry = index
+            .entry(path)
+            .ok_or_else(|| format!("'{}' isn't in the index", path))?;
+        Version::from_database(&entry.oid, &entry.tree_mode())
+    }
+
+    fn from_workspace(path: &str) -> Result<Version, String> {
+        let metadata = workspace::stat(path).map_err(|error| error.to_string())?;
+        let blob = Blob::from_file(workspace::root().join(path));
+        Ok(Version {
+            oid: blob.id(),
+            mode: objects::mode_for_metadata(&metadata).to_string(),
+            content: blob.content,
+        })
+    }
+}
+
+fn workspace_pairs() -> Result<Pairs, String> {
+    let status = Status::scan().map_err(|error| error.to_string())?;
+    let index = Index::load().map_err(|error| error.to_string())?;
+
+    let mut pairs = BTreeMap::new();
+    for (path, change) in status.workspace_changes {
+        let old = Some(Version::from_index(&index, &path)?);
+        let new = match change {
+            Change::Deleted => None,
+            _ => Some(Version::from_workspace(&path)?),
+        };
+        pairs.insert(path, (old, new));
+    }
+    Ok(pairs)
+}
+
+fn cached_pairs() -> Result<Pairs, String> {
+    let status = Status::scan().map_err(|error| error.to_string())?;
+    let index = Index::load().map_err(|error| error.to_string())?;
+
+    let mut pairs = BTreeMap::new();
+    for (path, change) in status.index_changes {
+        let old = match change {
+            Change::Added => None,
+            _ => Some(Version::from_entry(&status.head_tree[&path])?),
+        };
+        let new = match change {
+            Change::Deleted => None,
+            _ => Some(Version::from_index(&index, &path)?),
+        };
+        pairs.insert(path, (old, new));
+    }
+    Ok(pairs)
+}
+
+fn revision_pairs(old: &str, new: &str) -> Result<Pairs, String> {
+    let flatten = |revision: &str| {
+        let tree = revision::resolve_tree(revision).map_err(|error| error.to_string())?;
+        objects::flatten_tree(&tree).map_err(|error| error.to_string())
+    };
+    let (old, new) = (flatten(old)?, flatten(new)?);
+
+    let mut pairs = BTreeMap::new();
+    let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    for path in paths {
+        match (old.get(path), new.get(path)) {
+            (Some(old), Some(new)) if old == new => {}
+            (old, new) => {
+                let old = old.map(Version::from_entry).transpose()?;
+                let new = new.map(Version::from_entry).transpose()?;
+                pairs.insert(path.to_owned(), (old, new));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+// The patch for one file: a `diff --git` header describing what happened to it, then
+// its hunks (or a note that it's binary)
+fn file_diff(
+    path: &str,
+    old: Option<&Version>,
+    new: Option<&Version>,
+    context: usize,
+    algorithm: Algorithm,
+) -> Vec<u8> {
+    let null_oid = "0".repeat(7);
+    let short = |version: Option<&Version>| match version {
+        Some(version) => version.oid[..7].to_string(),
+        None => null_oid.to_owned(),
+    };
+
+    let mut header = format!("diff --git a/{0} b/{0}\n", path);
+    match (old, new) {
+        (None, Some(new)) => header.push_str(&format!("new file mode {}\n", new.mode)),
+        (Some(old), None) => header.push_str(&format!("deleted file mode {}\n", old.mode)),
+        (Some(old), Some(new)) if old.mode != new.mode => {
+            header.push_str(&format!("old mode {}\nnew mode {}\n", old.mode, new.mode))
+        }
+        _ => {}
+    }
+    if old.map(|old| &old.oid) != new.map(|new| &new.oid) {
+        header.push_str(&format!("index {}..{}", short(old), short(new)));
+        if let (Some(old), Some(new)) = (old, new) {
+            if old.mode == new.mode {
+                header.push_str(&format!(" {}", old.mode));
+            }
+        }
+        header.push('\n');
+    }
+
+    let old_name = old.map_or(String::from("/dev/null"), |_| format!("a/{}", path));
+    let new_name = new.map_or(String::from("/dev/null"), |_| format!("b/{}", path));
+    let old_content = old.map_or(&[][..], |old| &old.content);
+    let new_content = new.map_or(&[][..], |new| &new.content);
+
+    let mut output = header.into_bytes();
+    if old.map(|old| &old.oid) == new.map(|new| &new.oid) {
+        return output;
+    }
+    if diff::is_binary(old_content) || diff::is_binary(new_content) {
+        output.extend_from_slice(
+            format!("Binary files {} and {} differ\n", old_name, new_name).as_bytes(),
+        );
+        return output;
+    }
+    let hunks = diff::unified(old_content, new_content, context, algorithm);
+    if !hunks.is_empty() {
+        output.extend_from_slice(format!("--- {}\n+++ {}\n", old_name, new_name).as_bytes());
+        output.extend(hunks);
+    }
+    output
+}
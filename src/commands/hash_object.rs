@@ -0,0 +1,28 @@
+use crate::objects::{self, Blob, Object};
+use std::path::PathBuf;
+
+/// Compute the ID of blobs built from files and/or stdin, optionally writing them to the database
+pub fn run(files: &[PathBuf], stdin: Option<&[u8]>, write: bool) -> Result<String, String> {
+    let mut blobs: Vec<Blob> = Vec::new();
+    if let Some(stdin) = stdin {
+        blobs.push(Blob::from_bytes(stdin.to_owned()));
+    }
+    for file in files {
+        if !file.is_file() {
+            return Err(format!(
+                "could not open '{}' for reading: No such file",
+                file.display()
+            ));
+        }
+        blobs.push(Blob::from_file(file));
+    }
+
+    let mut output = String::new();
+    for blob in blobs {
+        if write {
+            objects::write(&blob).map_err(|error| format!("Couldn't write object: {}", error))?;
+        }
+        output.push_str(&format!("{}\n", blob.id()));
+    }
+    Ok(output)
+}
@@ -1,3 +1,4 @@
+use crate::refs::DEFAULT_BRANCH;
 use std::{env, fs, path::PathBuf};
 
 /// Create an empty Git repository
@@ -5,10 +6,23 @@ pub fn run(directory: Option<&PathBuf>) {
     let current_dir = env::current_dir().expect("Couldn't identify current working directory");
     let directory = directory.unwrap_or(&current_dir);
 
-    let required_git_directories = vec![".git", ".git/objects", ".git/refs"];
+    let required_git_directories = vec![
+        ".git",
+        ".git/objects",
+        ".git/refs",
+        ".git/refs/heads",
+        ".git/refs/tags",
+    ];
     for required_git_directory in required_git_directories {
         let required_git_directory = directory.join(required_git_directory);
         fs::create_dir_all(required_git_directory).expect("Could not create required directory");
     }
+
+    // Reinitializing keeps whatever HEAD already points at
+    let head = directory.join(".git").join("HEAD");
+    if !head.exists() {
+        fs::write(head, format!("ref: refs/heads/{}\n", DEFAULT_BRANCH))
+            .expect("Could not create HEAD");
+    }
     println!("Initialized the git repo at {:?}", &directory);
 }
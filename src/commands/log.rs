@@ -0,0 +1,120 @@
+use crate::{history::RevList, objects::Commit, refs, revision};
+
+/// How to show each commit
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    /// Git's default: the full ID, author, date and indented message
+    Medium,
+    /// The abbreviated ID and subject on one line
+    Oneline,
+    /// A template using `%H %h %an %ae %ad %s` (plus `%n` and `%%`), one line per commit
+    Custom(String),
+}
+
+/// Show commits reachable from the given revisions (HEAD if there are none)
+pub fn run(
+    revisions: &[String],
+    format: Format,
+    max_count: Option<usize>,
+) -> Result<String, String> {
+    let starts = match revisions.len() {
+        0 => vec![
+            refs::read_head().ok_or_else(|| match refs::current_branch() {
+                Some(branch) => format!(
+                    "your current branch '{}' does not have any commits yet",
+                    branch
+                ),
+                None => String::from("HEAD doesn't point at a commit"),
+            })?,
+        ],
+        _ => revisions
+            .iter()
+            .map(|revision| revision::resolve_commit(revision).map_err(|error| error.to_string()))
+            .collect::<Result<Vec<String>, String>>()?,
+    };
+
+    let rev_list = RevList::new(&starts).map_err(|error| error.to_string())?;
+    let mut output = String::new();
+    for (shown, walked) in rev_list.take(max_count.unwrap_or(usize::MAX)).enumerate() {
+        let (oid, commit) = walked.map_err(|error| error.to_string())?;
+        match &format {
+            Format::Medium => {
+                if shown > 0 {
+                    output.push('\n');
+                }
+                output.push_str(&medium(&oid, &commit));
+            }
+            Format::Oneline => output.push_str(&format!(
+                "{} {}\n",
+                revision::abbreviate(&oid),
+                commit.subject()
+            )),
+            Format::Custom(template) => {
+                output.push_str(&expand(template, &oid, &commit));
+                output.push('\n');
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn medium(oid: &str, commit: &Commit) -> String {
+    let mut shown = format!("commit {}\n", oid);
+    if commit.parents.len() > 1 {
+        let parents: Vec<String> = commit
+            .parents
+            .iter()
+            .map(|parent| revision::abbreviate(parent))
+            .collect();
+        shown.push_str(&format!("Merge: {}\n", parents.join(" ")));
+    }
+    shown.push_str(&format!(
+        "Author: {} <{}>\nDate:   {}\n\n",
+        commit.author.name,
+        commit.author.email,
+        commit.author.format_date()
+    ));
+    for line in commit.message.lines() {
+        shown.push_str(&format!("    {}\n", line));
+    }
+    shown
+}
+
+// Fill in a `--format` template's placeholders, leaving unknown ones as they are
+fn expand(template: &str, oid: &str, commit: &Commit) -> String {
+    let mut expanded = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        let mut placeholder = String::new();
+        if let Some(&next) = chars.peek() {
+            placeholder.push(next);
+            chars.next();
+            if next == 'a' {
+                if let Some(&next) = chars.peek() {
+                    placeholder.push(next);
+                    chars.next();
+                }
+            }
+        }
+        match placeholder.as_str() {
+            "H" => expanded.push_str(oid),
+            "h" => expanded.push_str(&revision::abbreviate(oid)),
+            "an" => expanded.push_str(&commit.author.name),
+            "ae" => expanded.push_str(&commit.author.email),
+            "ad" => expanded.push_str(&commit.author.format_date()),
+            "s" => expanded.push_str(&commit.subject()),
+            "n" => expanded.push('\n'),
+            "%" => expanded.push('%'),
+            other => {
+                expanded.push('%');
+                expanded.push_str(other);
+            }
+        }
+    }
+    expanded
+}
@@ -0,0 +1,186 @@
+use crate::{
+    history,
+    index::{self, Index},
+    merge::{self, TreeMerge},
+    migration::Migration,
+    objects::{self, Commit, Entry, Object, Tree},
+    refs, revision,
+};
+use std::collections::BTreeMap;
+
+/// What a merge did, and whether it finished or stopped for conflicts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// What git would print about the merge
+    pub output: String,
+    /// False when conflicts were left in the workspace and index to be resolved
+    pub clean: bool,
+}
+
+/// Join another line of history into the current branch: fast-forwarding when HEAD is an
+/// ancestor of it, otherwise merging their changes since the merge base with ours and
+/// committing the result, unless there are conflicts to resolve first. When history
+/// criss-crosses, the newest merge base is used rather than a merge of all of them.
+pub fn run(revision: &str, message: Option<&str>) -> Result<Outcome, String> {
+    if merge::pending().is_some() {
+        return Err(String::from(
+            "You have not concluded your merge (MERGE_HEAD exists).",
+        ));
+    }
+    let mut index = Index::load_for_update().map_err(|error| error.to_string())?;
+    if index.entries().any(|entry| entry.stage() != 0) {
+        return Err(String::from(
+            "Merging is not possible because you have unmerged files.",
+        ));
+    }
+
+    let theirs = revision::resolve_commit(revision).map_err(|error| match error {
+        revision::Error::Unknown(_) => format!("{} - not something we can merge", revision),
+        other => other.to_string(),
+    })?;
+    let ours = match refs::read_head() {
+        Some(ours) => ours,
+        None => {
+            migrate(&mut index, &BTreeMap::new(), &flatten_commit(&theirs)?)?;
+            index.write_updates().map_err(|error| error.to_string())?;
+            refs::update_head(&theirs);
+            return Ok(clean(String::new()));
+        }
+    };
+
+    let bases = history::merge_bases(&ours, &theirs).map_err(|error| error.to_string())?;
+    if bases.contains(&theirs) {
+        return Ok(clean(String::from("Already up to date.\n")));
+    }
+    if bases.contains(&ours) {
+        return fast_forward(index, &ours, &theirs);
+    }
+    let base = bases
+        .first()
+        .ok_or_else(|| String::from("refusing to merge unrelated histories"))?;
+
+    let (base_tree, ours_tree) = (flatten_commit(base)?, flatten_commit(&ours)?);
+    let tree_merge = merge::merge_trees(
+        &base_tree,
+        &ours_tree,
+        &flatten_commit(&theirs)?,
+        ("HEAD", revision),
+    )
+    .map_err(|error| error.to_string())?;
+
+    migrate(&mut index, &ours_tree, &tree_merge.result)?;
+    for (path, sides) in &tree_merge.conflicts {
+        index.add_conflict(path, conflict_stages(path, sides));
+    }
+    index.write_updates().map_err(|error| error.to_string())?;
+    refs::update_ref("ORIG_HEAD", &ours).map_err(|error| error.to_string())?;
+
+    let message = message.map_or_else(|| default_message(revision), String::from);
+    let mut output: String = tree_merge
+        .messages
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+    if tree_merge.is_clean() {
+        commit_merge(&tree_merge, vec![ours, theirs], &message)?;
+        output.push_str("Merge made by the 'ort' strategy.\n");
+        return Ok(clean(output));
+    }
+
+    merge::begin(&theirs, &conflicted_message(&message, &tree_merge))
+        .map_err(|error| error.to_string())?;
+    output.push_str("Automatic merge failed; fix conflicts and then commit the result.\n");
+    Ok(Outcome {
+        output,
+        clean: false,
+    })
+}
+
+fn clean(output: String) -> Outcome {
+    Outcome {
+        output,
+        clean: true,
+    }
+}
+
+// Move the branch forward to their commit, bringing the workspace and index along
+fn fast_forward(mut index: Index, ours: &str, theirs: &str) -> Result<Outcome, String> {
+    migrate(&mut index, &flatten_commit(ours)?, &flatten_commit(theirs)?)?;
+    index.write_updates().map_err(|error| error.to_string())?;
+    refs::update_ref("ORIG_HEAD", ours).map_err(|error| error.to_string())?;
+    refs::update_head(theirs);
+
+    Ok(clean(format!(
+        "Updating {}..{}\nFast-forward\n",
+        revision::abbreviate(ours),
+        revision::abbreviate(theirs)
+    )))
+}
+
+fn migrate(
+    index: &mut Index,
+    old: &BTreeMap<String, Entry>,
+    new: &BTreeMap<String, Entry>,
+) -> Result<(), String> {
+    Migration::new(index, old, new).apply("merge")
+}
+
+// Index entries for the base, ours and theirs (stages 1 to 3), skipping any side without the path
+fn conflict_stages(path: &str, sides: &[Option<Entry>; 3]) -> Vec<index::Entry> {
+    sides
+        .iter()
+        .zip(1..)
+        .filter_map(|(side, stage)| {
+            side.as_ref()
+                .map(|side| index::Entry::conflicted(path, &side.oid, &side.mode, stage))
+        })
+        .collect()
+}
+
+fn commit_merge(tree_merge: &TreeMerge, parents: Vec<String>, message: &str) -> Result<(), String> {
+    let entries = tree_merge
+        .result
+        .iter()
+        .map(|(path, entry)| Entry {
+            name: path.to_owned(),
+            ..entry.clone()
+        })
+        .collect();
+    let tree = Tree::build(entries);
+    objects::write_tree(&tree).map_err(|error| error.to_string())?;
+
+    let commit = Commit::new(tree.id(), parents, message).map_err(|error| error.to_string())?;
+    objects::write(&commit).map_err(|error| error.to_string())?;
+    refs::update_head(&commit.id());
+    Ok(())
+}
+
+// How git titles a merge, like `Merge branch 'topic'`, naming the branch merged into
+// unless it's the usual main branch
+fn default_message(revision: &str) -> String {
+    let is_branch = refs::is_valid_name(revision)
+        && refs::resolve(&format!("refs/heads/{}", revision)).is_some();
+    let merged = match is_branch {
+        true => format!("Merge branch '{}'", revision),
+        false => format!("Merge commit '{}'", revision),
+    };
+    match refs::current_branch() {
+        Some(branch) if branch == "master" || branch == "main" => merged,
+        Some(branch) => format!("{} into {}", merged, branch),
+        None => format!("{} into HEAD", merged),
+    }
+}
+
+// The message to commit once conflicts are resolved, listing them in comments like git does
+fn conflicted_message(message: &str, tree_merge: &TreeMerge) -> String {
+    let mut prepared = format!("{}\n\n# Conflicts:\n", message);
+    for path in tree_merge.conflicts.keys() {
+        prepared.push_str(&format!("#\t{}\n", path));
+    }
+    prepared
+}
+
+fn flatten_commit(oid: &str) -> Result<BTreeMap<String, Entry>, String> {
+    let commit = objects::read_commit(oid).map_err(|error| error.to_string())?;
+    objects::flatten_tree(&commit.tree).map_err(|error| error.to_string())
+}
@@ -0,0 +1,52 @@
+use crate::{history, revision};
+
+/// Which common ancestors to look for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Between the first commit and a merge of all the others
+    Default,
+    /// Between all of the commits at once (`--octopus`)
+    Octopus,
+}
+
+/// Find the best common ancestors of some commits, showing only the newest unless `all` is
+/// set. Nothing is shown when there isn't one.
+pub fn run(revisions: &[String], mode: Mode, all: bool) -> Result<String, String> {
+    let commits = resolve_all(revisions)?;
+    let bases = match mode {
+        Mode::Default => match commits.split_first() {
+            Some((one, others)) if !others.is_empty() => history::merge_bases_many(one, others),
+            _ => return Err(String::from("merge-base needs at least two commits")),
+        },
+        Mode::Octopus => history::octopus_merge_bases(&commits),
+    }
+    .map_err(|error| error.to_string())?;
+
+    let shown = match all {
+        true => bases.len(),
+        false => 1,
+    };
+    Ok(bases
+        .iter()
+        .take(shown)
+        .map(|oid| format!("{}\n", oid))
+        .collect())
+}
+
+/// Whether the first revision is an ancestor of the second (`--is-ancestor`)
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> Result<bool, String> {
+    let commits = resolve_all(&[ancestor.to_string(), descendant.to_string()])?;
+    history::is_ancestor(&commits[0], &commits[1]).map_err(|error| error.to_string())
+}
+
+fn resolve_all(revisions: &[String]) -> Result<Vec<String>, String> {
+    revisions
+        .iter()
+        .map(|name| {
+            revision::resolve_commit(name).map_err(|error| match error {
+                revision::Error::Unknown(_) => format!("Not a valid object name {}", name),
+                other => other.to_string(),
+            })
+        })
+        .collect()
+}
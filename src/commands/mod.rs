@@ -1,5 +1,38 @@
+mod add;
+mod branch;
+mod cat_file;
+mod checkout;
 mod commit;
+mod config;
+mod diff;
+mod hash_object;
 mod init;
+mod log;
+mod merge;
+mod merge_base;
+mod pack_refs;
+mod rev_parse;
+mod status;
+mod tag;
 
+pub use crate::config::Scope as ConfigScope;
+pub use crate::diff::Algorithm as DiffAlgorithm;
+pub use add::run as add;
+pub use branch::{run as branch, Action as BranchAction, Error as BranchError};
+pub use cat_file::{run as cat_file, Mode as CatFileMode};
+pub use checkout::{run as checkout, switch};
 pub use commit::run as commit;
+pub use config::{
+    run as config, Action as ConfigAction, Error as ConfigError, Options as ConfigOptions,
+    Type as ConfigType,
+};
+pub use diff::{run as diff, Target as DiffTarget};
+pub use hash_object::run as hash_object;
 pub use init::run as init;
+pub use log::{run as log, Format as LogFormat};
+pub use merge::{run as merge, Outcome as MergeOutcome};
+pub use merge_base::{is_ancestor, run as merge_base, Mode as MergeBaseMode};
+pub use pack_refs::run as pack_refs;
+pub use rev_parse::run as rev_parse;
+pub use status::{run as status, Format as StatusFormat};
+pub use tag::{run as tag, Action as TagAction};
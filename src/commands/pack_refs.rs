@@ -0,0 +1,26 @@
+use crate::{
+    refs::{self, PackedRef},
+    revision,
+};
+
+/// Move loose refs into `packed-refs`: tags, and refs that were already packed, or every ref
+/// under `refs/` with `all`. Symbolic refs always stay loose.
+pub fn run(all: bool) -> Result<String, String> {
+    let already_packed: Vec<String> = refs::read_packed_refs()
+        .into_iter()
+        .map(|packed_ref| packed_ref.name)
+        .collect();
+
+    let packed = refs::list("refs/")
+        .into_iter()
+        .filter(|(name, _)| all || name.starts_with("refs/tags/") || already_packed.contains(name))
+        .filter(|(name, _)| refs::read_symbolic_ref(name).is_none())
+        .map(|(name, oid)| {
+            let peeled = revision::peel(&oid, "").filter(|peeled| *peeled != oid);
+            PackedRef { name, oid, peeled }
+        })
+        .collect();
+
+    refs::pack(packed).map_err(|error| error.to_string())?;
+    Ok(String::new())
+}
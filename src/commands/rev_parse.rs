@@ -0,0 +1,16 @@
+use crate::revision;
+
+/// Resolve each revision to the object ID it names, one per line
+pub fn run(revisions: &[String], short: bool) -> Result<String, String> {
+    let mut output = String::new();
+    for name in revisions {
+        let oid = revision::resolve(name).map_err(|error| error.to_string())?;
+        let oid = match short {
+            true => revision::abbreviate(&oid),
+            false => oid,
+        };
+        output.push_str(&oid);
+        output.push('\n');
+    }
+    Ok(output)
+}
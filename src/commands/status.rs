@@ -0,0 +1,117 @@
+use crate::{
+    config::{self, Config},
+    status::Status,
+};
+use std::io::{self, IsTerminal};
+
+/// How to show the status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Git's short format, colored the way `color.status` (or `color.ui`) says
+    Short,
+    /// Porcelain v1, which is the short format made stable for scripts: never colored
+    Porcelain,
+}
+
+// The colors git's short format uses for each column, and the reset after them
+const UPDATED: &str = "\x1b[32m";
+const CHANGED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[m";
+
+/// Show the working tree status in short or porcelain v1 format. Paths are relative to the
+/// workspace root, which is where grit runs, so both formats name them the same way.
+pub fn run(format: Format) -> Result<String, String> {
+    let status = Status::scan().map_err(|error| format!("Couldn't get status: {}", error))?;
+    let color = format == Format::Short && uses_color()?;
+    let paint = |text: &str, code: &str| match color {
+        true => format!("{}{}{}", code, text, RESET),
+        false => text.to_string(),
+    };
+
+    let mut output = String::new();
+    for path in status.changed_paths() {
+        if let Some(code) = status.conflict_code(path) {
+            output.push_str(&format!("{} {}\n", paint(code, CHANGED), quote_path(path)));
+            continue;
+        }
+        let staged = match status.index_changes.get(path) {
+            Some(change) => paint(&change.code().to_string(), UPDATED),
+            None => String::from(" "),
+        };
+        let unstaged = match status.workspace_changes.get(path) {
+            Some(change) => paint(&change.code().to_string(), CHANGED),
+            None => String::from(" "),
+        };
+        output.push_str(&format!("{}{} {}\n", staged, unstaged, quote_path(path)));
+    }
+    for path in &status.untracked {
+        output.push_str(&format!("{} {}\n", paint("??", CHANGED), quote_path(path)));
+    }
+    Ok(output)
+}
+
+// Whether to color the short format: `color.status` wins over `color.ui`, and like git, `auto`
+// (the default) means only when writing to a terminal
+fn uses_color() -> Result<bool, String> {
+    let config = Config::build().map_err(|error| error.to_string())?;
+    let setting = match config.color.status.as_str() {
+        "" => config.color.ui,
+        _ => config.color.status,
+    };
+    let color = match setting.to_ascii_lowercase().as_str() {
+        "always" => true,
+        "never" => false,
+        "" | "auto" => io::stdout().is_terminal(),
+        // Any other true value means `auto` too
+        other => config::parse_bool(Some(other)) != Some(false) && io::stdout().is_terminal(),
+    };
+    Ok(color)
+}
+
+/// Quote a path the way git's status does when it contains spaces, quotes, control
+/// characters or anything outside of ASCII, escaping the troublesome bytes C-style
+pub fn quote_path(path: &str) -> String {
+    let needs_quotes = path
+        .bytes()
+        .any(|byte| byte == b' ' || byte == b'"' || byte == b'\\' || !(0x20..0x7f).contains(&byte));
+    if !needs_quotes {
+        return path.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for byte in path.bytes() {
+        match byte {
+            0x07 => quoted.push_str("\\a"),
+            0x08 => quoted.push_str("\\b"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\n' => quoted.push_str("\\n"),
+            0x0b => quoted.push_str("\\v"),
+            0x0c => quoted.push_str("\\f"),
+            b'\r' => quoted.push_str("\\r"),
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            byte if !(0x20..0x7f).contains(&byte) => quoted.push_str(&format!("\\{:03o}", byte)),
+            byte => quoted.push(byte as char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quote_path;
+
+    #[test]
+    fn leaves_plain_paths_alone() {
+        assert_eq!(quote_path("src/main.rs"), "src/main.rs");
+    }
+
+    #[test]
+    fn quotes_paths_like_git() {
+        assert_eq!(quote_path("a b"), "\"a b\"");
+        assert_eq!(quote_path("q\"x"), "\"q\\\"x\"");
+        assert_eq!(quote_path("tab\tx"), "\"tab\\tx\"");
+        assert_eq!(quote_path("é"), "\"\\303\\251\"");
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    objects::{self, Object, Tag},
+    refs, revision,
+    wildmatch::wildmatch,
+};
+
+/// What to do with tags
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Show every tag, or only those matching any of some glob patterns (`-l`)
+    List { patterns: Vec<String> },
+    /// Tag HEAD, or another object. With a message, the tag is an annotated tag object
+    /// (`-a -m`); without one, it's just a ref.
+    Create {
+        name: String,
+        target: Option<String>,
+        message: Option<String>,
+    },
+    /// Delete tags (`-d`)
+    Delete { names: Vec<String> },
+}
+
+/// List, create or delete tags
+pub fn run(action: Action) -> Result<String, String> {
+    match action {
+        Action::List { patterns } => Ok(list(&patterns)),
+        Action::Create {
+            name,
+            target,
+            message,
+        } => create(&name, target.as_deref(), message.as_deref()),
+        Action::Delete { names } => delete(&names),
+    }
+}
+
+fn list(patterns: &[String]) -> String {
+    refs::list("refs/tags/")
+        .into_iter()
+        .map(|(reference, _)| tag_name(&reference).to_string())
+        .filter(|name| patterns.is_empty() || patterns.iter().any(|p| wildmatch(p, name)))
+        .map(|name| format!("{}\n", name))
+        .collect()
+}
+
+fn create(name: &str, target: Option<&str>, message: Option<&str>) -> Result<String, String> {
+    if !refs::is_valid_name(name) {
+        return Err(format!("'{}' is not a valid tag name.", name));
+    }
+    let reference = format!("refs/tags/{}", name);
+    if refs::resolve(&reference).is_some() {
+        return Err(format!("tag '{}' already exists", name));
+    }
+
+    let target = target.unwrap_or("HEAD");
+    let oid = revision::resolve(target).map_err(|error| match error {
+        revision::Error::Ambiguous { .. } => error.to_string(),
+        _ => format!("Failed to resolve '{}' as a valid ref.", target),
+    })?;
+
+    let oid = match message {
+        Some(message) => {
+            let object = objects::read(&oid).map_err(|error| error.to_string())?;
+            let tag = Tag::new(&oid, object.kind().name(), name, message)
+                .map_err(|error| error.to_string())?;
+            objects::write(&tag).map_err(|error| error.to_string())?;
+            tag.id()
+        }
+        None => oid,
+    };
+    refs::update_ref(&reference, &oid).map_err(|error| error.to_string())?;
+    Ok(String::new())
+}
+
+fn delete(names: &[String]) -> Result<String, String> {
+    let mut output = String::new();
+    for name in names {
+        let reference = format!("refs/tags/{}", name);
+        let oid = refs::resolve(&reference).ok_or_else(|| format!("tag '{}' not found.", name))?;
+
+        refs::delete_ref(&reference).map_err(|error| error.to_string())?;
+        output.push_str(&format!(
+            "Deleted tag '{}' (was {})\n",
+            name,
+            revision::abbreviate(&oid)
+        ));
+    }
+    Ok(output)
+}
+
+fn tag_name(reference: &str) -> &str {
+    reference.trim_start_matches("refs/tags/")
+}
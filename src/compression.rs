@@ -6,7 +6,7 @@ pub fn compress(bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
     let mut bytes: &[u8] = bytes;
     let mut encoder = Encoder::new(Vec::new())?;
     io::copy(&mut bytes, &mut encoder)?;
-    Ok(encoder.finish().into_result()?)
+    encoder.finish().into_result()
 }
 
 pub fn decompress(compressed_bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
@@ -24,8 +24,7 @@ mod tests {
     #[test]
     fn compresses_some_data() {
         let some_text = "Hello 👋";
-        let some_text_compressed =
-            compress(&some_text.as_bytes().to_vec()).expect("Couldn't compress it");
+        let some_text_compressed = compress(some_text.as_bytes()).expect("Couldn't compress it");
         assert_eq!(
             some_text_compressed,
             vec![
@@ -0,0 +1,183 @@
+// Changing a config file the way `git config` does, by splicing new text into the old so every
+// comment, blank line and bit of indentation that isn't part of the change stays as it was
+
+use super::{
+    bad_line, parse_key,
+    parser::{self, Event},
+};
+use crate::lockfile::Lockfile;
+use std::{fs, io, ops::Range, path::Path};
+
+/// How a change to a config file went, when the file itself was fine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// The file was changed
+    Written,
+    /// Several entries have the key, so it isn't clear which to change
+    MultipleValues,
+    /// There's no entry with the key to remove
+    Missing,
+}
+
+/// Set a key in a config file, or remove it when there's no value, creating the file if it
+/// doesn't exist yet. An existing entry is replaced where it is, and a new one goes after the
+/// last entry in the last of its sections, or in a new section at the end. Removing the last
+/// entry of a section removes the section too, unless comments are around it.
+pub fn write(path: &Path, key: &str, value: Option<&str>) -> Result<Edit, io::Error> {
+    let normalized =
+        parse_key(key).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    let base_length = key.rfind('.').unwrap_or(0);
+
+    let contents = match fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    let events = parser::events(&contents).map_err(|line| bad_line(line, path))?;
+
+    let mut keys_sections = Vec::new();
+    let mut matches = Vec::new();
+    let mut last_in_section = None;
+    let mut in_keys_section = false;
+    for (index, (event, _)) in events.iter().enumerate() {
+        match event {
+            Event::Section(name) => {
+                in_keys_section = name == &normalized[..base_length];
+                if in_keys_section {
+                    last_in_section = Some(index);
+                }
+            }
+            Event::Entry(entry) if entry.key == normalized => matches.push(index),
+            Event::Entry(_) if in_keys_section => last_in_section = Some(index),
+            _ => {}
+        }
+        keys_sections.push(in_keys_section && matches!(event, Event::Section(_)));
+    }
+
+    match (matches.len(), value) {
+        (0, None) => return Ok(Edit::Missing),
+        (count, _) if count > 1 => return Ok(Edit::MultipleValues),
+        _ => {}
+    }
+
+    let bytes = contents.as_bytes();
+    let (copy_end, replace_end) = match matches.first() {
+        Some(&index) => {
+            let mut range = events[index].1.clone();
+            if value.is_none() {
+                maybe_remove_section(&events, &keys_sections, index, &mut range);
+            }
+            // Swallow whitespace before the entry on its line, since it's written indented
+            while range.start > 0
+                && bytes[range.start - 1].is_ascii_whitespace()
+                && bytes[range.start - 1] != b'\n'
+            {
+                range.start -= 1;
+            }
+            (range.start, range.end)
+        }
+        None => {
+            // After the section's last entry, or the end of the file when there's no section
+            let mut end = match last_in_section.or(events.len().checked_sub(1)) {
+                Some(index) => events[index].1.end,
+                None => bytes.len(),
+            };
+            // A section header's event stops short of its line break
+            if end > 0 && end < bytes.len() && bytes[end - 1] != b'\n' && bytes[end] == b'\n' {
+                end += 1;
+            }
+            (end, end)
+        }
+    };
+
+    let mut new = contents[..copy_end].to_string();
+    if copy_end > 0 && bytes[copy_end - 1] != b'\n' {
+        new.push('\n');
+    }
+    if let Some(value) = value {
+        if last_in_section.is_none() && matches.is_empty() {
+            new.push_str(&section_header(&key[..base_length]));
+        }
+        new.push_str(&pair(&key[base_length + 1..], value));
+    }
+    new.push_str(&contents[replace_end..]);
+
+    let mut lockfile = Lockfile::acquire(path)?;
+    lockfile.write(new.as_bytes())?;
+    lockfile.commit()?;
+    Ok(Edit::Written)
+}
+
+// Widen the range being removed for an entry to take its whole section with it, when it's the
+// only entry there and no comments are in or right around the section. `keys_sections` marks
+// the headers of the entry's section.
+fn maybe_remove_section(
+    events: &[(Event, Range<usize>)],
+    keys_sections: &[bool],
+    entry: usize,
+    range: &mut Range<usize>,
+) {
+    let mut section_seen = false;
+    let mut start = entry;
+    while start > 0 {
+        match &events[start - 1].0 {
+            Event::Comment => return,
+            Event::Entry(_) if !section_seen => return,
+            Event::Entry(_) => break,
+            Event::Section(_) if !keys_sections[start - 1] => break,
+            Event::Section(_) => section_seen = true,
+            Event::Whitespace => {}
+        }
+        start -= 1;
+    }
+
+    let mut end = entry + 1;
+    while end < events.len() {
+        match &events[end].0 {
+            Event::Comment | Event::Entry(_) => return,
+            Event::Section(_) if !keys_sections[end] => break,
+            Event::Section(_) | Event::Whitespace => {}
+        }
+        end += 1;
+    }
+
+    range.start = events[start].1.start;
+    range.end = match events.get(end) {
+        Some((_, following)) => following.start,
+        None => events[events.len() - 1].1.end,
+    };
+}
+
+// A header for a key's section, like `[user]` or `[remote "origin"]`, as it was typed
+fn section_header(base: &str) -> String {
+    match base.split_once('.') {
+        Some((section, subsection)) => {
+            let escaped = subsection.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("[{} \"{}\"]\n", section, escaped)
+        }
+        None => format!("[{}]\n", base),
+    }
+}
+
+// An indented `name = value` line, quoting the value when it would otherwise lose leading or
+// trailing spaces or be cut short by a comment, and escaping what has to be
+fn pair(name: &str, value: &str) -> String {
+    let needs_quotes = value.starts_with(' ') || value.ends_with(' ') || value.contains([';', '#']);
+    let quote = match needs_quotes {
+        true => "\"",
+        false => "",
+    };
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    format!("\t{} = {}{}{}\n", name, quote, escaped, quote)
+}
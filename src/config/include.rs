@@ -0,0 +1,107 @@
+// Config files can pull in others with `include.path`, or with `includeIf.<condition>.path`
+// when the repository is somewhere in particular (`gitdir:` and `gitdir/i:`) or on a
+// particular branch (`onbranch:`)
+
+use super::{expand_home, parser::Entry};
+use crate::{
+    refs,
+    wildmatch::{wildmatch_with, Flags},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The file an entry says to include, if it's an include whose condition holds here. Relative
+/// paths are relative to the directory of the file the entry is in.
+pub fn included_path(entry: &Entry, file: &Path) -> Option<PathBuf> {
+    let value = entry.value.as_deref()?;
+    if entry.key != "include.path" {
+        let condition = entry
+            .key
+            .strip_prefix("includeif.")?
+            .strip_suffix(".path")?;
+        if !holds(condition, file) {
+            return None;
+        }
+    }
+
+    let path = expand_home(value);
+    match path.is_absolute() {
+        true => Some(path),
+        false => Some(file.parent().unwrap_or_else(|| Path::new("")).join(path)),
+    }
+}
+
+fn holds(condition: &str, file: &Path) -> bool {
+    if let Some(pattern) = condition.strip_prefix("gitdir:") {
+        gitdir_matches(pattern, file, false)
+    } else if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+        gitdir_matches(pattern, file, true)
+    } else if let Some(pattern) = condition.strip_prefix("onbranch:") {
+        onbranch_matches(pattern)
+    } else {
+        false
+    }
+}
+
+// Whether the repository's `.git` directory matches a pattern, which is relative to the
+// including file's directory when it starts with `./`, can be anywhere when it's otherwise
+// relative, and takes in everything inside a directory when it ends with `/`. Like git, this
+// tries the real path first, then the path as given, in case of symlinks.
+fn gitdir_matches(pattern: &str, file: &Path, ignore_case: bool) -> bool {
+    let git_directory = match env::current_dir() {
+        Ok(directory) => directory.join(".git"),
+        Err(_) => return false,
+    };
+
+    let mut pattern = expand_home(pattern).to_string_lossy().into_owned();
+    // The including file's directory is taken literally, not as part of the glob
+    let mut prefix = 0;
+    if pattern.starts_with("./") {
+        let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+        let directory = file.parent().unwrap_or_else(|| Path::new(""));
+        let directory = directory.to_string_lossy();
+        let directory = directory.trim_end_matches('/');
+        pattern.replace_range(..1, directory);
+        prefix = directory.len() + 1;
+    } else if !Path::new(&pattern).is_absolute() {
+        pattern.insert_str(0, "**/");
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+
+    let flags = Flags {
+        pathname: true,
+        casefold: ignore_case,
+    };
+    let real = fs::canonicalize(&git_directory).unwrap_or_else(|_| git_directory.clone());
+    [real, git_directory].iter().any(|directory| {
+        let text = directory.to_string_lossy();
+        let same_prefix = match (text.get(..prefix), pattern.get(..prefix)) {
+            (Some(start), Some(literal)) if ignore_case => start.eq_ignore_ascii_case(literal),
+            (Some(start), Some(literal)) => start == literal,
+            _ => false,
+        };
+        same_prefix && wildmatch_with(&pattern[prefix..], &text[prefix..], flags)
+    })
+}
+
+// Whether HEAD is on a branch (born or not) matching a pattern, which takes in every branch
+// beneath it when it ends with `/`
+fn onbranch_matches(pattern: &str) -> bool {
+    let branch = match refs::current_branch() {
+        Some(branch) => branch,
+        None => return false,
+    };
+    let mut pattern = pattern.to_string();
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+    let flags = Flags {
+        pathname: true,
+        casefold: false,
+    };
+    wildmatch_with(&pattern, &branch, flags)
+}
@@ -0,0 +1,451 @@
+mod edit;
+mod include;
+mod parser;
+mod types;
+
+pub use edit::{write, Edit};
+pub use types::{parse_bool, parse_int};
+
+use parser::Entry;
+use std::{
+    collections::HashMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How deep includes can nest, which is what stops circular includes
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+pub struct Config {
+    pub user: User,
+    pub author: User,
+    pub committer: User,
+    pub diff: Diff,
+    pub color: Color,
+}
+
+pub struct User {
+    pub name: String,
+    pub email: String,
+    pub date: String,
+}
+
+pub struct Diff {
+    pub algorithm: String,
+}
+
+pub struct Color {
+    pub ui: String,
+    pub status: String,
+}
+
+/// Which config file to read or write, from the least to the most specific
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    /// Every user on the machine
+    System,
+    /// The current user, in their home directory
+    Global,
+    /// The repository
+    Local,
+}
+
+impl Scope {
+    /// Where the scope's config file is, as git finds it. `GIT_CONFIG_SYSTEM` and
+    /// `GIT_CONFIG_GLOBAL` name the system and global files outright. Otherwise the global file
+    /// is `~/.gitconfig`, unless only `$XDG_CONFIG_HOME/git/config` (or `~/.config/git/config`)
+    /// exists.
+    pub fn path(&self) -> Result<PathBuf, io::Error> {
+        match self {
+            Scope::System => Ok(system_path()),
+            Scope::Global => match global_paths() {
+                (None, _) => Err(io::Error::other("$HOME not set")),
+                (Some(user), Some(xdg)) if !user.exists() && xdg.exists() => Ok(xdg),
+                (Some(user), _) => Ok(user),
+            },
+            Scope::Local => Ok(PathBuf::from(".git/config")),
+        }
+    }
+}
+
+/// A config entry along with where it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub key: String,
+    /// None for a bare key, which is shorthand for true
+    pub value: Option<String>,
+    pub origin: Origin,
+}
+
+/// Where a setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    File(PathBuf),
+    /// Passed down in `GIT_CONFIG_COUNT`, `GIT_CONFIG_KEY_<n>` and `GIT_CONFIG_VALUE_<n>`
+    CommandLine,
+}
+
+impl fmt::Display for Origin {
+    // The way `--show-origin` shows it
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::File(path) => write!(f, "file:{}", path.display()),
+            Origin::CommandLine => write!(f, "command line:"),
+        }
+    }
+}
+
+/// Why a key given on the command line isn't one
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyError {
+    /// There's no dot, or nothing before the first one
+    NoSection(String),
+    /// Nothing comes after the last dot
+    NoName(String),
+    /// Section or variable names have characters they can't, or the variable name doesn't
+    /// start with a letter
+    Invalid(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyError::NoSection(key) => write!(f, "key does not contain a section: {}", key),
+            KeyError::NoName(key) => write!(f, "key does not contain variable name: {}", key),
+            KeyError::Invalid(key) => write!(f, "invalid key: {}", key),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Check a key like `user.name` or `remote.origin.url` and give it back the way entries' keys
+/// are parsed: with the section and variable names lowercased, but the subsection as it is
+pub fn parse_key(key: &str) -> Result<String, KeyError> {
+    let last_dot = match key.rfind('.') {
+        Some(0) | None => return Err(KeyError::NoSection(key.to_string())),
+        Some(dot) if dot == key.len() - 1 => return Err(KeyError::NoName(key.to_string())),
+        Some(dot) => dot,
+    };
+    let first_dot = key.find('.').unwrap_or(last_dot);
+
+    let (section, name) = (&key[..first_dot], &key[last_dot + 1..]);
+    let is_key_char = |c: char| c.is_ascii_alphanumeric() || c == '-';
+    if !section.chars().all(is_key_char)
+        || !name.chars().all(is_key_char)
+        || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || key.contains('\n')
+    {
+        return Err(KeyError::Invalid(key.to_string()));
+    }
+    Ok(format!(
+        "{}{}{}",
+        section.to_ascii_lowercase(),
+        &key[first_dot..=last_dot],
+        name.to_ascii_lowercase()
+    ))
+}
+
+/// Every setting in the order git reads them, so later ones win. With no scope, that's the
+/// system file (unless `GIT_CONFIG_NOSYSTEM` says not to), the XDG and then the home directory
+/// files, the repository's, each with whatever they include, and last of all the settings
+/// passed down in the environment. With a scope, it's just the scope's file as it is, which
+/// then has to exist.
+pub fn settings(scope: Option<Scope>) -> Result<Vec<Setting>, io::Error> {
+    if let Some(scope) = scope {
+        let path = scope.path()?;
+        let bytes = fs::read(&path)?;
+        return parse_text(&path, &bytes).map(|entries| with_file(entries, &path));
+    }
+
+    let mut paths = Vec::new();
+    if reads_system_config()? {
+        paths.push(system_path());
+    }
+    let (user, xdg) = global_paths();
+    paths.extend(xdg);
+    paths.extend(user);
+    paths.push(Scope::Local.path()?);
+
+    let mut settings = Vec::new();
+    for path in paths {
+        settings.extend(read_file_config(&path, 0)?);
+    }
+    settings.extend(command_line_settings()?);
+    Ok(settings)
+}
+
+// Try to get a config value, but default to an empty string
+fn extract(config_map: &HashMap<String, String>, key: &str) -> String {
+    match config_map.get(key) {
+        Some(value) => value.to_owned(),
+        None => String::from(""),
+    }
+}
+
+impl Config {
+    // Builds proper config struct from a raw config hashmap, failing when a file can't be
+    // read or parsed
+    pub fn build() -> Result<Config, io::Error> {
+        let raw_config = merge_configs()?;
+        Ok(Config {
+            user: User {
+                name: extract(&raw_config, "user.name"),
+                email: extract(&raw_config, "user.email"),
+                date: extract(&raw_config, "user.date"),
+            },
+            author: User {
+                name: extract(&raw_config, "author.name"),
+                email: extract(&raw_config, "author.email"),
+                date: extract(&raw_config, "author.date"),
+            },
+            committer: User {
+                name: extract(&raw_config, "committer.name"),
+                email: extract(&raw_config, "committer.email"),
+                date: extract(&raw_config, "committer.date"),
+            },
+            diff: Diff {
+                algorithm: extract(&raw_config, "diff.algorithm"),
+            },
+            color: Color {
+                ui: extract(&raw_config, "color.ui"),
+                status: extract(&raw_config, "color.status"),
+            },
+        })
+    }
+}
+
+// Finds configs wherever they may be (system, user and repo files; env)
+// And gives a map with config values back, where the last of a multi-valued key wins
+fn merge_configs() -> Result<HashMap<String, String>, io::Error> {
+    // Ordered by least importance, so we can overwrite as we go
+    let mut merged_config: HashMap<String, String> = settings(None)?
+        .into_iter()
+        .map(|setting| {
+            (
+                setting.key,
+                setting.value.unwrap_or_else(|| String::from("true")),
+            )
+        })
+        .collect();
+    merged_config.extend(parse_env_config());
+
+    Ok(merged_config)
+}
+
+// Finds stuff like "GIT_AUTHOR_NAME" in env and gives a map with stuff like "author.name"
+fn parse_env_config() -> HashMap<String, String> {
+    let mut parsed = HashMap::new();
+    for (key, value) in env::vars() {
+        if !key.starts_with("GIT") {
+            continue;
+        }
+        let mut words: Vec<String> = key
+            .split('_')
+            .map(|word| word.to_string().to_lowercase())
+            .collect();
+        words.remove(0);
+        let key = words.join(".");
+
+        parsed.insert(key, value);
+    }
+    parsed
+}
+
+fn system_path() -> PathBuf {
+    match env::var_os("GIT_CONFIG_SYSTEM") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from("/etc/gitconfig"),
+    }
+}
+
+fn reads_system_config() -> Result<bool, io::Error> {
+    match env::var("GIT_CONFIG_NOSYSTEM") {
+        Ok(value) => match parse_bool(Some(&value)) {
+            Some(no_system) => Ok(!no_system),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "bad boolean config value '{}' for 'GIT_CONFIG_NOSYSTEM'",
+                    value
+                ),
+            )),
+        },
+        Err(_) => Ok(true),
+    }
+}
+
+// The global file in the home directory and the XDG one, like git's `git_global_config`.
+// `GIT_CONFIG_GLOBAL` replaces both, and without a home there's neither.
+fn global_paths() -> (Option<PathBuf>, Option<PathBuf>) {
+    if let Some(path) = env::var_os("GIT_CONFIG_GLOBAL") {
+        return (Some(PathBuf::from(path)), None);
+    }
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let xdg = match env::var_os("XDG_CONFIG_HOME").filter(|xdg| !xdg.is_empty()) {
+        Some(xdg) => Some(PathBuf::from(xdg).join("git/config")),
+        None => home.as_ref().map(|home| home.join(".config/git/config")),
+    };
+    (home.map(|home| home.join(".gitconfig")), xdg)
+}
+
+// Settings passed down in `GIT_CONFIG_COUNT`, `GIT_CONFIG_KEY_<n>` and `GIT_CONFIG_VALUE_<n>`,
+// the way `git -c` hands them to the commands it runs
+fn command_line_settings() -> Result<Vec<Setting>, io::Error> {
+    let invalid = |reason: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unable to parse command-line config: {}", reason),
+        )
+    };
+    let count = match env::var("GIT_CONFIG_COUNT") {
+        Ok(count) if count.is_empty() => 0,
+        Ok(count) => count
+            .parse::<usize>()
+            .map_err(|_| invalid(String::from("bogus count in GIT_CONFIG_COUNT")))?,
+        Err(_) => return Ok(vec![]),
+    };
+
+    let variable = |kind: &str, n: usize| {
+        let name = format!("GIT_CONFIG_{}_{}", kind, n);
+        env::var(&name)
+            .map_err(|_| invalid(format!("missing config {} {}", kind.to_lowercase(), name)))
+    };
+    (0..count)
+        .map(|n| {
+            let key = variable("KEY", n)?;
+            let value = variable("VALUE", n)?;
+            if key.is_empty() {
+                return Err(invalid(String::from("empty config key")));
+            }
+            Ok(Setting {
+                key: parse_key(&key).map_err(|error| invalid(error.to_string()))?,
+                value: Some(value),
+                origin: Origin::CommandLine,
+            })
+        })
+        .collect()
+}
+
+// Read a config file's entries in order, each include followed by the entries of the file it
+// includes. Missing files, included or not, have no entries.
+fn read_file_config(path: &Path, depth: usize) -> Result<Vec<Setting>, io::Error> {
+    let entries = match fs::read(path) {
+        Ok(bytes) => parse_text(path, &bytes)?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+
+    let mut expanded = Vec::new();
+    for entry in entries {
+        let included = include::included_path(&entry, path).filter(|included| included.exists());
+        expanded.extend(with_file(vec![entry], path));
+        if let Some(included) = included {
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "exceeded maximum include depth ({}) while including\n\t{}\nfrom\n\t{}\n\
+                         This might be due to circular includes.",
+                        MAX_INCLUDE_DEPTH,
+                        included.display(),
+                        path.display()
+                    ),
+                ));
+            }
+            expanded.extend(read_file_config(&included, depth + 1)?);
+        }
+    }
+    Ok(expanded)
+}
+
+fn parse_text(path: &Path, bytes: &[u8]) -> Result<Vec<Entry>, io::Error> {
+    parser::parse(&String::from_utf8_lossy(bytes)).map_err(|line| bad_line(line, path))
+}
+
+// Git's complaint about a config file it can't make sense of
+fn bad_line(line: usize, path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("bad config line {} in file {}", line, path.display()),
+    )
+}
+
+fn with_file(entries: Vec<Entry>, path: &Path) -> Vec<Setting> {
+    entries
+        .into_iter()
+        .map(|entry| Setting {
+            key: entry.key,
+            value: entry.value,
+            origin: Origin::File(path.to_path_buf()),
+        })
+        .collect()
+}
+
+// Expand a leading `~` to the home directory, like git does for paths in config
+pub fn expand_home(path: &str) -> PathBuf {
+    let home = || PathBuf::from(env::var("HOME").unwrap_or_default());
+    match path.strip_prefix('~') {
+        Some("") => home(),
+        Some(rest) if rest.starts_with('/') => home().join(&rest[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn parses_keys_like_git() {
+        assert_eq!(parse_key("User.Name"), Ok(String::from("user.name")));
+        assert_eq!(
+            parse_key("Remote.My.Origin.URL"),
+            Ok(String::from("remote.My.Origin.url"))
+        );
+        assert_eq!(parse_key("a..b"), Ok(String::from("a..b")));
+        assert_eq!(parse_key("1a.b-2"), Ok(String::from("1a.b-2")));
+        assert_eq!(
+            parse_key("name"),
+            Err(KeyError::NoSection(String::from("name")))
+        );
+        assert_eq!(
+            parse_key(".name"),
+            Err(KeyError::NoSection(String::from(".name")))
+        );
+        assert_eq!(
+            parse_key("user."),
+            Err(KeyError::NoName(String::from("user.")))
+        );
+        assert_eq!(
+            parse_key("user.1"),
+            Err(KeyError::Invalid(String::from("user.1")))
+        );
+        assert_eq!(
+            parse_key("us_er.a"),
+            Err(KeyError::Invalid(String::from("us_er.a")))
+        );
+        assert_eq!(
+            parse_key("a.sub\n.b"),
+            Err(KeyError::Invalid(String::from("a.sub\n.b")))
+        );
+    }
+
+    #[test]
+    fn stops_circular_includes() {
+        let directory = env::temp_dir().join(format!("grit_config_{}", Uuid::new_v4()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("one"), "[include]\n\tpath = two\n").unwrap();
+        fs::write(directory.join("two"), "[include]\n\tpath = one\n").unwrap();
+
+        let error = read_file_config(&directory.join("one"), 0).unwrap_err();
+        fs::remove_dir_all(&directory).unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("exceeded maximum include depth (10) while including"));
+        assert!(error
+            .to_string()
+            .ends_with("This might be due to circular includes."));
+    }
+}
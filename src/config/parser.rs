@@ -0,0 +1,403 @@
+// Git's INI dialect for config files, following its `config.c` closely: `[section]`,
+// `[section "subsection"]` and old-style `[section.subsection]` headers, `name = value`
+// entries (or a bare `name`, meaning true), `#` and `;` comments, double quotes and backslash
+// escapes in values, and lines continued with a trailing backslash
+
+use std::ops::Range;
+
+/// One entry of a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// The full key, like `user.name` or `remote.origin.url`. Section and variable names are
+    /// case-insensitive, so they're lowercased, but a quoted subsection is kept as written.
+    pub key: String,
+    /// None for a bare `name` with no `=`, which is shorthand for true
+    pub value: Option<String>,
+}
+
+/// A piece of a config file, as git sees it when it edits one
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A section header, from `[` to `]`, with its name like `remote.origin`
+    Section(String),
+    /// An entry, through the end of its line
+    Entry(Entry),
+    /// A comment, up to the end of its line
+    Comment,
+    /// A run of blank space and line breaks
+    Whitespace,
+}
+
+/// Parse the text of a config file into its entries in order, or give back the number of the
+/// line where it stops making sense, as git reports it
+pub fn parse(text: &str) -> Result<Vec<Entry>, usize> {
+    Ok(events(text)?
+        .into_iter()
+        .filter_map(|(event, _)| match event {
+            Event::Entry(entry) => Some(entry),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Parse the text of a config file into the pieces that make it up, each with the byte range
+/// it covers, so nothing in the text is left out
+pub fn events(text: &str) -> Result<Vec<(Event, Range<usize>)>, usize> {
+    let start = match text.starts_with('\u{feff}') {
+        true => '\u{feff}'.len_utf8(),
+        false => 0,
+    };
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        position: start,
+        line: 1,
+        eof: false,
+        events: Vec::new(),
+    };
+    match parser.parse() {
+        Some(()) => {
+            let ends: Vec<usize> = parser
+                .events
+                .iter()
+                .skip(1)
+                .map(|(_, begin)| *begin)
+                .chain(Some(text.len()))
+                .collect();
+            Ok(parser
+                .events
+                .into_iter()
+                .zip(ends)
+                .map(|((event, begin), end)| (event, begin..end))
+                .collect())
+        }
+        None => Err(parser.line),
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    line: usize,
+    eof: bool,
+    // Each event and where it begins, since it ends where the next one does
+    events: Vec<(Event, usize)>,
+}
+
+impl Parser<'_> {
+    // The next character, reading `\r\n` as `\n`, and a final `\n` at the end of the file
+    fn next(&mut self) -> u8 {
+        let c = match self.bytes.get(self.position) {
+            Some(&c) => c,
+            None => {
+                self.eof = true;
+                self.line += 1;
+                return b'\n';
+            }
+        };
+        self.position += 1;
+        let c = match (c, self.bytes.get(self.position)) {
+            (b'\r', Some(b'\n')) => {
+                self.position += 1;
+                b'\n'
+            }
+            (c, _) => c,
+        };
+        if c == b'\n' {
+            self.line += 1;
+        }
+        c
+    }
+
+    // Note that an event starts at a position, where runs of whitespace are one event
+    fn begin(&mut self, event: Event, position: usize) {
+        if event == Event::Whitespace && self.events.last().map(|(last, _)| last) == Some(&event) {
+            return;
+        }
+        self.events.push((event, position));
+    }
+
+    fn parse(&mut self) -> Option<()> {
+        let mut section = String::new();
+        let mut comment = false;
+
+        loop {
+            let start = self.position;
+            let c = self.next();
+            if c == b'\n' {
+                if self.eof {
+                    return Some(());
+                }
+                self.begin(Event::Whitespace, start);
+                comment = false;
+                continue;
+            }
+            if comment {
+                continue;
+            }
+            if c.is_ascii_whitespace() {
+                self.begin(Event::Whitespace, start);
+                continue;
+            }
+            if c == b'#' || c == b';' {
+                self.begin(Event::Comment, start);
+                comment = true;
+                continue;
+            }
+            if c == b'[' {
+                section = self.section().filter(|section| !section.is_empty())?;
+                self.begin(Event::Section(section.clone()), start);
+                continue;
+            }
+            if !c.is_ascii_alphabetic() {
+                return None;
+            }
+
+            let (name, value) = self.entry(c)?;
+            let key = match section.is_empty() {
+                true => name,
+                false => format!("{}.{}", section, name),
+            };
+            self.begin(Event::Entry(Entry { key, value }), start);
+        }
+    }
+
+    // A section header, just past its `[`
+    fn section(&mut self) -> Option<String> {
+        let mut name = Vec::new();
+        loop {
+            let c = self.next();
+            if self.eof {
+                return None;
+            }
+            match c {
+                b']' => return Some(text(name)),
+                c if c.is_ascii_whitespace() => return self.subsection(name, c),
+                c if is_key_char(c) || c == b'.' => name.push(c.to_ascii_lowercase()),
+                _ => return None,
+            }
+        }
+    }
+
+    // The `"subsection"]` of a header, after the section's name and some whitespace
+    fn subsection(&mut self, mut name: Vec<u8>, mut c: u8) -> Option<String> {
+        while c.is_ascii_whitespace() {
+            if c == b'\n' {
+                return self.incomplete_line();
+            }
+            c = self.next();
+        }
+        if c != b'"' {
+            return None;
+        }
+
+        name.push(b'.');
+        loop {
+            let mut c = self.next();
+            if c == b'\n' {
+                return self.incomplete_line();
+            }
+            if c == b'"' {
+                break;
+            }
+            if c == b'\\' {
+                c = self.next();
+                if c == b'\n' {
+                    return self.incomplete_line();
+                }
+            }
+            name.push(c);
+        }
+        match self.next() {
+            b']' => Some(text(name)),
+            _ => None,
+        }
+    }
+
+    // A variable's name, from its first letter on, then its value if it has one
+    fn entry(&mut self, first: u8) -> Option<(String, Option<String>)> {
+        let mut name = vec![first.to_ascii_lowercase()];
+        let mut c;
+        loop {
+            c = self.next();
+            if self.eof || !is_key_char(c) {
+                break;
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        while c == b' ' || c == b'\t' {
+            c = self.next();
+        }
+
+        match c {
+            b'\n' => Some((text(name), None)),
+            b'=' => self.value().map(|value| (text(name), Some(value))),
+            _ => None,
+        }
+    }
+
+    // Whitespace around a value is dropped, and each unquoted whitespace character inside it
+    // becomes a space
+    fn value(&mut self) -> Option<String> {
+        let mut value = Vec::new();
+        let mut quoted = false;
+        let mut comment = false;
+        let mut spaces = 0;
+
+        loop {
+            let mut c = self.next();
+            if c == b'\n' {
+                if quoted {
+                    return self.incomplete_line();
+                }
+                return Some(text(value));
+            }
+            if comment {
+                continue;
+            }
+            if c.is_ascii_whitespace() && !quoted {
+                if !value.is_empty() {
+                    spaces += 1;
+                }
+                continue;
+            }
+            if !quoted && (c == b';' || c == b'#') {
+                comment = true;
+                continue;
+            }
+            value.extend((0..spaces).map(|_| b' '));
+            spaces = 0;
+
+            if c == b'\\' {
+                c = match self.next() {
+                    b'\n' => continue,
+                    b't' => b'\t',
+                    b'b' => 8,
+                    b'n' => b'\n',
+                    c @ (b'\\' | b'"') => c,
+                    _ => return None,
+                };
+                value.push(c);
+                continue;
+            }
+            if c == b'"' {
+                quoted = !quoted;
+                continue;
+            }
+            value.push(c);
+        }
+    }
+
+    // Git blames the line that was cut short, not the one after it
+    fn incomplete_line<T>(&mut self) -> Option<T> {
+        self.line -= 1;
+        None
+    }
+}
+
+fn is_key_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-'
+}
+
+fn text(bytes: Vec<u8>) -> String {
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, value: Option<&str>) -> Entry {
+        Entry {
+            key: key.to_string(),
+            value: value.map(String::from),
+        }
+    }
+
+    #[test]
+    fn parses_sections_and_subsections() {
+        let text = "[User]\n\
+            \tName = Count Dracula\n\
+            [remote \"Origin\"]\n\
+            \turl = https://example.com/castle.git\n\
+            [Branch.Main] remote = origin\n\
+            [sub \"with \\\"quotes\\\" \\\\ in it\"]\n\
+            \tkey = value\n";
+        assert_eq!(
+            parse(text).unwrap(),
+            vec![
+                entry("user.name", Some("Count Dracula")),
+                entry("remote.Origin.url", Some("https://example.com/castle.git")),
+                entry("branch.main.remote", Some("origin")),
+                entry("sub.with \"quotes\" \\ in it.key", Some("value")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_values_like_git() {
+        let text = "[core]\r\n\
+            \tbare\n\
+            \tempty =\n\
+            \tspaced =   a  \t b   # a comment\n\
+            \tquoted = \" keep  these \" ; another\n\
+            \tescaped = tab\\there\\nnewline \\\"\\\\\n\
+            \tcontinued = one \\\n   two\n\
+            # a comment line\n\
+            ; and another\n\
+            \tmulti = 1\n\
+            \tMulti = 2\n";
+        assert_eq!(
+            parse(text).unwrap(),
+            vec![
+                entry("core.bare", None),
+                entry("core.empty", Some("")),
+                entry("core.spaced", Some("a    b")),
+                entry("core.quoted", Some(" keep  these ")),
+                entry("core.escaped", Some("tab\there\nnewline \"\\")),
+                entry("core.continued", Some("one    two")),
+                entry("core.multi", Some("1")),
+                entry("core.multi", Some("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_bad_line_like_git() {
+        assert_eq!(parse("[a]\n\tk = \"unterminated\n"), Err(2));
+        assert_eq!(parse("[a]\nk = 1\n[b\n"), Err(3));
+        assert_eq!(parse("[a]\nk = 1\nbad # x\n"), Err(3));
+        assert_eq!(parse("[a]\nk = \"x\\q\"\n"), Err(2));
+        assert_eq!(parse("[a]\nk=v\n["), Err(4));
+        assert_eq!(parse("[]\nk=v\n"), Err(1));
+        assert_eq!(parse("[a]\n1k = v\n"), Err(2));
+    }
+
+    #[test]
+    fn covers_the_whole_text_with_events() {
+        let text = "# Hi\n[core]\n\tbare = false ; no\n\n[user] name = A\n\temail";
+        let events = events(text).unwrap();
+        let pieces: Vec<(&Event, &str)> = events
+            .iter()
+            .map(|(event, range)| (event, &text[range.clone()]))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (&Event::Comment, "# Hi"),
+                (&Event::Whitespace, "\n"),
+                (&Event::Section(String::from("core")), "[core]"),
+                (&Event::Whitespace, "\n\t"),
+                (
+                    &Event::Entry(entry("core.bare", Some("false"))),
+                    "bare = false ; no\n"
+                ),
+                (&Event::Whitespace, "\n"),
+                (&Event::Section(String::from("user")), "[user]"),
+                (&Event::Whitespace, " "),
+                (&Event::Entry(entry("user.name", Some("A"))), "name = A\n"),
+                (&Event::Whitespace, "\t"),
+                (&Event::Entry(entry("user.email", None)), "email"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+// Reading config values as booleans and numbers the way git does, for `--type`
+
+use std::convert::TryFrom;
+
+/// A value as a boolean: true for a bare key or `true`, `yes`, `on` or a nonzero number, and
+/// false for `false`, `no`, `off`, an empty value or zero, in any case. None for anything else.
+pub fn parse_bool(value: Option<&str>) -> Option<bool> {
+    let value = match value {
+        Some(value) => value,
+        None => return Some(true),
+    };
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" | "" => Some(false),
+        _ => parse_int(value).ok().map(|number| number != 0),
+    }
+}
+
+/// A value as a number, in decimal, hex (`0x`) or octal (a leading `0`), optionally scaled by a
+/// `k`, `m` or `g` suffix (in units of 1024). Otherwise, git's reason it isn't one.
+pub fn parse_int(value: &str) -> Result<i64, &'static str> {
+    const INVALID: &str = "invalid unit";
+    const OUT_OF_RANGE: &str = "out of range";
+
+    let trimmed = value.trim_start_matches([' ', '\t', '\n', '\x0b', '\x0c', '\r']);
+    let (negative, unsigned) = match trimmed.as_bytes().first() {
+        Some(b'-') => (true, &trimmed[1..]),
+        Some(b'+') => (false, &trimmed[1..]),
+        _ => (false, trimmed),
+    };
+    let (radix, digits) = match unsigned.as_bytes() {
+        [b'0', b'x' | b'X', c, ..] if c.is_ascii_hexdigit() => (16, &unsigned[2..]),
+        [b'0', ..] => (8, unsigned),
+        _ => (10, unsigned),
+    };
+
+    let length = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    if length == 0 {
+        return Err(INVALID);
+    }
+    let magnitude = i128::from_str_radix(&digits[..length], radix).map_err(|_| OUT_OF_RANGE)?;
+    let number = match negative {
+        true => -magnitude,
+        false => magnitude,
+    };
+    if number > i64::MAX as i128 || number < i64::MIN as i128 {
+        return Err(OUT_OF_RANGE);
+    }
+
+    let factor: i128 = match &digits[length..] {
+        "" => 1,
+        "k" | "K" => 1 << 10,
+        "m" | "M" => 1 << 20,
+        "g" | "G" => 1 << 30,
+        _ => return Err(INVALID),
+    };
+    i64::try_from(number * factor).map_err(|_| OUT_OF_RANGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_booleans_like_git() {
+        assert_eq!(parse_bool(None), Some(true));
+        assert_eq!(parse_bool(Some("YES")), Some(true));
+        assert_eq!(parse_bool(Some("On")), Some(true));
+        assert_eq!(parse_bool(Some("off")), Some(false));
+        assert_eq!(parse_bool(Some("")), Some(false));
+        assert_eq!(parse_bool(Some("0")), Some(false));
+        assert_eq!(parse_bool(Some("1k")), Some(true));
+        assert_eq!(parse_bool(Some("maybe")), None);
+    }
+
+    #[test]
+    fn parses_numbers_like_git() {
+        assert_eq!(parse_int("42"), Ok(42));
+        assert_eq!(parse_int(" -2M"), Ok(-2 * 1024 * 1024));
+        assert_eq!(parse_int("1k"), Ok(1024));
+        assert_eq!(parse_int("3g"), Ok(3 * 1024 * 1024 * 1024));
+        assert_eq!(parse_int("0x10"), Ok(16));
+        assert_eq!(parse_int("010"), Ok(8));
+        assert_eq!(parse_int("0"), Ok(0));
+        assert_eq!(parse_int(""), Err("invalid unit"));
+        assert_eq!(parse_int("12x"), Err("invalid unit"));
+        assert_eq!(parse_int("1kb"), Err("invalid unit"));
+        assert_eq!(parse_int("09"), Err("invalid unit"));
+        assert_eq!(parse_int("9223372036854775807"), Ok(i64::MAX));
+        assert_eq!(parse_int("9223372036854775808"), Err("out of range"));
+        assert_eq!(parse_int("9223372036854775807k"), Err("out of range"));
+    }
+}
@@ -0,0 +1,368 @@
+// Sliding runs of changed lines to where a reader would expect them, the way git does
+// after any diff algorithm runs: runs are merged where they can be, lined up with changes
+// on the other side, and otherwise placed by git's indent heuristic (when it's wanted).
+
+// Limits on how far the indent heuristic looks
+const MAX_INDENT: i32 = 200;
+const MAX_BLANKS: i32 = 20;
+const MAX_SLIDING: usize = 100;
+
+// How the indent heuristic weighs each split between a run and its context
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+
+/// One side of a diff: its lines, and which of them are changed
+pub struct Side<'a> {
+    lines: &'a [&'a [u8]],
+    changed: Vec<bool>,
+}
+
+// A run of changed lines, `start..end`, which may be empty
+struct Group {
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Side<'a> {
+    pub fn new(lines: &'a [&'a [u8]], changed: Vec<bool>) -> Side<'a> {
+        Side { lines, changed }
+    }
+
+    pub fn changed(&self, line: usize) -> bool {
+        self.changed.get(line).copied().unwrap_or(false)
+    }
+
+    fn first_group(&self) -> Group {
+        let mut end = 0;
+        while self.changed(end) {
+            end += 1;
+        }
+        Group { start: 0, end }
+    }
+
+    // Move to the next group, which may be empty, unless this one ends the file
+    fn next_group(&self, group: &mut Group) -> bool {
+        if group.end == self.lines.len() {
+            return false;
+        }
+        group.start = group.end + 1;
+        group.end = group.start;
+        while self.changed(group.end) {
+            group.end += 1;
+        }
+        true
+    }
+
+    fn previous_group(&self, group: &mut Group) -> bool {
+        if group.start == 0 {
+            return false;
+        }
+        group.end = group.start - 1;
+        group.start = group.end;
+        while group.start > 0 && self.changed(group.start - 1) {
+            group.start -= 1;
+        }
+        true
+    }
+
+    // Slide a group down a line if the line after it matches its first line, swallowing
+    // any group it runs into
+    fn slide_down(&mut self, group: &mut Group) -> bool {
+        if group.end < self.lines.len() && self.lines[group.start] == self.lines[group.end] {
+            self.changed[group.start] = false;
+            self.changed[group.end] = true;
+            group.start += 1;
+            group.end += 1;
+            while self.changed(group.end) {
+                group.end += 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn slide_up(&mut self, group: &mut Group) -> bool {
+        if group.start > 0 && self.lines[group.start - 1] == self.lines[group.end - 1] {
+            group.start -= 1;
+            group.end -= 1;
+            self.changed[group.start] = true;
+            self.changed[group.end] = false;
+            while group.start > 0 && self.changed(group.start - 1) {
+                group.start -= 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Slide the groups of changes on one side, keeping track of the matching groups on the other.
+/// Without the indent heuristic, groups that can't line up with the other side stay as far
+/// down as they go.
+pub fn compact(side: &mut Side, other: &Side, indent_heuristic: bool) {
+    let mut group = side.first_group();
+    let mut other_group = other.first_group();
+
+    loop {
+        if group.end != group.start {
+            let mut size;
+            let mut earliest_end;
+            let mut end_matching_other;
+
+            // Shift up and then down as far as possible, merging with any groups we hit,
+            // until the group stops growing
+            loop {
+                size = group.end - group.start;
+                end_matching_other = None;
+
+                while side.slide_up(&mut group) {
+                    other.previous_group(&mut other_group);
+                }
+                earliest_end = group.end;
+                if other_group.end > other_group.start {
+                    end_matching_other = Some(group.end);
+                }
+
+                while side.slide_down(&mut group) {
+                    other.next_group(&mut other_group);
+                    if other_group.end > other_group.start {
+                        end_matching_other = Some(group.end);
+                    }
+                }
+                if size == group.end - group.start {
+                    break;
+                }
+            }
+
+            if group.end == earliest_end {
+                // It can't move at all
+            } else if end_matching_other.is_some() {
+                // Line back up with the last change on the other side it can sit beside
+                while other_group.end == other_group.start {
+                    side.slide_up(&mut group);
+                    other.previous_group(&mut other_group);
+                }
+            } else if indent_heuristic {
+                let best_shift = best_shift(side, &group, size, earliest_end);
+                while group.end > best_shift {
+                    side.slide_up(&mut group);
+                    other.previous_group(&mut other_group);
+                }
+            }
+        }
+
+        if !side.next_group(&mut group) {
+            break;
+        }
+        other.next_group(&mut other_group);
+    }
+}
+
+// Where the end of a group that can slide reads best, scoring the splits it would leave
+// above and below itself by their indentation and blank lines
+fn best_shift(side: &Side, group: &Group, size: usize, earliest_end: usize) -> usize {
+    let lowest = earliest_end
+        .max(group.end.saturating_sub(size + 1))
+        .max(group.end.saturating_sub(MAX_SLIDING));
+
+    let mut best: Option<(usize, Score)> = None;
+    for shift in lowest..=group.end {
+        let mut score = Score::default();
+        score.add(&Split::measure(side.lines, shift));
+        score.add(&Split::measure(side.lines, shift - size));
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| score.compare(best) <= 0)
+        {
+            best = Some((shift, score));
+        }
+    }
+    best.map_or(group.end, |(shift, _)| shift)
+}
+
+// What surrounds a split between two lines
+struct Split {
+    end_of_file: bool,
+    // How far the line after the split is indented, or -1 if it's blank
+    indent: i32,
+    // How many blank lines are just above the split
+    pre_blank: i32,
+    // How far the closest non-blank line above is indented, or -1 if there isn't one
+    pre_indent: i32,
+    // How many blank lines follow the line after the split
+    post_blank: i32,
+    // How far the next non-blank line after that is indented, or -1 if there isn't one
+    post_indent: i32,
+}
+
+impl Split {
+    fn measure(lines: &[&[u8]], split: usize) -> Split {
+        let (end_of_file, indent) = match lines.get(split) {
+            Some(line) => (false, indent(line)),
+            None => (true, -1),
+        };
+
+        let (mut pre_blank, mut pre_indent) = (0, -1);
+        for line in lines[..split.min(lines.len())].iter().rev() {
+            pre_indent = self::indent(line);
+            if pre_indent != -1 {
+                break;
+            }
+            pre_blank += 1;
+            if pre_blank == MAX_BLANKS {
+                pre_indent = 0;
+                break;
+            }
+        }
+
+        let (mut post_blank, mut post_indent) = (0, -1);
+        for line in lines.iter().skip(split + 1) {
+            post_indent = self::indent(line);
+            if post_indent != -1 {
+                break;
+            }
+            post_blank += 1;
+            if post_blank == MAX_BLANKS {
+                post_indent = 0;
+                break;
+            }
+        }
+
+        Split {
+            end_of_file,
+            indent,
+            pre_blank,
+            pre_indent,
+            post_blank,
+            post_indent,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Score {
+    effective_indent: i32,
+    penalty: i32,
+}
+
+impl Score {
+    fn add(&mut self, split: &Split) {
+        if split.pre_indent == -1 && split.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if split.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = match split.indent {
+            -1 => 1 + split.post_blank,
+            _ => 0,
+        };
+        let total_blank = split.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+
+        let indent = match split.indent {
+            -1 => split.post_indent,
+            indent => indent,
+        };
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent;
+
+        if indent == -1 || split.pre_indent == -1 || indent == split.pre_indent {
+            return;
+        }
+        self.penalty += if indent > split.pre_indent {
+            match any_blanks {
+                true => RELATIVE_INDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_INDENT_PENALTY,
+            }
+        } else if split.post_indent != -1 && split.post_indent > indent {
+            match any_blanks {
+                true => RELATIVE_OUTDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_OUTDENT_PENALTY,
+            }
+        } else {
+            match any_blanks {
+                true => RELATIVE_DEDENT_WITH_BLANK_PENALTY,
+                false => RELATIVE_DEDENT_PENALTY,
+            }
+        };
+    }
+
+    fn compare(&self, other: &Score) -> i32 {
+        let indents = (self.effective_indent > other.effective_indent) as i32
+            - (self.effective_indent < other.effective_indent) as i32;
+        INDENT_WEIGHT * indents + (self.penalty - other.penalty)
+    }
+}
+
+// How far a line is indented, counting tabs to the next multiple of 8, or -1 if it's blank
+fn indent(line: &[u8]) -> i32 {
+    let mut indent = 0;
+    for &c in line {
+        // Like C's `isspace`, which counts vertical tabs too
+        if !c.is_ascii_whitespace() && c != 0x0b {
+            return indent;
+        }
+        match c {
+            b' ' => indent += 1,
+            b'\t' => indent += 8 - indent % 8,
+            _ => {}
+        }
+        if indent >= MAX_INDENT {
+            return MAX_INDENT;
+        }
+    }
+    -1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{edits, lines, Algorithm, Edit};
+
+    fn changes(old: &str, new: &str) -> Vec<Edit> {
+        edits(
+            &lines(old.as_bytes()),
+            &lines(new.as_bytes()),
+            Algorithm::Myers,
+        )
+        .into_iter()
+        .filter(|edit| !matches!(edit, Edit::Equal(..)))
+        .collect()
+    }
+
+    #[test]
+    fn slides_changes_as_far_down_as_they_go() {
+        assert_eq!(
+            changes("a\nb\n", "a\nb\na\nb\n"),
+            vec![Edit::Insert(2), Edit::Insert(3)]
+        );
+    }
+
+    #[test]
+    fn lines_changes_up_with_the_other_side() {
+        assert_eq!(
+            changes("x\na\nb\n", "a\nb\na\nb\n"),
+            vec![Edit::Delete(0), Edit::Insert(0), Edit::Insert(1)]
+        );
+    }
+
+    #[test]
+    fn places_changes_by_indentation() {
+        let old = "    }\n        start();\nfn run() {\n    }\n}\n";
+        let new = "    }\n    }\n        start();\nfn run() {\n    }\n}\n";
+        assert_eq!(changes(old, new), vec![Edit::Insert(0)]);
+    }
+}
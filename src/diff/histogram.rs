@@ -0,0 +1,163 @@
+use super::myers;
+use std::{collections::HashMap, ops::Range};
+
+// Lines appearing more often than this on the old side are never used to anchor a match
+const MAX_CHAIN_LENGTH: usize = 64;
+
+/// Match up lines with histogram diff, as git does it: anchor on the longest run of common
+/// lines built around the line that occurs least often on the old side, then handle each
+/// side of that run the same way. Gaps where every common line is too frequent use Myers.
+pub fn matches(old: &[&[u8]], new: &[&[u8]]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    histogram(old, new, 0..old.len(), 0..new.len(), &mut matches);
+    matches
+}
+
+// A run of lines common to both sides
+struct Run {
+    old: Range<usize>,
+    new: Range<usize>,
+}
+
+fn histogram(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    old_range: Range<usize>,
+    new_range: Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if old_range.is_empty() || new_range.is_empty() {
+        return;
+    }
+
+    match find_run(old, new, old_range.clone(), new_range.clone()) {
+        Some(Ok(run)) => {
+            histogram(
+                old,
+                new,
+                old_range.start..run.old.start,
+                new_range.start..run.new.start,
+                matches,
+            );
+            matches.extend(run.old.clone().zip(run.new.clone()));
+            histogram(
+                old,
+                new,
+                run.old.end..old_range.end,
+                run.new.end..new_range.end,
+                matches,
+            );
+        }
+        Some(Err(())) => {
+            let (a_start, b_start) = (old_range.start, new_range.start);
+            matches.extend(
+                myers::matches(&old[old_range], &new[new_range])
+                    .into_iter()
+                    .map(|(a, b)| (a + a_start, b + b_start)),
+            );
+        }
+        None => {}
+    }
+}
+
+// The run to anchor on, an error if there are common lines but they're all too frequent,
+// or nothing if the two sides have no lines in common at all
+fn find_run(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    old_range: Range<usize>,
+    new_range: Range<usize>,
+) -> Option<Result<Run, ()>> {
+    let mut occurrences: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    for a in old_range.clone() {
+        occurrences.entry(old[a]).or_default().push(a);
+    }
+    let count = |a: usize| occurrences[old[a]].len();
+
+    let mut best: Option<Run> = None;
+    let mut best_count = MAX_CHAIN_LENGTH + 1;
+    let mut has_common = false;
+    let mut b = new_range.start;
+
+    while b < new_range.end {
+        let mut next_b = b + 1;
+        let positions = match occurrences.get(new[b]) {
+            Some(positions) => positions,
+            None => {
+                b = next_b;
+                continue;
+            }
+        };
+        has_common = true;
+        if positions.len() > best_count {
+            b = next_b;
+            continue;
+        }
+
+        let mut candidates = positions.iter().copied().peekable();
+        while let Some(a) = candidates.next() {
+            let (mut old_start, mut new_start) = (a, b);
+            let (mut old_end, mut new_end) = (a + 1, b + 1);
+            let mut run_count = positions.len();
+
+            while old_start > old_range.start
+                && new_start > new_range.start
+                && old[old_start - 1] == new[new_start - 1]
+            {
+                old_start -= 1;
+                new_start -= 1;
+                if run_count > 1 {
+                    run_count = run_count.min(count(old_start));
+                }
+            }
+            while old_end < old_range.end && new_end < new_range.end && old[old_end] == new[new_end]
+            {
+                if run_count > 1 {
+                    run_count = run_count.min(count(old_end));
+                }
+                old_end += 1;
+                new_end += 1;
+            }
+
+            next_b = next_b.max(new_end);
+            // Like git, a lone line is no longer than having no run at all
+            let longer = best.as_ref().map_or(1, |best| best.old.len()) < old_end - old_start;
+            if longer || run_count < best_count {
+                best_count = run_count;
+                best = Some(Run {
+                    old: old_start..old_end,
+                    new: new_start..new_end,
+                });
+            }
+
+            // Skip occurrences already inside this run
+            while candidates.peek().is_some_and(|&next| next < old_end) {
+                candidates.next();
+            }
+        }
+        b = next_b;
+    }
+
+    match has_common && best_count > MAX_CHAIN_LENGTH {
+        true => Some(Err(())),
+        false => best.map(Ok),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::lines;
+
+    #[test]
+    fn anchors_on_the_rarest_lines() {
+        let old = lines(b"x\ny\nz\nx\n");
+        let new = lines(b"z\nx\ny\n");
+        assert_eq!(matches(&old, &new), vec![(2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn matches_nothing_without_common_lines() {
+        assert_eq!(matches(&lines(b"a\nb\n"), &lines(b"c\nd\n")), vec![]);
+    }
+}
@@ -0,0 +1,202 @@
+use super::{edits, lines, Algorithm, Edit};
+use std::ops::Range;
+
+// How much of a function line git shows after a hunk header
+const FUNCTION_LINE_LIMIT: usize = 80;
+
+/// Render the changes between two versions of a file as unified hunks, each with `context`
+/// unchanged lines around it, like the body of `git diff` after the `---` and `+++` lines
+pub fn unified(old: &[u8], new: &[u8], context: usize, algorithm: Algorithm) -> Vec<u8> {
+    let (old, new) = (lines(old), lines(new));
+    let edits = edits(&old, &new, algorithm);
+    let mut output = Vec::new();
+
+    for hunk_range in group(&edits, context) {
+        let hunk = &edits[hunk_range.clone()];
+        let (old_start, old_length) = span(hunk, |edit| match edit {
+            Edit::Equal(a, _) | Edit::Delete(a) => Some(*a),
+            Edit::Insert(_) => None,
+        });
+        let (new_start, new_length) = span(hunk, |edit| match edit {
+            Edit::Equal(_, b) | Edit::Insert(b) => Some(*b),
+            Edit::Delete(_) => None,
+        });
+        let old_start = old_start.unwrap_or_else(|| lines_before(&edits[..hunk_range.start], true));
+        let new_start =
+            new_start.unwrap_or_else(|| lines_before(&edits[..hunk_range.start], false));
+
+        output.extend_from_slice(
+            format!(
+                "@@ -{} +{} @@",
+                range(old_start, old_length),
+                range(new_start, new_length)
+            )
+            .as_bytes(),
+        );
+        let hunk_offset = match old_length {
+            0 => old_start,
+            _ => old_start - 1,
+        };
+        if let Some(function) = function_line(&old, hunk_offset) {
+            output.push(b' ');
+            output.extend_from_slice(function);
+        }
+        output.push(b'\n');
+
+        for edit in hunk {
+            let (marker, line) = match *edit {
+                Edit::Equal(a, _) => (b' ', old[a]),
+                Edit::Delete(a) => (b'-', old[a]),
+                Edit::Insert(b) => (b'+', new[b]),
+            };
+            output.push(marker);
+            output.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                output.extend_from_slice(b"\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    output
+}
+
+// Split an edit script into hunks of changes, each padded with up to `context` unchanged
+// lines on either side. Changes close enough that their context would touch share a hunk.
+fn group(edits: &[Edit], context: usize) -> Vec<Range<usize>> {
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < changes.len() {
+        let start = changes[index].saturating_sub(context);
+        let mut last = changes[index];
+        while index + 1 < changes.len() && changes[index + 1] - last <= 2 * context + 1 {
+            index += 1;
+            last = changes[index];
+        }
+        let end = (last + context + 1).min(edits.len());
+        hunks.push(start..end);
+        index += 1;
+    }
+    hunks
+}
+
+// The one-based number of the first line on one side of a hunk, and how many lines it has
+fn span(hunk: &[Edit], side: impl Fn(&Edit) -> Option<usize>) -> (Option<usize>, usize) {
+    let mut lines = hunk.iter().filter_map(side);
+    let first = lines.next();
+    (
+        first.map(|line| line + 1),
+        first.map_or(0, |_| lines.count() + 1),
+    )
+}
+
+// A side with no lines in a hunk is numbered by the last line before it, like `-3,0`
+fn lines_before(edits: &[Edit], old: bool) -> usize {
+    edits
+        .iter()
+        .filter(|edit| match edit {
+            Edit::Equal(..) => true,
+            Edit::Delete(_) => old,
+            Edit::Insert(_) => !old,
+        })
+        .count()
+}
+
+fn range(start: usize, length: usize) -> String {
+    match length {
+        1 => start.to_string(),
+        _ => format!("{},{}", start, length),
+    }
+}
+
+// Like git's default, the closest line before the hunk that starts with a letter, `_` or `$`
+fn function_line<'a>(old: &[&'a [u8]], hunk_offset: usize) -> Option<&'a [u8]> {
+    let line = old[..hunk_offset.min(old.len())]
+        .iter()
+        .rev()
+        .find(|line| matches!(line.first(), Some(c) if c.is_ascii_alphabetic() || *c == b'_' || *c == b'$'))?;
+    let line = &line[..line.len().min(FUNCTION_LINE_LIMIT)];
+    let end = line
+        .iter()
+        .rposition(|c| !c.is_ascii_whitespace())
+        .map_or(0, |last| last + 1);
+    Some(&line[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unified_text(old: &str, new: &str, context: usize) -> String {
+        String::from_utf8(unified(
+            old.as_bytes(),
+            new.as_bytes(),
+            context,
+            Algorithm::Myers,
+        ))
+        .unwrap()
+    }
+
+    fn numbered(range: std::ops::RangeInclusive<usize>) -> String {
+        range.map(|n| format!("  {}\n", n)).collect()
+    }
+
+    #[test]
+    fn shows_changes_with_context() {
+        let old = numbered(1..=10);
+        let new = old.replace("  5\n", "  five\n");
+        assert_eq!(
+            unified_text(&old, &new, 3),
+            "@@ -2,7 +2,7 @@\n   2\n   3\n   4\n-  5\n+  five\n   6\n   7\n   8\n"
+        );
+        assert_eq!(unified_text(&old, &new, 0), "@@ -5 +5 @@\n-  5\n+  five\n");
+    }
+
+    #[test]
+    fn joins_changes_whose_context_touches() {
+        let old = numbered(1..=20);
+        let close = old.replace("  2\n", "  two\n").replace("  9\n", "  nine\n");
+        assert_eq!(unified_text(&old, &close, 3).matches("@@ -").count(), 1);
+
+        let far = old.replace("  2\n", "  two\n").replace("  10\n", "  ten\n");
+        assert_eq!(
+            unified_text(&old, &far, 3),
+            "@@ -1,5 +1,5 @@\n   1\n-  2\n+  two\n   3\n   4\n   5\n\
+             @@ -7,7 +7,7 @@\n   7\n   8\n   9\n-  10\n+  ten\n   11\n   12\n   13\n"
+        );
+    }
+
+    #[test]
+    fn numbers_empty_sides_by_the_line_before() {
+        assert_eq!(unified_text("", "a\n", 3), "@@ -0,0 +1 @@\n+a\n");
+        assert_eq!(unified_text("a\nb\n", "", 3), "@@ -1,2 +0,0 @@\n-a\n-b\n");
+        assert_eq!(
+            unified_text("  1\n  2\n", "  1\n  2\n  3\n", 0),
+            "@@ -2,0 +3 @@\n+  3\n"
+        );
+    }
+
+    #[test]
+    fn marks_missing_newlines() {
+        assert_eq!(
+            unified_text("  1\n  2", "  1\n  2\n", 3),
+            "@@ -1,2 +1,2 @@\n   1\n-  2\n\\ No newline at end of file\n+  2\n"
+        );
+    }
+
+    #[test]
+    fn names_the_enclosing_function() {
+        let old = "fn main() {\n  1\n  2\n  3\n  4\n  5\n}\n";
+        let new = old.replace("  5\n", "  five\n");
+        assert!(unified_text(old, &new, 1).starts_with("@@ -5,3 +5,3 @@ fn main() {\n"));
+        assert_eq!(
+            unified_text("fn main() {\n  1\n}\n", "fn main() {\n  1\n  2\n}\n", 0),
+            "@@ -2,0 +3 @@ fn main() {\n+  2\n"
+        );
+    }
+}
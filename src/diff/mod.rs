@@ -0,0 +1,110 @@
+// Line-based differences between two versions of a file, rendered as unified hunks
+
+mod compact;
+mod histogram;
+mod hunks;
+mod myers;
+mod patience;
+
+use compact::{compact, Side};
+pub use hunks::unified;
+
+/// How to decide which lines to keep when two versions of a file differ
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Algorithm {
+    /// Git's default: Myers' shortest edit script, skipping lines unlikely to match well
+    #[default]
+    Myers,
+    /// Anchor on lines that appear once on each side
+    Patience,
+    /// Anchor on the lines that appear least often
+    Histogram,
+}
+
+impl Algorithm {
+    /// Find an algorithm by the name used for `diff.algorithm` and `--diff-algorithm`
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name.to_ascii_lowercase().as_str() {
+            "myers" | "default" | "minimal" => Some(Algorithm::Myers),
+            "patience" => Some(Algorithm::Patience),
+            "histogram" => Some(Algorithm::Histogram),
+            _ => None,
+        }
+    }
+}
+
+/// One step of an edit script turning the old lines into the new ones, holding the
+/// zero-based index of each line it touches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// The line at this index in the old version is kept as the line at this index in the new
+    Equal(usize, usize),
+    /// The line at this index in the old version is removed
+    Delete(usize),
+    /// The line at this index in the new version is added
+    Insert(usize),
+}
+
+/// Split content into lines, each keeping its `\n` (the last may not have one)
+pub fn lines(content: &[u8]) -> Vec<&[u8]> {
+    content.split_inclusive(|&byte| byte == b'\n').collect()
+}
+
+/// An edit script turning `old` into `new`. Runs of changes are slid into place the way
+/// git does it, and within each run, lines are deleted before any are inserted.
+pub fn edits(old: &[&[u8]], new: &[&[u8]], algorithm: Algorithm) -> Vec<Edit> {
+    edit_script(old, new, algorithm, true)
+}
+
+/// An edit script like `edits`, but without the indent heuristic, so runs that could go
+/// anywhere are slid as far down as they go. This is how git's merges see changes.
+pub fn plain_edits(old: &[&[u8]], new: &[&[u8]], algorithm: Algorithm) -> Vec<Edit> {
+    edit_script(old, new, algorithm, false)
+}
+
+fn edit_script(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    algorithm: Algorithm,
+    indent_heuristic: bool,
+) -> Vec<Edit> {
+    let matches = match algorithm {
+        Algorithm::Myers => myers::matches(old, new),
+        Algorithm::Patience => patience::matches(old, new),
+        Algorithm::Histogram => histogram::matches(old, new),
+    };
+
+    let mut old_changed = vec![true; old.len()];
+    let mut new_changed = vec![true; new.len()];
+    for (a, b) in matches {
+        old_changed[a] = false;
+        new_changed[b] = false;
+    }
+    let mut old_side = Side::new(old, old_changed);
+    let mut new_side = Side::new(new, new_changed);
+    compact(&mut old_side, &new_side, indent_heuristic);
+    compact(&mut new_side, &old_side, indent_heuristic);
+
+    let mut edits = Vec::new();
+    let (mut a, mut b) = (0, 0);
+    while a < old.len() || b < new.len() {
+        if a < old.len() && old_side.changed(a) {
+            edits.push(Edit::Delete(a));
+            a += 1;
+        } else if b < new.len() && new_side.changed(b) {
+            edits.push(Edit::Insert(b));
+            b += 1;
+        } else {
+            edits.push(Edit::Equal(a, b));
+            a += 1;
+            b += 1;
+        }
+    }
+    edits
+}
+
+/// Whether git would treat this content as binary: it has a NUL byte somewhere in its
+/// first 8000 bytes
+pub fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|&byte| byte == 0)
+}
@@ -0,0 +1,328 @@
+// Myers' algorithm the way git's xdiff runs it: trim what both ends share, set aside lines
+// that can't or needn't match, then split the rest at the middle of the shortest edit path,
+// working in from both ends at once, and handle each half the same way
+
+// Lines with no match on the other side, one match or several
+const NO_MATCH: u8 = 0;
+const MATCH: u8 = 1;
+const MANY_MATCHES: u8 = 2;
+
+// Limits on how much work goes into setting aside lines with many matches
+const MAX_EQUAL_LIMIT: usize = 1024;
+const SIMILAR_SCAN_WINDOW: usize = 100;
+const KEEP_MANY_MATCHES_RUN: usize = 4;
+
+/// The pairs of lines the edit script keeps, in order
+pub fn matches(old: &[&[u8]], new: &[&[u8]]) -> Vec<(usize, usize)> {
+    let mut old_changed = vec![false; old.len()];
+    let mut new_changed = vec![false; new.len()];
+
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_kept = keep(old, new, prefix..old.len() - suffix, &mut old_changed);
+    let new_kept = keep(new, old, prefix..new.len() - suffix, &mut new_changed);
+
+    let old_lines: Vec<&[u8]> = old_kept.iter().map(|&a| old[a]).collect();
+    let new_lines: Vec<&[u8]> = new_kept.iter().map(|&b| new[b]).collect();
+    let mut compare = Compare {
+        old: &old_lines,
+        new: &new_lines,
+        old_changed: vec![false; old_lines.len()],
+        new_changed: vec![false; new_lines.len()],
+        offset: new_lines.len() as isize + 1,
+        forward: vec![0; old_lines.len() + new_lines.len() + 3],
+        backward: vec![0; old_lines.len() + new_lines.len() + 3],
+    };
+    compare.compare(0, old_lines.len() as isize, 0, new_lines.len() as isize);
+
+    for (index, &a) in old_kept.iter().enumerate() {
+        old_changed[a] = compare.old_changed[index];
+    }
+    for (index, &b) in new_kept.iter().enumerate() {
+        new_changed[b] = compare.new_changed[index];
+    }
+
+    let old_unchanged = (0..old.len()).filter(|&a| !old_changed[a]);
+    let new_unchanged = (0..new.len()).filter(|&b| !new_changed[b]);
+    old_unchanged.zip(new_unchanged).collect()
+}
+
+// Mark lines in `range` with no match on the other side as changed, along with lines that
+// match too often and sit among unmatched ones, and give back the rest
+fn keep(
+    lines: &[&[u8]],
+    other: &[&[u8]],
+    range: std::ops::Range<usize>,
+    changed: &mut [bool],
+) -> Vec<usize> {
+    let limit = bogus_sqrt(lines.len()).min(MAX_EQUAL_LIMIT);
+    let matches: Vec<u8> = lines
+        .iter()
+        .map(
+            |line| match other.iter().filter(|other| other == &line).count() {
+                0 => NO_MATCH,
+                count if count >= limit => MANY_MATCHES,
+                _ => MATCH,
+            },
+        )
+        .collect();
+
+    let mut kept = Vec::new();
+    for line in range.clone() {
+        let keep = match matches[line] {
+            MATCH => true,
+            MANY_MATCHES => !among_unmatched(&matches, line, range.start, range.end - 1),
+            _ => false,
+        };
+        match keep {
+            true => kept.push(line),
+            false => changed[line] = true,
+        }
+    }
+    kept
+}
+
+// Whether a line with many matches is surrounded by enough lines without any that it's
+// probably not worth matching
+fn among_unmatched(matches: &[u8], line: usize, start: usize, end: usize) -> bool {
+    let start = start.max(line.saturating_sub(SIMILAR_SCAN_WINDOW));
+    let end = end.min(line + SIMILAR_SCAN_WINDOW);
+
+    let (mut unmatched_before, mut many_before) = (0, 1);
+    for &before in matches[start..line].iter().rev() {
+        match before {
+            NO_MATCH => unmatched_before += 1,
+            MANY_MATCHES => many_before += 1,
+            _ => break,
+        }
+    }
+    if unmatched_before == 0 {
+        return false;
+    }
+
+    let (mut unmatched_after, mut many_after) = (0, 1);
+    for &after in &matches[line + 1..=end] {
+        match after {
+            NO_MATCH => unmatched_after += 1,
+            MANY_MATCHES => many_after += 1,
+            _ => break,
+        }
+    }
+    if unmatched_after == 0 {
+        return false;
+    }
+
+    let unmatched = unmatched_before + unmatched_after;
+    let many = many_before + many_after;
+    many * KEEP_MANY_MATCHES_RUN < many + unmatched
+}
+
+// xdiff's rough square root: the power of two with half as many bits as `n`, rounded up
+fn bogus_sqrt(mut n: usize) -> usize {
+    let mut root = 1;
+    while n > 0 {
+        root <<= 1;
+        n >>= 2;
+    }
+    root
+}
+
+// The state for comparing the lines that were kept, with the furthest reaching positions on
+// each diagonal `k = x - y`, searching forward and backward
+struct Compare<'a> {
+    old: &'a [&'a [u8]],
+    new: &'a [&'a [u8]],
+    old_changed: Vec<bool>,
+    new_changed: Vec<bool>,
+    offset: isize,
+    forward: Vec<isize>,
+    backward: Vec<isize>,
+}
+
+impl<'a> Compare<'a> {
+    fn compare(
+        &mut self,
+        mut old_start: isize,
+        mut old_end: isize,
+        mut new_start: isize,
+        mut new_end: isize,
+    ) {
+        while old_start < old_end
+            && new_start < new_end
+            && self.old[old_start as usize] == self.new[new_start as usize]
+        {
+            old_start += 1;
+            new_start += 1;
+        }
+        while old_start < old_end
+            && new_start < new_end
+            && self.old[old_end as usize - 1] == self.new[new_end as usize - 1]
+        {
+            old_end -= 1;
+            new_end -= 1;
+        }
+
+        if old_start == old_end {
+            for b in new_start..new_end {
+                self.new_changed[b as usize] = true;
+            }
+        } else if new_start == new_end {
+            for a in old_start..old_end {
+                self.old_changed[a as usize] = true;
+            }
+        } else {
+            let (split_old, split_new) = self.split(old_start, old_end, new_start, new_end);
+            self.compare(old_start, split_old, new_start, split_new);
+            self.compare(split_old, old_end, split_new, new_end);
+        }
+    }
+
+    // Find where the shortest edit path through the box crosses its middle diagonal band
+    fn split(
+        &mut self,
+        old_start: isize,
+        old_end: isize,
+        new_start: isize,
+        new_end: isize,
+    ) -> (isize, isize) {
+        let (min_k, max_k) = (old_start - new_end, old_end - new_start);
+        let (forward_mid, backward_mid) = (old_start - new_start, old_end - new_end);
+        let odd = (forward_mid - backward_mid) & 1 != 0;
+        let (mut forward_min, mut forward_max) = (forward_mid, forward_mid);
+        let (mut backward_min, mut backward_max) = (backward_mid, backward_mid);
+
+        self.set_forward(forward_mid, old_start);
+        self.set_backward(backward_mid, old_end);
+
+        loop {
+            if forward_min > min_k {
+                forward_min -= 1;
+                self.set_forward(forward_min - 1, -1);
+            } else {
+                forward_min += 1;
+            }
+            if forward_max < max_k {
+                forward_max += 1;
+                self.set_forward(forward_max + 1, -1);
+            } else {
+                forward_max -= 1;
+            }
+
+            let mut k = forward_max;
+            while k >= forward_min {
+                let mut x = match self.get_forward(k - 1) >= self.get_forward(k + 1) {
+                    true => self.get_forward(k - 1) + 1,
+                    false => self.get_forward(k + 1),
+                };
+                let mut y = x - k;
+                while x < old_end && y < new_end && self.old[x as usize] == self.new[y as usize] {
+                    x += 1;
+                    y += 1;
+                }
+                self.set_forward(k, x);
+                if odd && backward_min <= k && k <= backward_max && self.get_backward(k) <= x {
+                    return (x, y);
+                }
+                k -= 2;
+            }
+
+            if backward_min > min_k {
+                backward_min -= 1;
+                self.set_backward(backward_min - 1, isize::MAX);
+            } else {
+                backward_min += 1;
+            }
+            if backward_max < max_k {
+                backward_max += 1;
+                self.set_backward(backward_max + 1, isize::MAX);
+            } else {
+                backward_max -= 1;
+            }
+
+            let mut k = backward_max;
+            while k >= backward_min {
+                let mut x = match self.get_backward(k - 1) < self.get_backward(k + 1) {
+                    true => self.get_backward(k - 1),
+                    false => self.get_backward(k + 1) - 1,
+                };
+                let mut y = x - k;
+                while x > old_start
+                    && y > new_start
+                    && self.old[x as usize - 1] == self.new[y as usize - 1]
+                {
+                    x -= 1;
+                    y -= 1;
+                }
+                self.set_backward(k, x);
+                if !odd && forward_min <= k && k <= forward_max && x <= self.get_forward(k) {
+                    return (x, y);
+                }
+                k -= 2;
+            }
+        }
+    }
+
+    fn get_forward(&self, k: isize) -> isize {
+        self.forward[(k + self.offset) as usize]
+    }
+
+    fn set_forward(&mut self, k: isize, x: isize) {
+        self.forward[(k + self.offset) as usize] = x;
+    }
+
+    fn get_backward(&self, k: isize) -> isize {
+        self.backward[(k + self.offset) as usize]
+    }
+
+    fn set_backward(&mut self, k: isize, x: isize) {
+        self.backward[(k + self.offset) as usize] = x;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::lines;
+
+    fn matches_text(old: &str, new: &str) -> Vec<(usize, usize)> {
+        matches(&lines(old.as_bytes()), &lines(new.as_bytes()))
+    }
+
+    #[test]
+    fn keeps_the_longest_common_subsequence() {
+        let kept = matches_text("a\nb\nc\na\nb\nb\na\n", "c\nb\na\nb\na\nc\n");
+        assert_eq!(kept.len(), 4);
+        assert!(kept
+            .windows(2)
+            .all(|pair| pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1));
+    }
+
+    #[test]
+    fn handles_empty_sides() {
+        assert_eq!(matches_text("", ""), vec![]);
+        assert_eq!(matches_text("", "a\nb\n"), vec![]);
+        assert_eq!(matches_text("a\nb\n", ""), vec![]);
+    }
+
+    #[test]
+    fn keeps_lines_that_differ_only_in_their_newline_apart() {
+        assert_eq!(matches_text("a\nb", "a\nb\n"), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn sets_aside_lines_that_only_match_too_often() {
+        let old = lines(b"a\nb\nc\nd\n{\ne\nf\ng\nh\n");
+        let new = lines(b"{\n{\n{\n{\n}\n");
+        let mut changed = [false; 9];
+        assert_eq!(keep(&old, &new, 0..9, &mut changed), vec![]);
+        assert!(changed.iter().all(|&changed| changed));
+
+        let new = lines(b"{\n{\n{\n{\nh\n");
+        assert_eq!(keep(&old, &new, 0..9, &mut [false; 9]), vec![8]);
+    }
+}
@@ -0,0 +1,146 @@
+use super::myers;
+use std::{collections::HashMap, ops::Range};
+
+/// Match up lines with patience diff: lines that appear exactly once on each side anchor the
+/// longest sequence of matches that keeps both sides in order, and the gaps between anchors
+/// are diffed the same way, falling back to Myers when a gap has no unique lines in common
+pub fn matches(old: &[&[u8]], new: &[&[u8]]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    patience(old, new, 0..old.len(), 0..new.len(), &mut matches);
+    matches
+}
+
+fn patience(
+    old: &[&[u8]],
+    new: &[&[u8]],
+    old_range: Range<usize>,
+    new_range: Range<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if old_range.is_empty() || new_range.is_empty() {
+        return;
+    }
+
+    // For each line on the old side: where it last appeared on each side, and how often
+    let mut counts: HashMap<&[u8], (usize, usize, usize, usize)> = HashMap::new();
+    let mut order = Vec::new();
+    for a in old_range.clone() {
+        let entry = counts.entry(old[a]).or_insert_with(|| {
+            order.push(old[a]);
+            (0, a, 0, 0)
+        });
+        entry.0 += 1;
+    }
+    let mut any_common = false;
+    for b in new_range.clone() {
+        if let Some(entry) = counts.get_mut(new[b]) {
+            any_common = true;
+            entry.2 += 1;
+            entry.3 = b;
+        }
+    }
+    if !any_common {
+        return;
+    }
+
+    let unique: Vec<(usize, usize)> = order
+        .iter()
+        .map(|line| counts[line])
+        .filter(|&(old_count, _, new_count, _)| old_count == 1 && new_count == 1)
+        .map(|(_, a, _, b)| (a, b))
+        .collect();
+    let anchors = longest_increasing(&unique);
+    if anchors.is_empty() {
+        let (a_start, b_start) = (old_range.start, new_range.start);
+        matches.extend(
+            myers::matches(&old[old_range], &new[new_range])
+                .into_iter()
+                .map(|(a, b)| (a + a_start, b + b_start)),
+        );
+        return;
+    }
+
+    let (mut a, mut b) = (old_range.start, new_range.start);
+    let mut anchors = anchors.into_iter().peekable();
+    loop {
+        let (mut next_a, mut next_b) = match anchors.peek() {
+            Some(&(anchor_a, anchor_b)) => (anchor_a, anchor_b),
+            None => (old_range.end, new_range.end),
+        };
+        // Grow the anchor backwards over lines that match anyway...
+        let mut grown = Vec::new();
+        if anchors.peek().is_some() {
+            while next_a > a && next_b > b && old[next_a - 1] == new[next_b - 1] {
+                next_a -= 1;
+                next_b -= 1;
+                grown.push((next_a, next_b));
+            }
+        }
+        // ...and the previous one forwards, before diffing what's left in between
+        while a < next_a && b < next_b && old[a] == new[b] {
+            matches.push((a, b));
+            a += 1;
+            b += 1;
+        }
+        patience(old, new, a..next_a, b..next_b, matches);
+        matches.extend(grown.into_iter().rev());
+
+        let (mut anchor_a, mut anchor_b) = match anchors.next() {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        matches.push((anchor_a, anchor_b));
+        while anchors.peek() == Some(&(anchor_a + 1, anchor_b + 1)) {
+            anchors.next();
+            anchor_a += 1;
+            anchor_b += 1;
+            matches.push((anchor_a, anchor_b));
+        }
+        a = anchor_a + 1;
+        b = anchor_b + 1;
+    }
+}
+
+// The longest run of pairs (in order of the first position) whose second positions increase,
+// found with patience sorting
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut tops: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+
+    for (index, &(_, b)) in pairs.iter().enumerate() {
+        let pile = tops.partition_point(|&top| pairs[top].1 < b);
+        previous.push(pile.checked_sub(1).map(|pile| tops[pile]));
+        match pile == tops.len() {
+            true => tops.push(index),
+            false => tops[pile] = index,
+        }
+    }
+
+    let mut sequence = Vec::new();
+    let mut current = tops.last().copied();
+    while let Some(index) = current {
+        sequence.push(pairs[index]);
+        current = previous[index];
+    }
+    sequence.reverse();
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::lines;
+
+    #[test]
+    fn anchors_on_unique_lines() {
+        let old = lines(b"a\n}\nb\n}\n");
+        let new = lines(b"a\n}\nx\n}\nb\n}\n");
+        assert_eq!(matches(&old, &new), vec![(0, 0), (1, 3), (2, 4), (3, 5)]);
+    }
+
+    #[test]
+    fn finds_the_longest_increasing_sequence() {
+        let pairs = vec![(0, 3), (1, 1), (2, 4), (3, 2), (4, 5)];
+        assert_eq!(longest_increasing(&pairs), vec![(1, 1), (3, 2), (4, 5)]);
+    }
+}
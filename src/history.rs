@@ -0,0 +1,190 @@
+use crate::objects::{self, Commit};
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+    io,
+};
+
+/// Whether `ancestor` can be reached by following parents back from `descendant`
+/// (a commit counts as its own ancestor)
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> Result<bool, io::Error> {
+    let mut seen = HashSet::new();
+    let mut pending = vec![descendant.to_string()];
+
+    while let Some(oid) = pending.pop() {
+        if oid == ancestor {
+            return Ok(true);
+        }
+        if seen.insert(oid.to_owned()) {
+            pending.append(&mut objects::read_commit(&oid)?.parents);
+        }
+    }
+    Ok(false)
+}
+
+/// The best common ancestors of two commits: every commit reachable from both that isn't
+/// itself an ancestor of another such commit, newest first. Criss-cross histories can have
+/// more than one; unrelated histories have none.
+pub fn merge_bases(one: &str, two: &str) -> Result<Vec<String>, io::Error> {
+    merge_bases_many(one, &[two.to_string()])
+}
+
+/// The best common ancestors of one commit and a merge of all the others (which doesn't have
+/// to exist), newest first: what `git merge-base` shows for more than two commits
+pub fn merge_bases_many(one: &str, others: &[String]) -> Result<Vec<String>, io::Error> {
+    let reachable_from_one = ancestors(vec![one.to_string()])?;
+    let common = ancestors(others.to_vec())?
+        .into_iter()
+        .filter(|oid| reachable_from_one.contains(oid))
+        .collect();
+    best(common)
+}
+
+/// The best common ancestors of every commit at once, as needed to merge them all together
+/// in an octopus merge
+pub fn octopus_merge_bases(commits: &[String]) -> Result<Vec<String>, io::Error> {
+    let mut bases: Vec<String> = commits.iter().take(1).cloned().collect();
+    for commit in commits.iter().skip(1) {
+        let mut next = Vec::new();
+        for base in &bases {
+            next.append(&mut merge_bases(commit, base)?);
+        }
+        bases = next;
+    }
+    independent(&bases)
+}
+
+/// The commits that can't be reached from any of the others, in their original order and
+/// without duplicates
+pub fn independent(commits: &[String]) -> Result<Vec<String>, io::Error> {
+    let mut unique: Vec<&String> = Vec::new();
+    for commit in commits {
+        if !unique.contains(&commit) {
+            unique.push(commit);
+        }
+    }
+
+    let mut independent = Vec::new();
+    for &commit in &unique {
+        let mut reachable = false;
+        for &other in unique.iter().filter(|&&other| other != commit) {
+            if is_ancestor(commit, other)? {
+                reachable = true;
+                break;
+            }
+        }
+        if !reachable {
+            independent.push(commit.to_owned());
+        }
+    }
+    Ok(independent)
+}
+
+// The common ancestors that aren't ancestors of each other, newest first. Anything reachable
+// from a common commit's parents is older than it, so it can't be best.
+fn best(common: HashSet<String>) -> Result<Vec<String>, io::Error> {
+    let mut parents = Vec::new();
+    for oid in &common {
+        parents.append(&mut objects::read_commit(oid)?.parents);
+    }
+    let redundant = ancestors(parents)?;
+
+    let mut bases = Vec::new();
+    for oid in common.into_iter().filter(|oid| !redundant.contains(oid)) {
+        let timestamp = objects::read_commit(&oid)?.committer.timestamp();
+        bases.push((timestamp, oid));
+    }
+    bases.sort_by(|a, b| b.cmp(a));
+    Ok(bases.into_iter().map(|(_, oid)| oid).collect())
+}
+
+// Every commit reachable from some starting points, including the starting points themselves
+fn ancestors(mut pending: Vec<String>) -> Result<HashSet<String>, io::Error> {
+    let mut seen = HashSet::new();
+    while let Some(oid) = pending.pop() {
+        if seen.insert(oid.to_owned()) {
+            pending.append(&mut objects::read_commit(&oid)?.parents);
+        }
+    }
+    Ok(seen)
+}
+
+/// Walks history back from some starting commits, newest first by committer date
+/// (ties go to whichever commit was found first), visiting each commit once
+pub struct RevList {
+    queue: BinaryHeap<Pending>,
+    seen: HashSet<String>,
+    found: usize,
+}
+
+struct Pending {
+    timestamp: i64,
+    found: usize,
+    oid: String,
+    commit: Commit,
+}
+
+impl RevList {
+    pub fn new(starts: &[String]) -> Result<RevList, io::Error> {
+        let mut rev_list = RevList {
+            queue: BinaryHeap::new(),
+            seen: HashSet::new(),
+            found: 0,
+        };
+        for oid in starts {
+            rev_list.enqueue(oid)?;
+        }
+        Ok(rev_list)
+    }
+
+    fn enqueue(&mut self, oid: &str) -> Result<(), io::Error> {
+        if !self.seen.insert(oid.to_string()) {
+            return Ok(());
+        }
+        let commit = objects::read_commit(oid)?;
+        self.queue.push(Pending {
+            timestamp: commit.committer.timestamp(),
+            found: self.found,
+            oid: oid.to_string(),
+            commit,
+        });
+        self.found += 1;
+        Ok(())
+    }
+}
+
+impl Iterator for RevList {
+    type Item = Result<(String, Commit), io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pending = self.queue.pop()?;
+        for parent in &pending.commit.parents {
+            if let Err(error) = self.enqueue(parent) {
+                return Some(Err(error));
+            }
+        }
+        Some(Ok((pending.oid, pending.commit)))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Pending) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then_with(|| other.found.cmp(&self.found))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Pending) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Pending) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
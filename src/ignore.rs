@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Holds ignore patterns and answers questions about whether stuff should be ignored
 pub struct Ignore {
@@ -19,22 +19,18 @@ impl Ignore {
     }
 
     /// Check to see if a given path should be ignored
-    pub fn ignore_item(&self, path: &PathBuf) -> bool {
+    pub fn ignore_item(&self, path: &Path) -> bool {
         let file_name: String = path
             .file_name()
             .expect("Couldn't get file name from path")
             .to_string_lossy()
-            .to_owned()
             .to_string();
         self.patterns.contains(&file_name)
     }
 
     /// Filter a list of paths to those that shouldn't be ignored
     pub fn ignore_items(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
-        paths
-            .into_iter()
-            .filter(|p| !self.ignore_item(&p))
-            .collect()
+        paths.into_iter().filter(|p| !self.ignore_item(p)).collect()
     }
 }
 
@@ -56,7 +52,7 @@ mod tests {
         let mut ignore = Ignore::new();
         ignore.add_pattern(String::from("README"));
         let should_ignore = ignore.ignore_item(&PathBuf::from("./haha/README"));
-        assert_eq!(should_ignore, true);
+        assert!(should_ignore);
     }
 
     #[test]
@@ -64,7 +60,7 @@ mod tests {
         let mut ignore = Ignore::new();
         ignore.add_pattern(String::from("README"));
         let should_ignore = ignore.ignore_item(&PathBuf::from("./haha/LICENSE"));
-        assert_eq!(should_ignore, false);
+        assert!(!should_ignore);
     }
 
     #[test]
@@ -77,7 +73,7 @@ mod tests {
         ];
         assert_eq!(ignore.patterns.len(), 1);
         assert_eq!(
-            ignore.ignore_items(files).get(0).unwrap().to_owned(),
+            ignore.ignore_items(files).first().unwrap().to_owned(),
             PathBuf::from("./haha/LICENSE")
         );
     }
@@ -99,7 +95,7 @@ mod tests {
 
         assert_eq!(ignore.patterns.len(), 2);
         assert_eq!(
-            remaining_files.get(0).unwrap().to_owned(),
+            remaining_files.first().unwrap().to_owned(),
             PathBuf::from("./haha/WAT")
         );
 
@@ -0,0 +1,539 @@
+use crate::{
+    lockfile::Lockfile,
+    objects::{mode_for_metadata, Blob, Object},
+    workspace,
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, Metadata},
+    io,
+    os::unix::fs::MetadataExt,
+    path::PathBuf,
+};
+
+const SIGNATURE: &[u8] = b"DIRC";
+const VERSION: u32 = 2;
+const HEADER_SIZE: usize = 12;
+const ENTRY_FIXED_SIZE: usize = 62;
+const CHECKSUM_SIZE: usize = 20;
+const MAX_PATH_SIZE: usize = 0xfff;
+const STAGE_SHIFT: u16 = 12;
+const EXTENDED_FLAG: u16 = 0x4000;
+
+/// Git's staging area, kept in `.git/index` using git's `DIRC` version 2 format
+#[derive(Debug, Default)]
+pub struct Index {
+    /// Entries keyed by path and stage, which is the order git keeps them in
+    entries: BTreeMap<(String, u8), Entry>,
+    lockfile: Option<Lockfile>,
+    /// When the index file was last written, in seconds, if it was there to be read
+    timestamp: Option<u32>,
+}
+
+/// A file in the staging area, with the stat data used to spot changes in the workspace
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub ctime: u32,
+    pub ctime_nsec: u32,
+    pub mtime: u32,
+    pub mtime_nsec: u32,
+    pub dev: u32,
+    pub ino: u32,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u32,
+    pub oid: String,
+    pub flags: u16,
+    pub path: String,
+}
+
+impl Index {
+    /// Read the index for the repository in the current directory, which may not exist yet
+    pub fn load() -> Result<Index, io::Error> {
+        let mut index = Index::default();
+        match fs::read(index_path()) {
+            Ok(bytes) => {
+                for entry in Index::parse(&bytes)? {
+                    index.insert(entry);
+                }
+                index.timestamp = Some(fs::metadata(index_path())?.mtime() as u32);
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        Ok(index)
+    }
+
+    /// Lock the index against other writers, then read it
+    pub fn load_for_update() -> Result<Index, io::Error> {
+        let lockfile = Lockfile::acquire(&index_path())?;
+        let mut index = Index::load()?;
+        index.lockfile = Some(lockfile);
+        Ok(index)
+    }
+
+    /// Write the index back to disk and release the lock taken by `load_for_update`, first
+    /// smudging entries whose stat data can't be trusted, like git does
+    pub fn write_updates(mut self) -> Result<(), io::Error> {
+        let mut lockfile = self.lockfile.take().ok_or_else(|| {
+            io::Error::other("The index must be loaded for update before it's written")
+        })?;
+        self.smudge_racily_clean_entries();
+        lockfile.write(&self.serialize())?;
+        lockfile.commit()
+    }
+
+    /// Stage a file, replacing anything it conflicts with: an entry at the same path,
+    /// any of its parent directories that were files, or anything inside it if it was a directory
+    pub fn add(&mut self, entry: Entry) {
+        for parent in parent_directories(&entry.path) {
+            self.remove_exact(&parent);
+        }
+        self.remove(&entry.path);
+        self.insert(entry);
+    }
+
+    /// Unstage a path, along with everything inside it if it's a directory
+    pub fn remove(&mut self, path: &str) {
+        self.remove_exact(path);
+        let prefix = format!("{}/", path);
+        let children: Vec<(String, u8)> = self
+            .entries
+            .range((prefix.clone(), 0)..)
+            .take_while(|((child, _), _)| child.starts_with(&prefix))
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        for child in children {
+            self.entries.remove(&child);
+        }
+    }
+
+    /// Put an entry in place without resolving conflicts, keeping any other stages of its path
+    pub fn insert(&mut self, entry: Entry) {
+        self.entries
+            .insert((entry.path.clone(), entry.stage()), entry);
+    }
+
+    /// Replace whatever is staged at a path with the sides of a merge conflict, each entry
+    /// holding its stage (see `Entry::conflicted`)
+    pub fn add_conflict(&mut self, path: &str, sides: Vec<Entry>) {
+        self.remove_exact(path);
+        for entry in sides {
+            self.insert(entry);
+        }
+    }
+
+    /// All entries, in the order git stores them
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// The merged (stage 0) entry for a path
+    pub fn entry(&self, path: &str) -> Option<&Entry> {
+        self.entries.get(&(path.to_string(), 0))
+    }
+
+    /// Whether a path is in the index as a file, at any stage
+    pub fn is_tracked_file(&self, path: &str) -> bool {
+        (0..=3).any(|stage| self.entries.contains_key(&(path.to_string(), stage)))
+    }
+
+    /// Whether anything in the index lives inside a directory
+    pub fn is_tracked_directory(&self, path: &str) -> bool {
+        let prefix = format!("{}/", path);
+        self.entries
+            .range((prefix.clone(), 0)..)
+            .next()
+            .is_some_and(|((child, _), _)| child.starts_with(&prefix))
+    }
+
+    /// Refresh the stat data cached for a path, once it's known to match the workspace
+    pub fn update_stat(&mut self, path: &str, metadata: &Metadata) {
+        if let Some(entry) = self.entries.get_mut(&(path.to_string(), 0)) {
+            entry.update_stat(metadata);
+        }
+    }
+
+    /// Whether a lock is held, so changes can be written with `write_updates`
+    pub fn is_locked(&self) -> bool {
+        self.lockfile.is_some()
+    }
+
+    // A file changed in the same second the index was last written can change again without
+    // its stat data showing it, and once the index is rewritten, nothing would tell git to look
+    // closer. So like git's `ce_smudge_racily_clean_entry`, an entry like that whose file
+    // doesn't match it gets a size of 0, which makes anyone reading the index hash the file.
+    // Git compares timestamps to the second, so this does too.
+    fn smudge_racily_clean_entries(&mut self) {
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None => return,
+        };
+        for entry in self.entries.values_mut() {
+            if entry.stage() != 0 || entry.mtime < timestamp {
+                continue;
+            }
+            let metadata = match workspace::stat(&entry.path) {
+                Ok(metadata) if !metadata.is_dir() => metadata,
+                _ => continue,
+            };
+            // The size or mode already shows the change
+            if entry.stat_differs(&metadata) {
+                continue;
+            }
+            if Blob::from_file(workspace::root().join(&entry.path)).id() != entry.oid {
+                entry.size = 0;
+            }
+        }
+    }
+
+    // Remove every stage of exactly this path
+    fn remove_exact(&mut self, path: &str) {
+        for stage in 0..=3 {
+            self.entries.remove(&(path.to_string(), stage));
+        }
+    }
+
+    /// Parse the bytes of an index file, checking its header and checksum
+    pub fn parse(bytes: &[u8]) -> Result<Vec<Entry>, io::Error> {
+        if bytes.len() < HEADER_SIZE + CHECKSUM_SIZE {
+            return Err(invalid_data("Index file is too small"));
+        }
+        let (data, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+        if sha1::Sha1::from(data).digest().bytes() != checksum {
+            return Err(invalid_data("Index checksum doesn't match its content"));
+        }
+        if &data[0..4] != SIGNATURE {
+            return Err(invalid_data("Index signature isn't DIRC"));
+        }
+        let version = read_u32(data, 4);
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "Index version {} isn't supported",
+                version
+            )));
+        }
+
+        let count = read_u32(data, 8) as usize;
+        let mut entries = Vec::with_capacity(count);
+        let mut offset = HEADER_SIZE;
+        for _ in 0..count {
+            let (entry, size) = Entry::parse(&data[offset..])?;
+            entries.push(entry);
+            offset += size;
+        }
+
+        // Extensions follow the entries; ones starting with a capital letter are optional
+        // caches (like `TREE`) that are safe to drop, anything else we can't handle
+        while offset < data.len() {
+            if data.len() < offset + 8 {
+                return Err(invalid_data("Index extension is truncated"));
+            }
+            let signature = &data[offset..offset + 4];
+            if !signature[0].is_ascii_uppercase() {
+                return Err(invalid_data(format!(
+                    "Index extension {} isn't supported",
+                    String::from_utf8_lossy(signature)
+                )));
+            }
+            offset += 8 + read_u32(data, offset + 4) as usize;
+        }
+
+        Ok(entries)
+    }
+
+    /// Format the index for persistence, checksum and all
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(SIGNATURE);
+        bytes.extend_from_slice(&VERSION.to_be_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+        for entry in self.entries() {
+            bytes.append(&mut entry.serialize());
+        }
+        let checksum = sha1::Sha1::from(&bytes).digest().bytes();
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+}
+
+impl Entry {
+    /// Build a stage 0 entry for a file in the workspace
+    pub fn new(path: &str, oid: &str, metadata: &Metadata) -> Entry {
+        let mut entry = Entry {
+            ctime: 0,
+            ctime_nsec: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            dev: 0,
+            ino: 0,
+            mode: u32::from_str_radix(mode_for_metadata(metadata), 8).unwrap(),
+            uid: 0,
+            gid: 0,
+            size: 0,
+            oid: oid.to_string(),
+            flags: path.len().min(MAX_PATH_SIZE) as u16,
+            path: path.to_string(),
+        };
+        entry.update_stat(metadata);
+        entry
+    }
+
+    /// Build an entry for one side of a merge conflict, which has no stat data since it
+    /// isn't what's in the workspace
+    pub fn conflicted(path: &str, oid: &str, mode: &str, stage: u8) -> Entry {
+        Entry {
+            ctime: 0,
+            ctime_nsec: 0,
+            mtime: 0,
+            mtime_nsec: 0,
+            dev: 0,
+            ino: 0,
+            mode: u32::from_str_radix(mode, 8).unwrap_or(0o100644),
+            uid: 0,
+            gid: 0,
+            size: 0,
+            oid: oid.to_string(),
+            flags: ((stage as u16 & 0b11) << STAGE_SHIFT) | path.len().min(MAX_PATH_SIZE) as u16,
+            path: path.to_string(),
+        }
+    }
+
+    /// Refresh the cached stat data from the file's current metadata
+    pub fn update_stat(&mut self, metadata: &Metadata) {
+        self.ctime = metadata.ctime() as u32;
+        self.ctime_nsec = metadata.ctime_nsec() as u32;
+        self.mtime = metadata.mtime() as u32;
+        self.mtime_nsec = metadata.mtime_nsec() as u32;
+        self.dev = metadata.dev() as u32;
+        self.ino = metadata.ino() as u32;
+        self.uid = metadata.uid();
+        self.gid = metadata.gid();
+        self.size = metadata.size() as u32;
+    }
+
+    /// Whether the cached stat data says the file could have changed without hashing it,
+    /// because its size or mode no longer match
+    pub fn stat_differs(&self, metadata: &Metadata) -> bool {
+        self.size != metadata.size() as u32
+            || self.mode != u32::from_str_radix(mode_for_metadata(metadata), 8).unwrap()
+    }
+
+    /// Whether the file's timestamps match the cached stat data, so it's very likely unchanged
+    pub fn times_match(&self, metadata: &Metadata) -> bool {
+        self.ctime == metadata.ctime() as u32
+            && self.ctime_nsec == metadata.ctime_nsec() as u32
+            && self.mtime == metadata.mtime() as u32
+            && self.mtime_nsec == metadata.mtime_nsec() as u32
+    }
+
+    /// Which side of a merge conflict this entry is: 0 when merged, 1 to 3 when conflicted
+    pub fn stage(&self) -> u8 {
+        ((self.flags >> STAGE_SHIFT) & 0b11) as u8
+    }
+
+    /// The entry's mode the way trees write it, like `100644`
+    pub fn tree_mode(&self) -> String {
+        format!("{:o}", self.mode)
+    }
+
+    // Parse an entry from the start of `bytes`, also giving back how many bytes it took up
+    fn parse(bytes: &[u8]) -> Result<(Entry, usize), io::Error> {
+        if bytes.len() < ENTRY_FIXED_SIZE + 1 {
+            return Err(invalid_data("Index entry is truncated"));
+        }
+        let flags = u16::from_be_bytes([bytes[60], bytes[61]]);
+        if flags & EXTENDED_FLAG != 0 {
+            return Err(invalid_data(
+                "Index entries with extended flags aren't supported",
+            ));
+        }
+        let path_length = bytes[ENTRY_FIXED_SIZE..]
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| invalid_data("Index entry path isn't terminated"))?;
+        let path = &bytes[ENTRY_FIXED_SIZE..ENTRY_FIXED_SIZE + path_length];
+        let size = padded_size(path_length);
+        if bytes.len() < size {
+            return Err(invalid_data("Index entry is truncated"));
+        }
+
+        let entry = Entry {
+            ctime: read_u32(bytes, 0),
+            ctime_nsec: read_u32(bytes, 4),
+            mtime: read_u32(bytes, 8),
+            mtime_nsec: read_u32(bytes, 12),
+            dev: read_u32(bytes, 16),
+            ino: read_u32(bytes, 20),
+            mode: read_u32(bytes, 24),
+            uid: read_u32(bytes, 28),
+            gid: read_u32(bytes, 32),
+            size: read_u32(bytes, 36),
+            oid: hex::encode(&bytes[40..60]),
+            flags,
+            path: String::from_utf8_lossy(path).into_owned(),
+        };
+        Ok((entry, size))
+    }
+
+    // Format the entry, padded with NULs to a multiple of eight bytes
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for field in &[
+            self.ctime,
+            self.ctime_nsec,
+            self.mtime,
+            self.mtime_nsec,
+            self.dev,
+            self.ino,
+            self.mode,
+            self.uid,
+            self.gid,
+            self.size,
+        ] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.append(&mut hex::decode(&self.oid).expect("Invalid hex ID"));
+        bytes.extend_from_slice(&self.flags.to_be_bytes());
+        bytes.extend_from_slice(self.path.as_bytes());
+        bytes.resize(padded_size(self.path.len()), 0);
+        bytes
+    }
+}
+
+// Every directory a path is inside of, like `a` and `a/b` for `a/b/c.txt`
+fn parent_directories(path: &str) -> Vec<String> {
+    path.match_indices('/')
+        .map(|(index, _)| path[..index].to_string())
+        .collect()
+}
+
+// An entry's size on disk: the fixed fields, the path, and at least one NUL up to a multiple of 8
+fn padded_size(path_length: usize) -> usize {
+    (ENTRY_FIXED_SIZE + path_length + 8) / 8 * 8
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_be_bytes(word)
+}
+
+fn index_path() -> PathBuf {
+    let current_dir = env::current_dir().expect("Couldn't get current directory");
+    current_dir.join(".git").join("index")
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> Entry {
+        Entry {
+            ctime: 1,
+            ctime_nsec: 2,
+            mtime: 3,
+            mtime_nsec: 4,
+            dev: 5,
+            ino: 6,
+            mode: 0o100644,
+            uid: 7,
+            gid: 8,
+            size: 9,
+            oid: String::from("5ab2f8a4323abafb10abb68657d9d39f1a775057"),
+            flags: path.len() as u16,
+            path: path.to_string(),
+        }
+    }
+
+    fn paths(index: &Index) -> Vec<&str> {
+        index.entries().map(|entry| entry.path.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_what_it_serializes() {
+        let mut index = Index::default();
+        index.add(entry("b.txt"));
+        index.add(entry("a/nested/file.rs"));
+        index.add(entry("a.txt"));
+
+        let bytes = index.serialize();
+        let parsed = Index::parse(&bytes).expect("Couldn't parse the index");
+
+        assert_eq!(parsed, index.entries().cloned().collect::<Vec<Entry>>());
+        assert_eq!(paths(&index), vec!["a.txt", "a/nested/file.rs", "b.txt"]);
+        // 12 byte header, 72 + 80 + 72 bytes of entries, 20 byte checksum
+        assert_eq!(bytes.len(), 12 + 72 + 80 + 72 + 20);
+    }
+
+    #[test]
+    fn refuses_a_bad_checksum() {
+        let mut index = Index::default();
+        index.add(entry("a.txt"));
+        let mut bytes = index.serialize();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(Index::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn replaces_directories_and_files_that_conflict() {
+        let mut index = Index::default();
+        index.add(entry("alice.txt"));
+        index.add(entry("nested/bob.txt"));
+        index.add(entry("nested/inner/claire.txt"));
+
+        index.add(entry("alice.txt/nested.txt"));
+        index.add(entry("nested"));
+
+        assert_eq!(paths(&index), vec!["alice.txt/nested.txt", "nested"]);
+    }
+
+    #[test]
+    fn keeps_conflict_stages_in_order() {
+        let mut index = Index::default();
+        for stage in &[3, 1, 2] {
+            let mut conflicted = entry("conflicted.txt");
+            conflicted.flags |= stage << STAGE_SHIFT;
+            index.insert(conflicted);
+        }
+        let stages: Vec<u8> = index.entries().map(|entry| entry.stage()).collect();
+        assert_eq!(stages, vec![1, 2, 3]);
+        assert!(index.is_tracked_file("conflicted.txt"));
+        assert!(index.entry("conflicted.txt").is_none());
+
+        index.add(entry("conflicted.txt"));
+        let stages: Vec<u8> = index.entries().map(|entry| entry.stage()).collect();
+        assert_eq!(stages, vec![0]);
+    }
+
+    #[test]
+    fn replaces_a_merged_entry_with_conflict_stages() {
+        let mut index = Index::default();
+        index.add(entry("conflicted.txt"));
+        let oid = "5ab2f8a4323abafb10abb68657d9d39f1a775057";
+        index.add_conflict(
+            "conflicted.txt",
+            vec![
+                Entry::conflicted("conflicted.txt", oid, "100644", 1),
+                Entry::conflicted("conflicted.txt", oid, "100755", 3),
+            ],
+        );
+
+        let stages: Vec<(u8, String)> = index
+            .entries()
+            .map(|entry| (entry.stage(), entry.tree_mode()))
+            .collect();
+        assert_eq!(
+            stages,
+            vec![(1, String::from("100644")), (3, String::from("100755"))]
+        );
+        assert!(index.entry("conflicted.txt").is_none());
+    }
+}
@@ -1,6 +1,16 @@
 pub mod commands;
 pub mod compression;
 mod config;
+mod diff;
+pub mod history;
 mod ignore;
-mod objects;
+pub mod index;
+mod lockfile;
+mod merge;
+mod migration;
+pub mod objects;
 mod refs;
+mod revision;
+mod status;
+mod wildmatch;
+mod workspace;
@@ -0,0 +1,108 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Guards a file against concurrent writers the way git does, with a `<file>.lock` sibling.
+/// Changes are written to the lock and only replace the real file on `commit`.
+#[derive(Debug)]
+pub struct Lockfile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    lock: Option<File>,
+}
+
+impl Lockfile {
+    /// Take the lock for a file, failing if somebody else already holds it
+    pub fn acquire(path: &Path) -> Result<Lockfile, io::Error> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+
+        let lock = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::AlreadyExists => io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "Unable to create '{}': File exists. Another git process seems to be running",
+                        lock_path.display()
+                    ),
+                ),
+                _ => error,
+            })?;
+
+        Ok(Lockfile {
+            path: path.to_path_buf(),
+            lock_path,
+            lock: Some(lock),
+        })
+    }
+
+    /// Add bytes to what will become the new file
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), io::Error> {
+        match self.lock.as_mut() {
+            Some(lock) => lock.write_all(bytes),
+            None => Err(stale_lock(&self.lock_path)),
+        }
+    }
+
+    /// Replace the real file with what was written to the lock, releasing it
+    pub fn commit(mut self) -> Result<(), io::Error> {
+        match self.lock.take() {
+            Some(lock) => {
+                lock.sync_all()?;
+                fs::rename(&self.lock_path, &self.path)
+            }
+            None => Err(stale_lock(&self.lock_path)),
+        }
+    }
+}
+
+impl Drop for Lockfile {
+    // A lock that's dropped without being committed is rolled back, so a failure part
+    // way through an update never leaves the repository locked
+    fn drop(&mut self) {
+        if self.lock.take().is_some() {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+fn stale_lock(lock_path: &Path) -> io::Error {
+    io::Error::other(format!("Not holding lock on {}", lock_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use uuid::Uuid;
+
+    #[test]
+    fn replaces_the_file_on_commit() {
+        let path = env::temp_dir().join(format!("grit_lockfile_{}", Uuid::new_v4()));
+        fs::write(&path, "before").unwrap();
+
+        let mut lockfile = Lockfile::acquire(&path).unwrap();
+        lockfile.write(b"after").unwrap();
+        assert!(
+            Lockfile::acquire(&path).is_err(),
+            "Took a lock that was already held"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "before");
+
+        lockfile.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after");
+
+        let mut abandoned = Lockfile::acquire(&path).unwrap();
+        abandoned.write(b"never mind").unwrap();
+        drop(abandoned);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after");
+        Lockfile::acquire(&path).expect("Dropping a lock didn't release it");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+// A port of git's three-way line merge (xdiff's `xdl_merge`), at the zealous level git uses
+// for merges: both sides are diffed against the base, changes that overlap become conflicts,
+// and each conflict is then narrowed down by diffing the two sides against each other
+
+use crate::diff::{self, Algorithm, Edit};
+
+const MARKER_SIZE: usize = 7;
+
+/// The result of merging one file's content
+#[derive(Debug, PartialEq)]
+pub struct Merged {
+    pub content: Vec<u8>,
+    /// Whether the sides merged without leaving any conflict markers
+    pub clean: bool,
+}
+
+// A run of lines changed on one side, starting at `base` for `base_count` lines in the base
+// and at `side` for `side_count` lines on that side
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    base: isize,
+    base_count: isize,
+    side: isize,
+    side_count: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Conflict,
+    Ours,
+    Theirs,
+    /// A conflict that turned out to change both sides the same way
+    Identical,
+}
+
+// A region of the merge, with where it starts and how long it is in each of the three files
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    mode: Mode,
+    base: isize,
+    base_count: isize,
+    ours: isize,
+    ours_count: isize,
+    theirs: isize,
+    theirs_count: isize,
+}
+
+/// Merge the changes `ours` and `theirs` each made to `base`, wrapping anything they both
+/// changed differently in conflict markers labelled with `labels`
+pub fn merge(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    labels: (&str, &str),
+    algorithm: Algorithm,
+) -> Merged {
+    let (base, ours, theirs) = (diff::lines(base), diff::lines(ours), diff::lines(theirs));
+    let mut chunks = chunks(&base, &ours, &theirs, algorithm);
+    refine_conflicts(&mut chunks, &ours, &theirs, algorithm);
+    join_conflicts(&mut chunks);
+
+    Merged {
+        content: fill(&chunks, &ours, &theirs, labels),
+        clean: chunks.iter().all(|chunk| chunk.mode != Mode::Conflict),
+    }
+}
+
+// Every run of changes from the base to one side
+fn changes(base: &[&[u8]], side: &[&[u8]], algorithm: Algorithm) -> Vec<Change> {
+    let mut changes: Vec<Change> = Vec::new();
+    let (mut a, mut b) = (0, 0);
+    let mut in_change = false;
+    for edit in diff::plain_edits(base, side, algorithm) {
+        if let Edit::Equal(..) = edit {
+            in_change = false;
+            a += 1;
+            b += 1;
+            continue;
+        }
+        if !in_change {
+            changes.push(Change {
+                base: a,
+                base_count: 0,
+                side: b,
+                side_count: 0,
+            });
+            in_change = true;
+        }
+        let change = changes.last_mut().expect("A change was just started");
+        match edit {
+            Edit::Delete(_) => {
+                change.base_count += 1;
+                a += 1;
+            }
+            _ => {
+                change.side_count += 1;
+                b += 1;
+            }
+        }
+    }
+    changes
+}
+
+// Line up the changes each side made to the base, as regions taken from one side or the
+// other, and conflicts where they overlap
+fn chunks(base: &[&[u8]], ours: &[&[u8]], theirs: &[&[u8]], algorithm: Algorithm) -> Vec<Chunk> {
+    let ours_changes = changes(base, ours, algorithm);
+    let theirs_changes = changes(base, theirs, algorithm);
+    let mut chunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < ours_changes.len() && j < theirs_changes.len() {
+        let (x, y) = (ours_changes[i], theirs_changes[j]);
+        if x.base + x.base_count < y.base {
+            let theirs_start = y.side - y.base + x.base;
+            append(
+                &mut chunks,
+                Mode::Ours,
+                x.base,
+                x.base_count,
+                x.side,
+                x.side_count,
+                theirs_start,
+                x.base_count,
+            );
+            i += 1;
+            continue;
+        }
+        if y.base + y.base_count < x.base {
+            let ours_start = x.side - x.base + y.base;
+            append(
+                &mut chunks,
+                Mode::Theirs,
+                y.base,
+                y.base_count,
+                ours_start,
+                y.base_count,
+                y.side,
+                y.side_count,
+            );
+            j += 1;
+            continue;
+        }
+
+        let identical = x.base == y.base
+            && x.base_count == y.base_count
+            && x.side_count == y.side_count
+            && same_lines(ours, x.side, theirs, y.side, x.side_count);
+        if !identical {
+            let offset = x.base - y.base;
+            let end_offset = offset + x.base_count - y.base_count;
+            let (mut base_start, mut ours_start, mut theirs_start) = (x.base, x.side, y.side);
+            if offset > 0 {
+                base_start -= offset;
+                ours_start -= offset;
+            } else {
+                theirs_start += offset;
+            }
+            let mut base_count = x.base + x.base_count - base_start;
+            let mut ours_count = x.side + x.side_count - ours_start;
+            let mut theirs_count = y.side + y.side_count - theirs_start;
+            if end_offset < 0 {
+                base_count -= end_offset;
+                ours_count -= end_offset;
+            } else {
+                theirs_count += end_offset;
+            }
+            append(
+                &mut chunks,
+                Mode::Conflict,
+                base_start,
+                base_count,
+                ours_start,
+                ours_count,
+                theirs_start,
+                theirs_count,
+            );
+        }
+
+        let (ours_end, theirs_end) = (x.base + x.base_count, y.base + y.base_count);
+        if ours_end >= theirs_end {
+            j += 1;
+        }
+        if theirs_end >= ours_end {
+            i += 1;
+        }
+    }
+
+    let ours_shift = ours.len() as isize - base.len() as isize;
+    let theirs_shift = theirs.len() as isize - base.len() as isize;
+    for x in &ours_changes[i..] {
+        append(
+            &mut chunks,
+            Mode::Ours,
+            x.base,
+            x.base_count,
+            x.side,
+            x.side_count,
+            x.base + theirs_shift,
+            x.base_count,
+        );
+    }
+    for y in &theirs_changes[j..] {
+        append(
+            &mut chunks,
+            Mode::Theirs,
+            y.base,
+            y.base_count,
+            y.base + ours_shift,
+            y.base_count,
+            y.side,
+            y.side_count,
+        );
+    }
+    chunks
+}
+
+// Add a chunk, folding it into the previous one when they overlap on either side
+#[allow(clippy::too_many_arguments)]
+fn append(
+    chunks: &mut Vec<Chunk>,
+    mode: Mode,
+    base: isize,
+    base_count: isize,
+    ours: isize,
+    ours_count: isize,
+    theirs: isize,
+    theirs_count: isize,
+) {
+    if let Some(last) = chunks.last_mut() {
+        if ours <= last.ours + last.ours_count || theirs <= last.theirs + last.theirs_count {
+            if mode != last.mode {
+                last.mode = Mode::Conflict;
+            }
+            last.base_count = base + base_count - last.base;
+            last.ours_count = ours + ours_count - last.ours;
+            last.theirs_count = theirs + theirs_count - last.theirs;
+            return;
+        }
+    }
+    chunks.push(Chunk {
+        mode,
+        base,
+        base_count,
+        ours,
+        ours_count,
+        theirs,
+        theirs_count,
+    });
+}
+
+fn same_lines(ours: &[&[u8]], from: isize, theirs: &[&[u8]], to: isize, count: isize) -> bool {
+    (0..count).all(|offset| ours[(from + offset) as usize] == theirs[(to + offset) as usize])
+}
+
+// Shrink each conflict to just the lines where the two sides really differ, splitting it
+// in pieces when they agree on some lines in the middle
+fn refine_conflicts(
+    chunks: &mut Vec<Chunk>,
+    ours: &[&[u8]],
+    theirs: &[&[u8]],
+    algorithm: Algorithm,
+) {
+    let mut refined = Vec::with_capacity(chunks.len());
+    for chunk in chunks.drain(..) {
+        if chunk.mode != Mode::Conflict || chunk.ours_count == 0 || chunk.theirs_count == 0 {
+            refined.push(chunk);
+            continue;
+        }
+        let ours_lines = &ours[chunk.ours as usize..(chunk.ours + chunk.ours_count) as usize];
+        let theirs_lines =
+            &theirs[chunk.theirs as usize..(chunk.theirs + chunk.theirs_count) as usize];
+        let differences = changes(ours_lines, theirs_lines, algorithm);
+        if differences.is_empty() {
+            refined.push(Chunk {
+                mode: Mode::Identical,
+                ..chunk
+            });
+            continue;
+        }
+        for difference in differences {
+            refined.push(Chunk {
+                ours: chunk.ours + difference.base,
+                ours_count: difference.base_count,
+                theirs: chunk.theirs + difference.side,
+                theirs_count: difference.side_count,
+                ..chunk
+            });
+        }
+    }
+    *chunks = refined;
+}
+
+// Join conflicts separated by three lines or fewer, which read better as one conflict
+fn join_conflicts(chunks: &mut Vec<Chunk>) {
+    let mut joined: Vec<Chunk> = Vec::with_capacity(chunks.len());
+    for chunk in chunks.drain(..) {
+        if let Some(last) = joined.last_mut() {
+            let gap = chunk.ours - (last.ours + last.ours_count);
+            if last.mode == Mode::Conflict && chunk.mode == Mode::Conflict && gap <= 3 {
+                last.base_count = chunk.base + chunk.base_count - last.base;
+                last.ours_count = chunk.ours + chunk.ours_count - last.ours;
+                last.theirs_count = chunk.theirs + chunk.theirs_count - last.theirs;
+                continue;
+            }
+        }
+        joined.push(chunk);
+    }
+    *chunks = joined;
+}
+
+// Write out the merged file: our lines between chunks, each chunk's chosen side, and both
+// sides of every conflict between markers
+fn fill(chunks: &[Chunk], ours: &[&[u8]], theirs: &[&[u8]], labels: (&str, &str)) -> Vec<u8> {
+    let mut content = Vec::new();
+    let mut position = 0;
+    for chunk in chunks {
+        let (start, end) = (
+            chunk.ours as usize,
+            (chunk.ours + chunk.ours_count) as usize,
+        );
+        let theirs_range = chunk.theirs as usize..(chunk.theirs + chunk.theirs_count) as usize;
+        match chunk.mode {
+            Mode::Identical => continue,
+            Mode::Ours => copy(&mut content, &ours[position..end], false),
+            Mode::Theirs => {
+                copy(&mut content, &ours[position..start], false);
+                copy(&mut content, &theirs[theirs_range], false);
+            }
+            Mode::Conflict => {
+                copy(&mut content, &ours[position..start], false);
+                marker(&mut content, b'<', labels.0);
+                copy(&mut content, &ours[start..end], true);
+                marker(&mut content, b'=', "");
+                copy(&mut content, &theirs[theirs_range], true);
+                marker(&mut content, b'>', labels.1);
+            }
+        }
+        position = end;
+    }
+    copy(&mut content, &ours[position..], false);
+    content
+}
+
+// Copy lines, making sure they end with a newline when something is going to follow them
+fn copy(content: &mut Vec<u8>, lines: &[&[u8]], end_with_newline: bool) {
+    for line in lines {
+        content.extend_from_slice(line);
+    }
+    if end_with_newline && lines.last().is_some_and(|line| !line.ends_with(b"\n")) {
+        content.push(b'\n');
+    }
+}
+
+fn marker(content: &mut Vec<u8>, character: u8, label: &str) {
+    content.extend(std::iter::repeat_n(character, MARKER_SIZE));
+    if !label.is_empty() {
+        content.push(b' ');
+        content.extend_from_slice(label.as_bytes());
+    }
+    content.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_text(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+        let merged = merge(
+            base.as_bytes(),
+            ours.as_bytes(),
+            theirs.as_bytes(),
+            ("HEAD", "topic"),
+            Algorithm::Myers,
+        );
+        (String::from_utf8(merged.content).unwrap(), merged.clean)
+    }
+
+    #[test]
+    fn takes_changes_from_both_sides() {
+        let (content, clean) = merge_text("a\nb\nc\nd\ne\n", "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\n");
+        assert_eq!(content, "A\nb\nc\nd\nE\n");
+        assert!(clean);
+    }
+
+    #[test]
+    fn takes_the_same_change_once() {
+        let (content, clean) = merge_text("a\nb\nc\n", "a\nB\nc\n", "a\nB\nc\n");
+        assert_eq!(content, "a\nB\nc\n");
+        assert!(clean);
+    }
+
+    #[test]
+    fn marks_overlapping_changes_as_conflicts() {
+        let (content, clean) = merge_text("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(
+            content,
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nc\n"
+        );
+        assert!(!clean);
+    }
+
+    #[test]
+    fn narrows_conflicts_to_the_lines_that_differ() {
+        let (content, _) = merge_text("a\nb\n", "a\nsame\nours\n", "a\nsame\ntheirs\n");
+        assert_eq!(
+            content,
+            "a\nsame\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n"
+        );
+    }
+
+    #[test]
+    fn joins_conflicts_that_are_close_together() {
+        let (content, _) = merge_text(
+            "1\n2\n3\n4\n5\n",
+            "ours\n2\n3\n4\nours\n",
+            "theirs\n2\n3\n4\ntheirs\n",
+        );
+        assert_eq!(
+            content,
+            "<<<<<<< HEAD\nours\n2\n3\n4\nours\n=======\ntheirs\n2\n3\n4\ntheirs\n>>>>>>> topic\n"
+        );
+    }
+
+    #[test]
+    fn ends_conflicting_lines_with_newlines() {
+        let (content, _) = merge_text("a\n", "ours", "theirs");
+        assert_eq!(
+            content,
+            "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n"
+        );
+    }
+}
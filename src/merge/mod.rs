@@ -0,0 +1,228 @@
+// Three-way merges of trees, file by file, and the state git keeps while a merge with
+// conflicts waits to be committed
+
+mod diff3;
+
+pub use diff3::{merge as merge_content, Merged};
+
+use crate::{
+    diff::{self, Algorithm},
+    objects::{self, Blob, Entry, Object},
+    refs,
+};
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+const MERGE_HEAD: &str = "MERGE_HEAD";
+const MERGE_MSG: &str = "MERGE_MSG";
+const MERGE_MODE: &str = "MERGE_MODE";
+
+/// What merging two trees with a common base came to
+#[derive(Debug, Default)]
+pub struct TreeMerge {
+    /// Everything in the merged tree by path, where conflicted files hold conflict markers
+    pub result: BTreeMap<String, Entry>,
+    /// Conflicted paths, with what the base, ours and theirs had there (stages 1 to 3)
+    pub conflicts: BTreeMap<String, [Option<Entry>; 3]>,
+    /// What happened along the way, in git's words, like `Auto-merging README`
+    pub messages: Vec<String>,
+}
+
+/// Merge the changes two flattened trees (see `objects::flatten_tree`) made to their base,
+/// writing any merged files to the database. `labels` name our side and theirs, in conflict
+/// markers and messages.
+pub fn merge_trees(
+    base: &BTreeMap<String, Entry>,
+    ours: &BTreeMap<String, Entry>,
+    theirs: &BTreeMap<String, Entry>,
+    labels: (&str, &str),
+) -> Result<TreeMerge, io::Error> {
+    let mut paths: Vec<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut merge = TreeMerge::default();
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+        let merged = if o == t || b == t {
+            o.cloned()
+        } else if b == o {
+            t.cloned()
+        } else {
+            match (o, t) {
+                (Some(o), Some(t)) => Some(merge.merge_file(path, b, o, t, labels)?),
+                (Some(kept), None) | (None, Some(kept)) => {
+                    let (deleted_in, modified_in) = match o.is_some() {
+                        true => (labels.1, labels.0),
+                        false => (labels.0, labels.1),
+                    };
+                    merge.messages.push(format!(
+                        "CONFLICT (modify/delete): {} deleted in {} and modified in {}.  Version {} of {} left in tree.",
+                        path, deleted_in, modified_in, modified_in, path
+                    ));
+                    merge
+                        .conflicts
+                        .insert(path.to_owned(), [b.cloned(), o.cloned(), t.cloned()]);
+                    Some(kept.clone())
+                }
+                (None, None) => None,
+            }
+        };
+        if let Some(entry) = merged {
+            merge.result.insert(path.to_owned(), entry);
+        }
+    }
+    merge.move_files_out_of_the_way(ours, labels);
+    Ok(merge)
+}
+
+impl TreeMerge {
+    /// Whether every path merged without conflicts
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    // A file one side has where the other made a directory can't keep its path, so like git,
+    // move it aside to `path~side` (where `side` is its side's label) and leave it as a
+    // conflict with only that side's stage
+    fn move_files_out_of_the_way(&mut self, ours: &BTreeMap<String, Entry>, labels: (&str, &str)) {
+        let in_the_way: Vec<String> = self
+            .result
+            .keys()
+            .filter(|path| {
+                let directory = format!("{}/", path);
+                self.result
+                    .range(directory.clone()..)
+                    .next()
+                    .is_some_and(|(next, _)| next.starts_with(&directory))
+            })
+            .cloned()
+            .collect();
+
+        for path in in_the_way {
+            let entry = self.result.remove(&path).unwrap();
+            let ours_has_it = ours.contains_key(&path);
+            let label = match ours_has_it {
+                true => labels.0,
+                false => labels.1,
+            };
+            let moved_to = format!("{}~{}", path, label);
+            self.messages.push(format!(
+                "CONFLICT (file/directory): directory in the way of {} from {}; moving it to {} instead.",
+                path, label, moved_to
+            ));
+            let sides = match ours_has_it {
+                true => [None, Some(entry.clone()), None],
+                false => [None, None, Some(entry.clone())],
+            };
+            self.conflicts.remove(&path);
+            self.conflicts.insert(moved_to.clone(), sides);
+            self.result.insert(moved_to, entry);
+        }
+    }
+
+    // Merge the content of a file both sides changed, recording a conflict if they disagree
+    fn merge_file(
+        &mut self,
+        path: &str,
+        base: Option<&Entry>,
+        ours: &Entry,
+        theirs: &Entry,
+        labels: (&str, &str),
+    ) -> Result<Entry, io::Error> {
+        let base_content = match base {
+            Some(base) => read_blob(&base.oid)?,
+            None => vec![],
+        };
+        let (ours_content, theirs_content) = (read_blob(&ours.oid)?, read_blob(&theirs.oid)?);
+
+        self.messages.push(format!("Auto-merging {}", path));
+        let merged = if [&base_content, &ours_content, &theirs_content]
+            .iter()
+            .any(|content| diff::is_binary(content))
+        {
+            self.messages.push(format!(
+                "warning: Cannot merge binary files: {} ({} vs. {})",
+                path, labels.0, labels.1
+            ));
+            Merged {
+                content: ours_content,
+                clean: false,
+            }
+        } else {
+            merge_content(
+                &base_content,
+                &ours_content,
+                &theirs_content,
+                labels,
+                Algorithm::Histogram,
+            )
+        };
+
+        // A mode change on one side carries over, but when both changed it, ours wins
+        let mode = match base {
+            Some(base) if base.mode == ours.mode => theirs.mode.to_owned(),
+            _ => ours.mode.to_owned(),
+        };
+        if !merged.clean {
+            let kind = match base {
+                Some(_) => "content",
+                None => "add/add",
+            };
+            self.messages
+                .push(format!("CONFLICT ({}): Merge conflict in {}", kind, path));
+            self.conflicts.insert(
+                path.to_owned(),
+                [base.cloned(), Some(ours.clone()), Some(theirs.clone())],
+            );
+        }
+
+        let blob = Blob::from_bytes(merged.content);
+        objects::write(&blob)?;
+        Ok(Entry {
+            mode,
+            name: ours.name.to_owned(),
+            oid: blob.id(),
+        })
+    }
+}
+
+fn read_blob(oid: &str) -> Result<Vec<u8>, io::Error> {
+    Ok(objects::read(oid)?.content().to_owned())
+}
+
+/// The commit being merged in, while a merge with conflicts waits to be committed
+pub fn pending() -> Option<String> {
+    refs::resolve(MERGE_HEAD)
+}
+
+/// The message prepared for the commit that concludes a pending merge
+pub fn pending_message() -> Option<String> {
+    fs::read_to_string(state_path(MERGE_MSG)).ok()
+}
+
+/// Record a merge that stopped for conflicts, so the next commit can conclude it
+pub fn begin(merge_head: &str, message: &str) -> Result<(), io::Error> {
+    refs::update_ref(MERGE_HEAD, merge_head)?;
+    fs::write(state_path(MERGE_MODE), "")?;
+    fs::write(state_path(MERGE_MSG), message)
+}
+
+/// Forget about a pending merge, once it's been committed
+pub fn conclude() -> Result<(), io::Error> {
+    for name in &[MERGE_HEAD, MERGE_MSG, MERGE_MODE] {
+        match fs::remove_file(state_path(name)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn state_path(name: &str) -> PathBuf {
+    let current_dir = env::current_dir().expect("Couldn't get current directory");
+    current_dir.join(".git").join(name)
+}
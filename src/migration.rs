@@ -0,0 +1,213 @@
+use crate::{
+    index::{self, Index},
+    objects::{self, Entry, Object},
+    status, workspace,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+};
+
+/// Everything that would be lost by a migration, grouped the way git reports it
+#[derive(Debug, Default)]
+struct Conflicts {
+    stale_files: BTreeSet<String>,
+    stale_directories: BTreeSet<String>,
+    untracked_overwritten: BTreeSet<String>,
+    untracked_removed: BTreeSet<String>,
+}
+
+/// Moves the workspace and index from one tree to another, the way checkout does: only
+/// paths that differ between the trees are touched, and nothing happens at all if that
+/// would throw away changes that haven't been committed
+pub struct Migration<'a> {
+    index: &'a mut Index,
+    changes: BTreeMap<String, (Option<Entry>, Option<Entry>)>,
+}
+
+impl<'a> Migration<'a> {
+    /// Plan a migration between two flattened trees (see `objects::flatten_tree`)
+    pub fn new(
+        index: &'a mut Index,
+        old: &BTreeMap<String, Entry>,
+        new: &BTreeMap<String, Entry>,
+    ) -> Migration<'a> {
+        let mut changes = BTreeMap::new();
+        for path in old.keys().chain(new.keys()) {
+            let (before, after) = (old.get(path), new.get(path));
+            if before != after {
+                changes.insert(path.to_owned(), (before.cloned(), after.cloned()));
+            }
+        }
+        Migration { index, changes }
+    }
+
+    /// Check for conflicts, then update the workspace and index. `operation` names what's
+    /// being done (like `checkout`) in any error.
+    pub fn apply(self, operation: &str) -> Result<(), String> {
+        let conflicts = self
+            .check_for_conflicts()
+            .map_err(|error| error.to_string())?;
+        if let Some(message) = conflicts.message(operation) {
+            return Err(message);
+        }
+        self.update().map_err(|error| error.to_string())
+    }
+
+    fn check_for_conflicts(&self) -> Result<Conflicts, io::Error> {
+        let mut conflicts = Conflicts::default();
+
+        for (path, (old, new)) in &self.changes {
+            let entry = self.index.entry(path);
+            if index_differs(entry, old.as_ref()) && index_differs(entry, new.as_ref()) {
+                conflicts.stale_files.insert(path.to_owned());
+                continue;
+            }
+
+            match workspace::stat(path) {
+                // Nothing to lose by removing a missing file, but a new one needs its
+                // parent directories, which might be untracked files right now
+                Err(_) => {
+                    if let (Some(_), Some(parent)) = (new, self.untracked_parent(path)) {
+                        conflicts.untracked_overwritten.insert(parent);
+                    }
+                }
+                // A directory in the way goes with everything in it, tracked or not
+                Ok(metadata) if metadata.is_dir() => {
+                    let files = workspace::list_files(path)?;
+                    if files.iter().any(|file| !self.index.is_tracked_file(file)) {
+                        conflicts.stale_directories.insert(path.to_owned());
+                    }
+                }
+                Ok(metadata) => match entry {
+                    Some(entry) if status::workspace_differs(entry, &metadata) => {
+                        conflicts.stale_files.insert(path.to_owned());
+                    }
+                    Some(_) => {}
+                    None => {
+                        match old {
+                            Some(_) => conflicts.untracked_removed.insert(path.to_owned()),
+                            None => conflicts.untracked_overwritten.insert(path.to_owned()),
+                        };
+                    }
+                },
+            }
+        }
+        Ok(conflicts)
+    }
+
+    // An untracked file sitting where one of the path's parent directories needs to go
+    fn untracked_parent(&self, path: &str) -> Option<String> {
+        path.match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .find(|parent| match workspace::stat(parent) {
+                Ok(metadata) => !metadata.is_dir() && !self.index.is_tracked_file(parent),
+                Err(_) => false,
+            })
+            .map(String::from)
+    }
+
+    // Remove everything old first (deepest paths first, so emptied directories go too),
+    // then write everything new
+    fn update(self) -> Result<(), io::Error> {
+        for (path, (old, new)) in self.changes.iter().rev() {
+            if old.is_some() && new.is_none() {
+                workspace::remove_file(path)?;
+                self.index.remove(path);
+            }
+        }
+        for (path, (_, new)) in &self.changes {
+            if let Some(new) = new {
+                let blob = objects::read(&new.oid)?;
+                workspace::write_file(path, blob.content(), &new.mode)?;
+                let metadata = workspace::stat(path)?;
+                self.index.add(index::Entry::new(path, &new.oid, &metadata));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Whether the index holds something different from a tree entry at the same path
+fn index_differs(entry: Option<&index::Entry>, item: Option<&Entry>) -> bool {
+    match (entry, item) {
+        (None, None) => false,
+        (Some(entry), Some(item)) => entry.oid != item.oid || entry.tree_mode() != item.mode,
+        _ => true,
+    }
+}
+
+impl Conflicts {
+    // Describe the conflicts like git does, or give back None when there aren't any
+    fn message(&self, operation: &str) -> Option<String> {
+        let groups = [
+            (
+                &self.stale_files,
+                format!(
+                    "Your local changes to the following files would be overwritten by {}:",
+                    operation
+                ),
+                format!(
+                    "Please commit your changes or stash them before you {}.",
+                    purpose(operation)
+                ),
+            ),
+            (
+                &self.stale_directories,
+                String::from(
+                    "Updating the following directories would lose untracked files in them:",
+                ),
+                String::new(),
+            ),
+            (
+                &self.untracked_overwritten,
+                format!(
+                    "The following untracked working tree files would be overwritten by {}:",
+                    operation
+                ),
+                format!(
+                    "Please move or remove them before you {}.",
+                    purpose(operation)
+                ),
+            ),
+            (
+                &self.untracked_removed,
+                format!(
+                    "The following untracked working tree files would be removed by {}:",
+                    operation
+                ),
+                format!(
+                    "Please move or remove them before you {}.",
+                    purpose(operation)
+                ),
+            ),
+        ];
+
+        let mut message = String::new();
+        for (paths, header, footer) in groups.iter() {
+            if paths.is_empty() {
+                continue;
+            }
+            message.push_str(&format!("{}\n", header));
+            for path in paths.iter() {
+                message.push_str(&format!("\t{}\n", path));
+            }
+            if !footer.is_empty() {
+                message.push_str(&format!("{}\n", footer));
+            }
+        }
+
+        match message.is_empty() {
+            true => None,
+            false => Some(format!("{}Aborting", message)),
+        }
+    }
+}
+
+// How git words what an operation was trying to do, in advice about conflicts
+fn purpose(operation: &str) -> &'static str {
+    match operation {
+        "checkout" => "switch branches",
+        _ => "merge",
+    }
+}
@@ -1,25 +1,47 @@
 use crate::objects::{Kind, Object};
-use std::{fs, path::PathBuf};
+use std::{fs, os::unix::ffi::OsStrExt, path::PathBuf};
 
 #[derive(Debug)]
 pub struct Blob {
     pub content: Vec<u8>,
-    pub source: PathBuf,
+    pub source: Option<PathBuf>,
 }
 
 impl Blob {
-    /// Build a Blob from a file
+    /// Build a Blob from a file, or from the target of a symlink
     pub fn from_file<P: Into<PathBuf>>(source: P) -> Blob {
         let source = source.into();
-        let content = fs::read_to_string(&source)
-            .expect("Couldn't read the file")
-            .as_bytes()
-            .to_owned();
-        Blob { content, source }
+        let metadata = fs::symlink_metadata(&source).expect("Couldn't read the file's metadata");
+        let content = match metadata.file_type().is_symlink() {
+            true => fs::read_link(&source)
+                .expect("Couldn't read the symlink")
+                .as_os_str()
+                .as_bytes()
+                .to_owned(),
+            false => fs::read(&source).expect("Couldn't read the file"),
+        };
+        Blob {
+            content,
+            source: Some(source),
+        }
     }
 
-    pub fn source(&self) -> &PathBuf {
-        &self.source
+    /// Build a Blob from bytes that didn't come from a file, like stdin
+    pub fn from_bytes(content: Vec<u8>) -> Blob {
+        Blob {
+            content,
+            source: None,
+        }
+    }
+
+    /// Build a Blob from the content of an object read from the database
+    pub fn parse(content: Vec<u8>) -> Blob {
+        Blob::from_bytes(content)
+    }
+
+    /// Where the Blob came from in the workspace, if it came from there at all
+    pub fn source(&self) -> Option<&PathBuf> {
+        self.source.as_ref()
     }
 }
 
@@ -1,33 +1,191 @@
-use crate::objects::{Kind, Object};
-use crate::{config::Config, refs};
-use chrono::Local;
+use crate::config::{Config, User};
+use crate::objects::{invalid_data, Kind, Object};
+use chrono::{FixedOffset, Local, TimeZone};
+use std::{fmt, io};
 
 #[derive(Debug)]
 pub struct Commit {
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: Signature,
+    pub committer: Signature,
+    pub message: String,
     content: Vec<u8>,
 }
 
+/// Who did something, and when they did it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    /// Seconds since the epoch followed by a timezone offset, like `1570000000 +0200`
+    pub date: String,
+}
+
 impl Commit {
-    pub fn new(tree_id: String, message: &str) -> Commit {
-        let config = Config::build();
-        let author_name = config.author.name;
-        let author_email = config.author.email;
-        let author_date = match config.author.date.len() {
-            0 => Local::now().format("%s %z").to_string(),
-            _ => config.author.date,
-        };
-        let parent = match refs::read_head() {
-            Some(head) => format!("parent {}\n", head),
-            None => String::from(""),
-        };
-        let author = format!("{} <{}> {}", author_name, author_email, author_date);
+    /// The first paragraph of the message, joined into one line
+    pub fn subject(&self) -> String {
+        self.message
+            .lines()
+            .take_while(|line| !line.trim().is_empty())
+            .map(str::trim)
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Build a commit of a tree, signed by whoever config says, on top of its parents
+    /// (none for a root commit, two or more for a merge). Fails when config can't be read.
+    pub fn new(tree_id: String, parents: Vec<String>, message: &str) -> Result<Commit, io::Error> {
+        let config = Config::build()?;
+        let author = Signature::from_config(&config.author, &config.user);
+        let committer = Signature::from_config(&config.committer, &config.user);
+
+        let mut headers = format!("tree {}\n", tree_id);
+        for parent in &parents {
+            headers.push_str(&format!("parent {}\n", parent));
+        }
+        let message = format!("{}\n", message);
         let content = format!(
-            "tree {}\n{}author {}\ncommitter {}\n\n{}\n",
-            tree_id, parent, author, author, message
+            "{}author {}\ncommitter {}\n\n{}",
+            headers, author, committer, message
         )
         .as_bytes()
         .to_owned();
-        Commit { content }
+
+        Ok(Commit {
+            tree: tree_id,
+            parents,
+            author,
+            committer,
+            message,
+            content,
+        })
+    }
+
+    /// Build a Commit from the content of an object read from the database
+    pub fn parse(content: Vec<u8>) -> Result<Commit, io::Error> {
+        let text = String::from_utf8_lossy(&content).into_owned();
+        let (headers, message) = match text.find("\n\n") {
+            Some(index) => (&text[..index], &text[index + 2..]),
+            None => (text.trim_end_matches('\n'), ""),
+        };
+
+        let mut tree = None;
+        let mut parents = Vec::new();
+        let mut author = None;
+        let mut committer = None;
+
+        for line in headers.lines() {
+            // Continuation lines belong to multi-line headers like `gpgsig`, which we skip
+            if line.starts_with(' ') {
+                continue;
+            }
+            let (key, value) = match line.find(' ') {
+                Some(index) => (&line[..index], &line[index + 1..]),
+                None => (line, ""),
+            };
+            match key {
+                "tree" => tree = Some(value.to_string()),
+                "parent" => parents.push(value.to_string()),
+                "author" => author = Some(Signature::parse(value)?),
+                "committer" => committer = Some(Signature::parse(value)?),
+                _ => {}
+            }
+        }
+
+        Ok(Commit {
+            tree: tree.ok_or_else(|| invalid_data("Commit is missing its tree"))?,
+            parents,
+            author: author.ok_or_else(|| invalid_data("Commit is missing its author"))?,
+            committer: committer.ok_or_else(|| invalid_data("Commit is missing its committer"))?,
+            message: message.to_string(),
+            content,
+        })
+    }
+}
+
+impl Signature {
+    /// Build a signature from config, using `fallback` (usually `user.*`) for missing values
+    pub(super) fn from_config(user: &User, fallback: &User) -> Signature {
+        let pick = |value: &String, fallback: &String| match value.len() {
+            0 => fallback.to_owned(),
+            _ => value.to_owned(),
+        };
+        let date = match pick(&user.date, &fallback.date).len() {
+            0 => Local::now().format("%s %z").to_string(),
+            _ => pick(&user.date, &fallback.date),
+        };
+        Signature {
+            name: pick(&user.name, &fallback.name),
+            email: pick(&user.email, &fallback.email),
+            date,
+        }
+    }
+
+    /// Parse a signature line like `Count Dracula <count@dracula> 1570000000 +0200`
+    pub fn parse(line: &str) -> Result<Signature, io::Error> {
+        let email_start = line.find('<');
+        let email_end = line.rfind('>');
+        match (email_start, email_end) {
+            (Some(start), Some(end)) if start < end => Ok(Signature {
+                name: line[..start].trim().to_string(),
+                email: line[start + 1..end].to_string(),
+                date: line[end + 1..].trim().to_string(),
+            }),
+            _ => Err(invalid_data(format!("Invalid signature: {}", line))),
+        }
+    }
+}
+
+impl Signature {
+    /// When it happened, in seconds since the epoch
+    pub fn timestamp(&self) -> i64 {
+        self.date
+            .split_whitespace()
+            .next()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// The date in git's default format, in the signer's own timezone,
+    /// like `Wed Oct 2 09:06:40 2019 +0200`
+    pub fn format_date(&self) -> String {
+        self.format_date_as("%a %b %-d %H:%M:%S %Y %z")
+    }
+
+    /// The date in the signer's own timezone, using a `strftime`-style format
+    pub fn format_date_as(&self, format: &str) -> String {
+        let offset = self
+            .date
+            .split_whitespace()
+            .nth(1)
+            .and_then(parse_offset)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        match offset.timestamp_opt(self.timestamp(), 0).single() {
+            Some(date) => date.format(format).to_string(),
+            None => self.date.to_owned(),
+        }
+    }
+}
+
+// Parse a timezone offset like `+0200` or `-0530`
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    if offset.len() != 5 {
+        return None;
+    }
+    let sign = match &offset[..1] {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let hours: i32 = offset[1..3].parse().ok()?;
+    let minutes: i32 = offset[3..5].parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} <{}> {}", self.name, self.email, self.date)
     }
 }
 
@@ -40,3 +198,71 @@ impl Object for Commit {
         &Kind::Commit
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_commit_with_parents() {
+        let content = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+parent 1111111111111111111111111111111111111111\n\
+parent 2222222222222222222222222222222222222222\n\
+author Count Dracula <count@dracula> 1570000000 +0200\n\
+committer Van Helsing <van@helsing> 1570000001 -0500\n\
+\n\
+Merge it all\n\nWith a body\n"
+            .to_vec();
+        let commit = Commit::parse(content.clone()).expect("Couldn't parse commit");
+
+        assert_eq!(commit.tree, "4b825dc642cb6eb9a060e54bf8d69288fbee4904");
+        assert_eq!(
+            commit.parents,
+            vec![
+                "1111111111111111111111111111111111111111",
+                "2222222222222222222222222222222222222222"
+            ]
+        );
+        assert_eq!(
+            commit.author,
+            Signature {
+                name: String::from("Count Dracula"),
+                email: String::from("count@dracula"),
+                date: String::from("1570000000 +0200"),
+            }
+        );
+        assert_eq!(commit.committer.name, "Van Helsing");
+        assert_eq!(commit.message, "Merge it all\n\nWith a body\n");
+        assert_eq!(commit.content(), &content);
+    }
+
+    #[test]
+    fn skips_multi_line_headers() {
+        let content = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+author A <a@a> 1 +0000\n\
+committer A <a@a> 1 +0000\n\
+gpgsig -----BEGIN PGP SIGNATURE-----\n \n -----END PGP SIGNATURE-----\n\
+\n\
+Signed\n"
+            .to_vec();
+        let commit = Commit::parse(content).expect("Couldn't parse commit");
+        assert!(commit.parents.is_empty());
+        assert_eq!(commit.message, "Signed\n");
+    }
+
+    #[test]
+    fn formats_dates_in_their_own_timezone() {
+        let signature = Signature::parse("A <a@a> 1570000000 +0200").unwrap();
+        assert_eq!(signature.timestamp(), 1570000000);
+        assert_eq!(signature.format_date(), "Wed Oct 2 09:06:40 2019 +0200");
+
+        let signature = Signature::parse("A <a@a> 1570000000 -0530").unwrap();
+        assert_eq!(signature.format_date(), "Wed Oct 2 01:36:40 2019 -0530");
+    }
+
+    #[test]
+    fn refuses_a_commit_without_a_tree() {
+        let content = b"author A <a@a> 1 +0000\ncommitter A <a@a> 1 +0000\n\nHi\n".to_vec();
+        assert!(Commit::parse(content).is_err());
+    }
+}
@@ -1,30 +1,59 @@
 use crate::compression;
 pub use blob::Blob;
-pub use commit::Commit;
-use sha1;
+pub use commit::{Commit, Signature};
 use std::{
+    collections::BTreeMap,
     env, fs,
     io::{self, Write},
     path::PathBuf,
+    str,
 };
-pub use tree::Tree;
+pub use tag::Tag;
+pub use tree::{file_mode, mode_for_metadata, Entry, Tree};
 use uuid::Uuid;
 
 mod blob;
 mod commit;
+mod tag;
 mod tree;
 
 /// The kind of object we're dealing with
+#[derive(Debug, PartialEq)]
 pub enum Kind {
     /// Represents a file
     Blob,
     /// Represents a collection of blobs
     Tree,
     Commit,
+    /// An annotated tag, pointing at another object
+    Tag,
 }
 
 use Kind::*;
 
+impl Kind {
+    /// The name git uses for this kind of object in headers and output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Blob => "blob",
+            Tree => "tree",
+            Commit => "commit",
+            Tag => "tag",
+        }
+    }
+
+    /// Figure out the kind of object from the name found in its header
+    pub fn from_name(name: &str) -> Option<Kind> {
+        match name {
+            "blob" => Some(Blob),
+            "tree" => Some(Tree),
+            "commit" => Some(Commit),
+            "tag" => Some(Tag),
+            _ => None,
+        }
+    }
+}
+
 /// Something that can be saved to Git's database in `objects/`
 pub trait Object {
     /// Get the content of the object, in bytes
@@ -43,26 +72,17 @@ pub trait Object {
 
     /// Show the path to where the object might be saved in the database
     fn path(&self) -> PathBuf {
-        let current_dir = env::current_dir().expect("Couldn't get current working directory");
-        let id = self.id();
-        current_dir
-            .join(".git")
-            .join("objects")
-            .join(&id[0..2])
-            .join(&id[2..])
+        object_path(&self.id())
     }
 
     /// Format the object for file persistence
     fn build_file(&self) -> Vec<u8> {
-        let kind = match self.kind() {
-            Blob => "blob",
-            Tree => "tree",
-            Commit => "commit",
-        };
         let byte_length = self.content().len();
 
-        let mut file: Vec<u8> = format!("{} {}\0", kind, byte_length).as_bytes().to_vec();
-        file.append(&mut self.content().to_owned());
+        let mut file: Vec<u8> = format!("{} {}\0", self.kind().name(), byte_length)
+            .as_bytes()
+            .to_vec();
+        file.extend_from_slice(self.content());
         file
     }
 
@@ -72,6 +92,35 @@ pub trait Object {
     }
 }
 
+/// An object that was read back out of the Git database
+#[derive(Debug)]
+pub enum ParsedObject {
+    Blob(Blob),
+    Tree(Tree),
+    Commit(Commit),
+    Tag(Tag),
+}
+
+impl Object for ParsedObject {
+    fn content(&self) -> &Vec<u8> {
+        match self {
+            ParsedObject::Blob(blob) => blob.content(),
+            ParsedObject::Tree(tree) => tree.content(),
+            ParsedObject::Commit(commit) => commit.content(),
+            ParsedObject::Tag(tag) => tag.content(),
+        }
+    }
+
+    fn kind(&self) -> &Kind {
+        match self {
+            ParsedObject::Blob(blob) => blob.kind(),
+            ParsedObject::Tree(tree) => tree.kind(),
+            ParsedObject::Commit(commit) => commit.kind(),
+            ParsedObject::Tag(tag) => tag.kind(),
+        }
+    }
+}
+
 /// Write an object to the Git database
 pub fn write(object: &impl Object) -> Result<(), io::Error> {
     let compressed_data = compression::compress(&object.build_file())?;
@@ -89,6 +138,143 @@ pub fn write(object: &impl Object) -> Result<(), io::Error> {
     Ok(())
 }
 
+/// Write a tree to the database, along with every subtree beneath it
+pub fn write_tree(tree: &Tree) -> Result<(), io::Error> {
+    for subtree in tree.subtrees() {
+        write_tree(subtree)?;
+    }
+    write(tree)
+}
+
+/// Read an object out of the Git database by its ID
+pub fn read(oid: &str) -> Result<ParsedObject, io::Error> {
+    if oid.len() != 40 || !oid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_data(format!("Not a valid object ID: {}", oid)));
+    }
+    let compressed_data = fs::read(object_path(oid))?;
+    let file = compression::decompress(&compressed_data)?;
+    parse(&file)
+}
+
+/// Find the IDs of every object in the database starting with a hex prefix of at least two
+/// characters, sorted
+pub fn find_by_prefix(prefix: &str) -> Result<Vec<String>, io::Error> {
+    let prefix = prefix.to_ascii_lowercase();
+    if prefix.len() < 2 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(vec![]);
+    }
+    let directory = object_path(&format!("{:0<40}", &prefix[..2]));
+    let directory = directory
+        .parent()
+        .expect("Object paths always have a parent");
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut found = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let oid = format!("{}{}", &prefix[..2], entry?.file_name().to_string_lossy());
+        if oid.len() == 40 && oid.starts_with(&prefix) {
+            found.push(oid);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// Read an object that's expected to be a commit
+pub fn read_commit(oid: &str) -> Result<Commit, io::Error> {
+    match read(oid)? {
+        ParsedObject::Commit(commit) => Ok(commit),
+        other => Err(invalid_data(format!(
+            "{} is a {}, not a commit",
+            oid,
+            other.kind().name()
+        ))),
+    }
+}
+
+/// Read an object that's expected to be a tree
+pub fn read_tree(oid: &str) -> Result<Tree, io::Error> {
+    match read(oid)? {
+        ParsedObject::Tree(tree) => Ok(tree),
+        other => Err(invalid_data(format!(
+            "{} is a {}, not a tree",
+            oid,
+            other.kind().name()
+        ))),
+    }
+}
+
+/// Read a tree and every subtree beneath it, giving back everything that isn't a tree
+/// keyed by its `/`-separated path from the root
+pub fn flatten_tree(oid: &str) -> Result<BTreeMap<String, Entry>, io::Error> {
+    let mut flattened = BTreeMap::new();
+    for entry in read_tree(oid)?.entries {
+        match entry.kind() {
+            Kind::Tree => {
+                for (path, nested) in flatten_tree(&entry.oid)? {
+                    flattened.insert(format!("{}/{}", entry.name, path), nested);
+                }
+            }
+            _ => {
+                flattened.insert(entry.name.to_owned(), entry);
+            }
+        }
+    }
+    Ok(flattened)
+}
+
+/// Parse an inflated object file, validating its `<kind> <len>\0` header
+pub fn parse(file: &[u8]) -> Result<ParsedObject, io::Error> {
+    let header_end = file
+        .iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| invalid_data("Object header isn't terminated"))?;
+    let header = str::from_utf8(&file[..header_end])
+        .map_err(|_| invalid_data("Object header isn't valid UTF-8"))?;
+
+    let mut header_parts = header.splitn(2, ' ');
+    let kind = header_parts
+        .next()
+        .and_then(Kind::from_name)
+        .ok_or_else(|| invalid_data(format!("Unknown object kind in header: {}", header)))?;
+    let byte_length: usize = header_parts
+        .next()
+        .and_then(|length| length.parse().ok())
+        .ok_or_else(|| invalid_data(format!("Invalid object length in header: {}", header)))?;
+
+    let content = file[header_end + 1..].to_vec();
+    if content.len() != byte_length {
+        return Err(invalid_data(format!(
+            "Object length is {} but header says {}",
+            content.len(),
+            byte_length
+        )));
+    }
+
+    Ok(match kind {
+        Blob => ParsedObject::Blob(blob::Blob::parse(content)),
+        Tree => ParsedObject::Tree(tree::Tree::parse(content)?),
+        Commit => ParsedObject::Commit(commit::Commit::parse(content)?),
+        Tag => ParsedObject::Tag(tag::Tag::parse(content)?),
+    })
+}
+
+/// Where an object with a given ID lives in the database
+fn object_path(id: &str) -> PathBuf {
+    let current_dir = env::current_dir().expect("Couldn't get current working directory");
+    current_dir
+        .join(".git")
+        .join("objects")
+        .join(&id[0..2])
+        .join(&id[2..])
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +336,25 @@ mod tests {
 
         assert_eq!(object.path(), expected_path);
     }
+
+    #[test]
+    fn parses_a_file_built_for_persistence() {
+        let object = SomeObject::new();
+        let parsed = parse(&object.build_file()).expect("Couldn't parse the object file");
+        assert_eq!(parsed.kind(), &Kind::Blob);
+        assert_eq!(parsed.content(), object.content());
+        assert_eq!(parsed.id(), object.id());
+    }
+
+    #[test]
+    fn rejects_a_header_with_the_wrong_length() {
+        let file = b"blob 3\0four".to_vec();
+        assert!(parse(&file).is_err());
+    }
+
+    #[test]
+    fn rejects_a_header_with_an_unknown_kind() {
+        let file = b"widget 4\0four".to_vec();
+        assert!(parse(&file).is_err());
+    }
 }
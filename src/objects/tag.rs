@@ -0,0 +1,132 @@
+use crate::config::Config;
+use crate::objects::{commit::Signature, invalid_data, Kind, Object};
+use std::io;
+
+/// An annotated tag: a name and message attached to another object, signed by its tagger
+#[derive(Debug)]
+pub struct Tag {
+    /// The ID of the tagged object
+    pub object: String,
+    /// The kind of the tagged object, like `commit`
+    pub kind: String,
+    /// The tag's name, without `refs/tags/`
+    pub name: String,
+    /// Who made the tag, which very old tags don't record
+    pub tagger: Option<Signature>,
+    pub message: String,
+    content: Vec<u8>,
+}
+
+impl Tag {
+    /// Build a tag of an object, signed by whoever config says commits. Fails when config
+    /// can't be read.
+    pub fn new(object: &str, kind: &str, name: &str, message: &str) -> Result<Tag, io::Error> {
+        let config = Config::build()?;
+        let tagger = Signature::from_config(&config.committer, &config.user);
+        let message = format!("{}\n", message);
+        let content = format!(
+            "object {}\ntype {}\ntag {}\ntagger {}\n\n{}",
+            object, kind, name, tagger, message
+        )
+        .into_bytes();
+
+        Ok(Tag {
+            object: object.to_string(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+            tagger: Some(tagger),
+            message,
+            content,
+        })
+    }
+
+    /// Build a Tag from the content of an object read from the database
+    pub fn parse(content: Vec<u8>) -> Result<Tag, io::Error> {
+        let text = String::from_utf8_lossy(&content).into_owned();
+        let (headers, message) = match text.find("\n\n") {
+            Some(index) => (&text[..index], &text[index + 2..]),
+            None => (text.trim_end_matches('\n'), ""),
+        };
+
+        let mut object = None;
+        let mut kind = None;
+        let mut name = None;
+        let mut tagger = None;
+
+        for line in headers.lines() {
+            let (key, value) = match line.find(' ') {
+                Some(index) => (&line[..index], &line[index + 1..]),
+                None => (line, ""),
+            };
+            match key {
+                "object" => object = Some(value.to_string()),
+                "type" => kind = Some(value.to_string()),
+                "tag" => name = Some(value.to_string()),
+                "tagger" => tagger = Some(Signature::parse(value)?),
+                _ => {}
+            }
+        }
+
+        Ok(Tag {
+            object: object.ok_or_else(|| invalid_data("Tag is missing its object"))?,
+            kind: kind.ok_or_else(|| invalid_data("Tag is missing its type"))?,
+            name: name.ok_or_else(|| invalid_data("Tag is missing its name"))?,
+            tagger,
+            message: message.to_string(),
+            content,
+        })
+    }
+}
+
+impl Object for Tag {
+    fn content(&self) -> &Vec<u8> {
+        &self.content
+    }
+
+    fn kind(&self) -> &Kind {
+        &Kind::Tag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_an_annotated_tag() {
+        let content = b"object 1111111111111111111111111111111111111111\n\
+type commit\n\
+tag v1.0\n\
+tagger Count Dracula <count@dracula> 1570000000 +0200\n\
+\n\
+The first release\n"
+            .to_vec();
+        let tag = Tag::parse(content.clone()).expect("Couldn't parse tag");
+
+        assert_eq!(tag.object, "1111111111111111111111111111111111111111");
+        assert_eq!(tag.kind, "commit");
+        assert_eq!(tag.name, "v1.0");
+        assert_eq!(tag.tagger.as_ref().unwrap().name, "Count Dracula");
+        assert_eq!(tag.message, "The first release\n");
+        assert_eq!(tag.content(), &content);
+    }
+
+    #[test]
+    fn parses_a_tag_without_a_tagger() {
+        let content = b"object 1111111111111111111111111111111111111111\n\
+type tree\n\
+tag old\n\
+\n\
+From long ago\n"
+            .to_vec();
+        let tag = Tag::parse(content).expect("Couldn't parse tag");
+        assert!(tag.tagger.is_none());
+        assert_eq!(tag.kind, "tree");
+    }
+
+    #[test]
+    fn refuses_a_tag_without_an_object() {
+        let content = b"type commit\ntag v1.0\n\nHi\n".to_vec();
+        assert!(Tag::parse(content).is_err());
+    }
+}
@@ -1,54 +1,217 @@
-use crate::objects::{blob::Blob, Kind, Object};
-use hex;
-use std::{env, path::PathBuf};
+use crate::objects::{blob::Blob, invalid_data, Kind, Object};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    env,
+    fs::{self, Metadata},
+    io,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    str,
+};
 
 #[derive(Debug)]
 pub struct Tree {
-    blobs: Vec<Blob>,
+    pub entries: Vec<Entry>,
+    /// Trees built for subdirectories, which need writing alongside this one
+    subtrees: Vec<Tree>,
     content: Vec<u8>,
 }
 
-#[allow(dead_code)]
+/// A single line in a tree: something with a mode and a name, pointing at another object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub mode: String,
+    pub name: String,
+    pub oid: String,
+}
+
 impl Tree {
-    /// Build a new tree from a collection of Blobs
+    /// Build a new tree from a collection of Blobs found anywhere in the workspace
     pub fn from_blobs(blobs: Vec<Blob>) -> Tree {
-        let mut blobs = blobs;
-
-        blobs.sort_by(|a, b| {
-            let source_of_a = a.source();
-            let source_of_b = b.source();
-            source_of_a
-                .to_str()
-                .unwrap()
-                .cmp(source_of_b.to_str().unwrap())
-        });
-
-        let content: Vec<u8> = blobs
+        let entries: Vec<Entry> = blobs
             .iter()
             .map(|blob| {
-                let mut binary_blob: Vec<u8> = vec![];
+                let source = blob.source().expect("Blob has no source file");
+                Entry {
+                    mode: file_mode(source),
+                    name: path_relative_to_cwd(source),
+                    oid: blob.id(),
+                }
+            })
+            .collect();
+
+        Tree::build(entries)
+    }
+
+    /// Build a tree from entries named by their `/`-separated path from the root,
+    /// nesting a subtree for every directory along the way
+    pub fn build(entries: Vec<Entry>) -> Tree {
+        let mut own_entries: Vec<Entry> = Vec::new();
+        let mut directories: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+
+        for entry in entries {
+            match entry.name.find('/') {
+                Some(index) => {
+                    let directory = entry.name[..index].to_string();
+                    let nested = Entry {
+                        name: entry.name[index + 1..].to_string(),
+                        ..entry
+                    };
+                    directories.entry(directory).or_default().push(nested);
+                }
+                None => own_entries.push(entry),
+            }
+        }
 
-                let mut mode: Vec<u8> = String::from("100644").as_bytes().to_owned();
-                let mut empty_string = String::from(" ").as_bytes().to_owned();
-                let mut source = path_relative_to_cwd(blob.source()).as_bytes().to_owned();
-                let mut null_byte: Vec<u8> = vec![0];
-                let mut hex_id = hex::decode(blob.id()).expect("Invalid hex ID");
+        let subtrees: Vec<Tree> = directories
+            .into_iter()
+            .map(|(name, nested)| {
+                let subtree = Tree::build(nested);
+                own_entries.push(Entry {
+                    mode: String::from("40000"),
+                    name,
+                    oid: subtree.id(),
+                });
+                subtree
+            })
+            .collect();
+
+        own_entries.sort();
+
+        Tree {
+            subtrees,
+            ..Tree::from_entries(own_entries)
+        }
+    }
+
+    /// Build a tree from entries that are already in order
+    pub fn from_entries(entries: Vec<Entry>) -> Tree {
+        let content: Vec<u8> = entries
+            .iter()
+            .flat_map(|entry| {
+                let mut binary_entry: Vec<u8> = vec![];
 
-                binary_blob.append(&mut mode);
-                binary_blob.append(&mut empty_string);
-                binary_blob.append(&mut source);
-                binary_blob.append(&mut null_byte);
-                binary_blob.append(&mut hex_id);
+                binary_entry.extend_from_slice(entry.mode.as_bytes());
+                binary_entry.push(b' ');
+                binary_entry.extend_from_slice(entry.name.as_bytes());
+                binary_entry.push(0);
+                binary_entry.append(&mut hex::decode(&entry.oid).expect("Invalid hex ID"));
 
-                binary_blob
+                binary_entry
             })
-            .flatten()
             .collect();
-        Tree { content, blobs }
+        Tree {
+            entries,
+            subtrees: Vec::new(),
+            content,
+        }
+    }
+
+    /// Trees built for subdirectories, each of which may have subtrees of its own
+    pub fn subtrees(&self) -> &[Tree] {
+        &self.subtrees
+    }
+
+    /// Build a Tree from the content of an object read from the database
+    pub fn parse(content: Vec<u8>) -> Result<Tree, io::Error> {
+        let mut entries = Vec::new();
+        let mut rest = content.as_slice();
+
+        while !rest.is_empty() {
+            let mode_end = rest
+                .iter()
+                .position(|&byte| byte == b' ')
+                .ok_or_else(|| invalid_data("Tree entry is missing its mode"))?;
+            let name_end = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(|| invalid_data("Tree entry is missing its name"))?;
+            if name_end < mode_end || rest.len() < name_end + 21 {
+                return Err(invalid_data("Tree entry is truncated"));
+            }
+
+            let mode = str::from_utf8(&rest[..mode_end])
+                .map_err(|_| invalid_data("Tree entry mode isn't valid UTF-8"))?;
+            let name = String::from_utf8_lossy(&rest[mode_end + 1..name_end]);
+            let oid = hex::encode(&rest[name_end + 1..name_end + 21]);
+
+            entries.push(Entry {
+                mode: mode.to_string(),
+                name: name.into_owned(),
+                oid,
+            });
+            rest = &rest[name_end + 21..];
+        }
+
+        Ok(Tree {
+            entries,
+            subtrees: Vec::new(),
+            content,
+        })
+    }
+}
+
+impl Entry {
+    /// The kind of object an entry points at, judging by its mode
+    pub fn kind(&self) -> Kind {
+        match self.mode.as_str() {
+            "40000" => Kind::Tree,
+            "160000" => Kind::Commit,
+            _ => Kind::Blob,
+        }
+    }
+
+    /// The bytes git sorts entries by: the name, plus a trailing `/` for trees
+    fn sort_key(&self) -> Vec<u8> {
+        let mut key = self.name.as_bytes().to_vec();
+        if self.kind() == Kind::Tree {
+            key.push(b'/');
+        }
+        key
+    }
+}
+
+/// Entries are ordered the way git orders them within a tree, byte-wise by name with
+/// directories compared as if their name ended in `/`, so `foo.txt` comes before `foo/`
+/// which comes before `foo0`
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        self.sort_key()
+            .cmp(&other.sort_key())
+            .then_with(|| self.mode.cmp(&other.mode))
+            .then_with(|| self.oid.cmp(&other.oid))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-fn path_relative_to_cwd(path: &PathBuf) -> String {
+/// The mode git records for a file: a symlink, an executable, or a regular file
+/// (which is also what we assume for a file we can't look at)
+pub fn file_mode(path: &Path) -> String {
+    let mode = match fs::symlink_metadata(path) {
+        Ok(metadata) => mode_for_metadata(&metadata),
+        _ => "100644",
+    };
+    String::from(mode)
+}
+
+/// The mode git records for a file, given its (unfollowed) metadata
+pub fn mode_for_metadata(metadata: &Metadata) -> &'static str {
+    if metadata.file_type().is_symlink() {
+        "120000"
+    } else if metadata.permissions().mode() & 0o100 != 0 {
+        "100755"
+    } else {
+        "100644"
+    }
+}
+
+fn path_relative_to_cwd(path: &Path) -> String {
     let current_dir = env::current_dir()
         .expect("Couldn't determine current directory")
         .to_str()
@@ -85,8 +248,79 @@ mod tests {
     fn creates_from_blobs() {
         let blob = Blob {
             content: "Hello".to_string().as_bytes().to_owned(),
-            source: PathBuf::from("wat.txt"),
+            source: Some(PathBuf::from("wat.txt")),
         };
         let _tree = Tree::from_blobs(vec![blob]);
     }
+
+    #[test]
+    fn nests_subtrees_for_directories() {
+        let oid = String::from("5ab2f8a4323abafb10abb68657d9d39f1a775057");
+        let entry = |name: &str| Entry {
+            mode: String::from("100644"),
+            name: name.to_string(),
+            oid: oid.clone(),
+        };
+        let tree = Tree::build(vec![entry("b.txt"), entry("a/b/c.txt"), entry("a/d.txt")]);
+
+        let names: Vec<&str> = tree.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b.txt"]);
+        assert_eq!(tree.entries[0].mode, "40000");
+
+        let a = &tree.subtrees()[0];
+        assert_eq!(tree.entries[0].oid, a.id());
+        let names: Vec<&str> = a.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "d.txt"]);
+        assert_eq!(a.subtrees()[0].entries, vec![entry("c.txt")]);
+    }
+
+    #[test]
+    fn orders_entries_like_git() {
+        let entry = |mode: &str, name: &str| Entry {
+            mode: mode.to_string(),
+            name: name.to_string(),
+            oid: String::from("5ab2f8a4323abafb10abb68657d9d39f1a775057"),
+        };
+        let mut entries = [
+            entry("100644", "foo0"),
+            entry("40000", "foo"),
+            entry("100644", "foo.txt"),
+            entry("100644", "foo-bar"),
+            entry("100644", "Zebra"),
+            entry("40000", "a"),
+        ];
+        entries.sort();
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Zebra", "a", "foo-bar", "foo.txt", "foo", "foo0"]
+        );
+    }
+
+    #[test]
+    fn parses_what_it_builds() {
+        let blob = Blob {
+            content: "Hello".to_string().as_bytes().to_owned(),
+            source: Some(PathBuf::from("wat.txt")),
+        };
+        let tree = Tree::from_blobs(vec![blob]);
+        let parsed = Tree::parse(tree.content().to_owned()).expect("Couldn't parse tree");
+
+        assert_eq!(parsed.id(), tree.id());
+        assert_eq!(
+            parsed.entries,
+            vec![Entry {
+                mode: String::from("100644"),
+                name: String::from("wat.txt"),
+                oid: String::from("5ab2f8a4323abafb10abb68657d9d39f1a775057"),
+            }]
+        );
+    }
+
+    #[test]
+    fn refuses_a_truncated_entry() {
+        let content = b"100644 wat.txt\0\x5a\xb2".to_vec();
+        assert!(Tree::parse(content).is_err());
+    }
 }
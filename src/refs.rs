@@ -1,17 +1,299 @@
-use std::{env, fs, path::PathBuf};
+use crate::lockfile::Lockfile;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
 
+const SYMREF_PREFIX: &str = "ref: ";
+const PACKED_REFS: &str = "packed-refs";
+const PACKED_REFS_HEADER: &str = "# pack-refs with: peeled fully-peeled sorted \n";
+/// How many refs git reads following a chain of symbolic refs before giving up, which is
+/// what stops a loop
+const SYMREF_MAXDEPTH: usize = 5;
+
+/// The branch a fresh repository's HEAD points at
+pub const DEFAULT_BRANCH: &str = "master";
+
+/// Move whatever HEAD points at to a new commit: the current branch, or HEAD itself when detached
 pub fn update_head(oid: &str) {
-    fs::write(head_path(), oid).expect("Couldn't write OID to HEAD");
+    let name = match read_symbolic_ref("HEAD") {
+        Some(target) => target,
+        None => String::from("HEAD"),
+    };
+    update_ref(&name, oid).expect("Couldn't write OID to HEAD");
 }
 
+/// The commit HEAD resolves to, if there is one yet
 pub fn read_head() -> Option<String> {
-    match fs::read_to_string(head_path()) {
-        Ok(head) => Some(head),
-        Err(_) => None,
+    resolve("HEAD")
+}
+
+/// The name of the branch HEAD points at, like `master`, or None when HEAD is detached
+pub fn current_branch() -> Option<String> {
+    read_symbolic_ref("HEAD")
+        .and_then(|target| target.strip_prefix("refs/heads/").map(String::from))
+}
+
+/// Point HEAD at a branch, which doesn't have to exist yet
+pub fn set_head_to_branch(branch: &str) -> Result<(), io::Error> {
+    write_ref_file("HEAD", &format!("{}refs/heads/{}", SYMREF_PREFIX, branch))
+}
+
+/// Follow a ref through any symbolic refs to the object ID it holds. Like git, a chain of
+/// more than `SYMREF_MAXDEPTH` refs can't be resolved.
+pub fn resolve(name: &str) -> Option<String> {
+    let mut name = name.to_string();
+    for _ in 0..SYMREF_MAXDEPTH {
+        match read_ref_file(&name)? {
+            RefContent::Symbolic(target) => name = target,
+            RefContent::Direct(oid) => return Some(oid),
+        }
+    }
+    None
+}
+
+/// Where a symbolic ref (like `HEAD`) points, or None if it holds an object ID directly
+pub fn read_symbolic_ref(name: &str) -> Option<String> {
+    match read_ref_file(name)? {
+        RefContent::Symbolic(target) => Some(target),
+        RefContent::Direct(_) => None,
+    }
+}
+
+/// Point a ref (like `refs/heads/master`) at an object, creating it if needed
+pub fn update_ref(name: &str, oid: &str) -> Result<(), io::Error> {
+    write_ref_file(name, oid)
+}
+
+/// Remove a ref, whether it's a loose file (along with any directories that leaves empty) or
+/// an entry in `packed-refs`, or both
+pub fn delete_ref(name: &str) -> Result<(), io::Error> {
+    // A packed ref's directories may not exist, but its lock needs somewhere to go, like git's
+    let path = ref_path(name);
+    create_parent_directories(&path)?;
+    let lockfile = Lockfile::acquire(&path)?;
+
+    let packed = read_packed_refs();
+    let was_packed = packed.iter().any(|packed_ref| packed_ref.name == name);
+    if was_packed {
+        let remaining: Vec<PackedRef> = packed
+            .into_iter()
+            .filter(|packed_ref| packed_ref.name != name)
+            .collect();
+        write_packed_refs(remaining)?;
+    }
+
+    if !remove_loose_ref(&path, lockfile)? && !was_packed {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No such ref: {}", name),
+        ));
+    }
+    Ok(())
+}
+
+/// Every ref under a prefix like `refs/heads/`, loose or packed, with the object ID it resolves
+/// to, sorted by name
+pub fn list(prefix: &str) -> Vec<(String, String)> {
+    let mut names = Vec::new();
+    collect_ref_names(&ref_path(prefix), prefix.trim_end_matches('/'), &mut names);
+    names.extend(
+        read_packed_refs()
+            .into_iter()
+            .map(|packed_ref| packed_ref.name)
+            .filter(|name| name.starts_with(prefix)),
+    );
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| resolve(&name).map(|oid| (name, oid)))
+        .collect()
+}
+
+/// A ref stored in the `packed-refs` file rather than a file of its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedRef {
+    pub name: String,
+    pub oid: String,
+    /// What the ref finally points at when it names an annotated tag (a `^` line)
+    pub peeled: Option<String>,
+}
+
+/// Every ref in `packed-refs`, in the order they're stored
+pub fn read_packed_refs() -> Vec<PackedRef> {
+    let content = match fs::read_to_string(git_directory().join(PACKED_REFS)) {
+        Ok(content) => content,
+        Err(_) => return vec![],
+    };
+
+    let mut packed: Vec<PackedRef> = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(peeled) = line.strip_prefix('^') {
+            if let Some(last) = packed.last_mut() {
+                last.peeled = Some(peeled.to_string());
+            }
+            continue;
+        }
+        if let Some((oid, name)) = line.split_once(' ') {
+            packed.push(PackedRef {
+                name: name.to_string(),
+                oid: oid.to_string(),
+                peeled: None,
+            });
+        }
+    }
+    packed
+}
+
+/// Move refs into `packed-refs`, replacing any packed refs of the same name, then remove their
+/// loose files
+pub fn pack(refs: Vec<PackedRef>) -> Result<(), io::Error> {
+    // Hold every loose ref before touching `packed-refs`. Refs that are only packed have no
+    // file to remove, and maybe no directory for a lock either.
+    let mut loose = Vec::new();
+    for packed_ref in &refs {
+        let path = ref_path(&packed_ref.name);
+        if path.is_file() {
+            let lockfile = Lockfile::acquire(&path)?;
+            loose.push((path, lockfile));
+        }
+    }
+
+    let names: Vec<String> = refs
+        .iter()
+        .map(|packed_ref| packed_ref.name.clone())
+        .collect();
+    let packed: Vec<PackedRef> = read_packed_refs()
+        .into_iter()
+        .filter(|packed_ref| !names.contains(&packed_ref.name))
+        .chain(refs)
+        .collect();
+    write_packed_refs(packed)?;
+
+    for (path, lockfile) in loose {
+        remove_loose_ref(&path, lockfile)?;
     }
+    Ok(())
+}
+
+/// Whether a name follows git's rules for refs, so it can be used as a branch or tag name
+pub fn is_valid_name(name: &str) -> bool {
+    let forbidden = |c: char| c.is_ascii_control() || " ~^:?*[\\".contains(c);
+    !name.is_empty()
+        && name != "@"
+        && !name.starts_with('-')
+        && !name.ends_with('/')
+        && !name.ends_with('.')
+        && !name.contains("..")
+        && !name.contains("@{")
+        && !name.contains("//")
+        && !name.chars().any(forbidden)
+        && name
+            .split('/')
+            .all(|component| !component.starts_with('.') && !component.ends_with(".lock"))
+}
+
+fn collect_ref_names(directory: &Path, prefix: &str, names: &mut Vec<String>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            collect_ref_names(&entry.path(), &name, names);
+        } else if !name.ends_with(".lock") {
+            names.push(name);
+        }
+    }
+}
+
+enum RefContent {
+    Symbolic(String),
+    Direct(String),
+}
+
+// Read a loose ref, falling back to `packed-refs` when there isn't one
+fn read_ref_file(name: &str) -> Option<RefContent> {
+    let content = match fs::read_to_string(ref_path(name)) {
+        Ok(content) => content,
+        Err(_) => {
+            return read_packed_refs()
+                .into_iter()
+                .find(|packed_ref| packed_ref.name == name)
+                .map(|packed_ref| RefContent::Direct(packed_ref.oid))
+        }
+    };
+    let content = content.trim();
+    match content.strip_prefix(SYMREF_PREFIX) {
+        Some(target) => Some(RefContent::Symbolic(target.to_string())),
+        None => Some(RefContent::Direct(content.to_string())),
+    }
+}
+
+// Write a ref under a lock, the way git does, creating parent directories as needed
+fn write_ref_file(name: &str, content: &str) -> Result<(), io::Error> {
+    let path = ref_path(name);
+    create_parent_directories(&path)?;
+    let mut lockfile = Lockfile::acquire(&path)?;
+    lockfile.write(format!("{}\n", content).as_bytes())?;
+    lockfile.commit()
+}
+
+// Rewrite `packed-refs` in name order, which its header promises readers
+fn write_packed_refs(mut packed: Vec<PackedRef>) -> Result<(), io::Error> {
+    packed.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut content = String::from(PACKED_REFS_HEADER);
+    for packed_ref in packed {
+        content.push_str(&format!("{} {}\n", packed_ref.oid, packed_ref.name));
+        if let Some(peeled) = &packed_ref.peeled {
+            content.push_str(&format!("^{}\n", peeled));
+        }
+    }
+
+    let mut lockfile = Lockfile::acquire(&git_directory().join(PACKED_REFS))?;
+    lockfile.write(content.as_bytes())?;
+    lockfile.commit()
+}
+
+// Remove a loose ref's file, saying whether there was one, then any directories that leaves
+// empty once its lock is gone too. Like git, this stops at a ref's category, so `refs/heads`
+// and `refs/tags` always stay.
+fn remove_loose_ref(path: &Path, lockfile: Lockfile) -> Result<bool, io::Error> {
+    let existed = match fs::remove_file(path) {
+        Ok(()) => true,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => false,
+        Err(error) => return Err(error),
+    };
+    drop(lockfile);
+
+    let refs_directory = git_directory().join("refs");
+    let mut directory = path.parent();
+    while let Some(parent) = directory {
+        if parent.parent() == Some(refs_directory.as_path()) || fs::remove_dir(parent).is_err() {
+            break;
+        }
+        directory = parent.parent();
+    }
+    Ok(existed)
+}
+
+fn create_parent_directories(path: &Path) -> Result<(), io::Error> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+fn ref_path(name: &str) -> PathBuf {
+    git_directory().join(name)
 }
 
-fn head_path() -> PathBuf {
+fn git_directory() -> PathBuf {
     let current_dir = env::current_dir().expect("Couldn't get current directory");
-    current_dir.join(".git").join("HEAD")
+    current_dir.join(".git")
 }
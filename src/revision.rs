@@ -0,0 +1,286 @@
+use crate::{
+    index::Index,
+    objects::{self, Object, ParsedObject},
+    refs, workspace,
+};
+use std::fmt;
+
+/// How many characters of an object ID git shows by default
+pub const ABBREVIATED_LENGTH: usize = 7;
+
+/// Why a revision couldn't be resolved
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Nothing matches the revision
+    Unknown(String),
+    /// An abbreviated object ID matches several objects, each described like git's hints
+    Ambiguous {
+        prefix: String,
+        candidates: Vec<String>,
+    },
+    /// A `<rev>:<path>` or `:<path>` names a path that isn't there
+    MissingPath {
+        path: String,
+        within: Option<String>,
+        on_disk: bool,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unknown(revision) => write!(
+                f,
+                "ambiguous argument '{}': unknown revision or path not in the working tree.",
+                revision
+            ),
+            Error::Ambiguous { prefix, candidates } => {
+                write!(
+                    f,
+                    "short object ID {} is ambiguous\nhint: The candidates are:",
+                    prefix
+                )?;
+                for candidate in candidates {
+                    write!(f, "\nhint:   {}", candidate)?;
+                }
+                Ok(())
+            }
+            Error::MissingPath {
+                path,
+                within,
+                on_disk,
+            } => match (within, on_disk) {
+                (Some(revision), true) => write!(
+                    f,
+                    "path '{}' exists on disk, but not in '{}'",
+                    path, revision
+                ),
+                (Some(revision), false) => {
+                    write!(f, "path '{}' does not exist in '{}'", path, revision)
+                }
+                (None, true) => write!(f, "path '{}' exists on disk, but not in the index", path),
+                (None, false) => write!(
+                    f,
+                    "path '{}' does not exist (neither on disk nor in the index)",
+                    path
+                ),
+            },
+        }
+    }
+}
+
+/// Resolve a revision to the object ID it names. This understands ref names (looked up in the
+/// same order git does), full or unique abbreviated object IDs, `@` for HEAD, the `^<n>`,
+/// `~<n>` and `^{<type>}` suffixes, `<rev>:<path>` for something in a commit's tree, and
+/// `:<path>` for something in the index.
+pub fn resolve(revision: &str) -> Result<String, Error> {
+    if let Some(colon) = revision.find(':') {
+        let path = revision[colon + 1..].trim_end_matches('/');
+        return match &revision[..colon] {
+            "" => index_path(path),
+            within => {
+                let tree = peel(&resolve(within)?, "tree")
+                    .ok_or_else(|| Error::Unknown(revision.to_string()))?;
+                tree_path(&tree, path).ok_or_else(|| Error::MissingPath {
+                    path: path.to_string(),
+                    within: Some(within.to_string()),
+                    on_disk: workspace::exists(path),
+                })
+            }
+        };
+    }
+
+    let unknown = || Error::Unknown(revision.to_string());
+    let base_end = revision.find(['^', '~']).unwrap_or(revision.len());
+    let mut oid = base(&revision[..base_end], revision)?;
+    let mut suffixes = &revision[base_end..];
+
+    while !suffixes.is_empty() {
+        if let Some(rest) = suffixes.strip_prefix("^{") {
+            let close = rest.find('}').ok_or_else(unknown)?;
+            oid = peel(&oid, &rest[..close]).ok_or_else(unknown)?;
+            suffixes = &rest[close + 1..];
+            continue;
+        }
+
+        let (operator, rest) = if let Some(rest) = suffixes.strip_prefix('^') {
+            ('^', rest)
+        } else if let Some(rest) = suffixes.strip_prefix('~') {
+            ('~', rest)
+        } else {
+            return Err(unknown());
+        };
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number = match digits {
+            0 => 1,
+            _ => rest[..digits].parse().map_err(|_| unknown())?,
+        };
+        oid = match operator {
+            '^' => parent(&oid, number),
+            _ => ancestor(&oid, number),
+        }
+        .ok_or_else(unknown)?;
+        suffixes = &rest[digits..];
+    }
+    Ok(oid)
+}
+
+/// Resolve a revision to a commit, peeling whatever it names until a commit turns up
+pub fn resolve_commit(revision: &str) -> Result<String, Error> {
+    let oid = resolve(revision)?;
+    peel(&oid, "commit").ok_or_else(|| Error::Unknown(revision.to_string()))
+}
+
+/// Resolve a revision to a tree, peeling tags and commits down to the tree they record
+pub fn resolve_tree(revision: &str) -> Result<String, Error> {
+    let oid = resolve(revision)?;
+    peel(&oid, "tree").ok_or_else(|| Error::Unknown(revision.to_string()))
+}
+
+/// The shortest abbreviation of an object ID, at least the default length, that no other
+/// object in the database shares
+pub fn abbreviate(oid: &str) -> String {
+    (ABBREVIATED_LENGTH..oid.len())
+        .map(|length| &oid[..length])
+        .find(|prefix| objects::find_by_prefix(prefix).map_or(true, |found| found.len() <= 1))
+        .unwrap_or(oid)
+        .to_string()
+}
+
+// A ref name, `@`, or an object ID, possibly abbreviated
+fn base(name: &str, revision: &str) -> Result<String, Error> {
+    let name = match name {
+        "@" => "HEAD",
+        name => name,
+    };
+    if let Some(oid) = ref_candidates(name)
+        .iter()
+        .find_map(|name| refs::resolve(name))
+    {
+        return Ok(oid);
+    }
+
+    if name.len() >= 4 && name.len() <= 40 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        let mut found = objects::find_by_prefix(name).unwrap_or_default();
+        match found.len() {
+            0 => {}
+            1 => return Ok(found.remove(0)),
+            _ => {
+                return Err(Error::Ambiguous {
+                    prefix: name.to_string(),
+                    candidates: describe_candidates(&found),
+                })
+            }
+        }
+    }
+    Err(Error::Unknown(revision.to_string()))
+}
+
+// Where a ref with this name might live, in the order git searches. Only names like
+// `HEAD` or `ORIG_HEAD` are looked up directly in `.git`, so `config` never counts as a ref.
+fn ref_candidates(name: &str) -> Vec<String> {
+    if !refs::is_valid_name(name) {
+        return vec![];
+    }
+    let mut candidates = Vec::new();
+    if name.starts_with("refs/") || name.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+        candidates.push(name.to_string());
+    }
+    candidates.push(format!("refs/{}", name));
+    candidates.push(format!("refs/tags/{}", name));
+    candidates.push(format!("refs/heads/{}", name));
+    candidates.push(format!("refs/remotes/{}", name));
+    candidates.push(format!("refs/remotes/{}/HEAD", name));
+    candidates
+}
+
+/// Follow an object until it's of the kind asked for. An empty kind takes whatever comes first
+/// that can't be peeled any further, and `object` takes anything at all.
+pub fn peel(oid: &str, kind: &str) -> Option<String> {
+    let object = objects::read(oid).ok()?;
+    if kind == "object" || kind == object.kind().name() {
+        return Some(oid.to_string());
+    }
+    match (object, kind) {
+        (ParsedObject::Tag(tag), _) => peel(&tag.object, kind),
+        (_, "") => Some(oid.to_string()),
+        (ParsedObject::Commit(commit), "tree") => Some(commit.tree),
+        _ => None,
+    }
+}
+
+// The `n`th parent of a commit, where the 0th is the commit itself
+fn parent(oid: &str, n: usize) -> Option<String> {
+    let oid = peel(oid, "commit")?;
+    match n {
+        0 => Some(oid),
+        n => objects::read_commit(&oid).ok()?.parents.get(n - 1).cloned(),
+    }
+}
+
+// The commit `n` generations back, following first parents
+fn ancestor(oid: &str, n: usize) -> Option<String> {
+    (0..n).try_fold(peel(oid, "commit")?, |oid, _| parent(&oid, 1))
+}
+
+fn tree_path(tree: &str, path: &str) -> Option<String> {
+    path.split('/')
+        .filter(|component| !component.is_empty())
+        .try_fold(tree.to_string(), |oid, component| {
+            objects::read_tree(&oid)
+                .ok()?
+                .entries
+                .into_iter()
+                .find(|entry| entry.name == component)
+                .map(|entry| entry.oid)
+        })
+}
+
+fn index_path(path: &str) -> Result<String, Error> {
+    let index = Index::load().map_err(|_| Error::Unknown(format!(":{}", path)))?;
+    match index.entry(path) {
+        Some(entry) => Ok(entry.oid.to_owned()),
+        None => Err(Error::MissingPath {
+            path: path.to_string(),
+            within: None,
+            on_disk: workspace::exists(path),
+        }),
+    }
+}
+
+// Describe each object an abbreviation could mean, the way git's hints do: tags, then
+// commits, then trees, then blobs, like `1a2b3c4 commit 2019-10-02 - Fix the thing`
+fn describe_candidates(oids: &[String]) -> Vec<String> {
+    let mut described: Vec<(usize, String)> = oids
+        .iter()
+        .map(|oid| match objects::read(oid) {
+            Ok(ParsedObject::Tag(tag)) => (
+                0,
+                format!(
+                    "{} tag {} - {}",
+                    abbreviate(oid),
+                    tag.tagger
+                        .map(|tagger| tagger.format_date_as("%Y-%m-%d"))
+                        .unwrap_or_default(),
+                    tag.name
+                ),
+            ),
+            Ok(ParsedObject::Commit(commit)) => (
+                1,
+                format!(
+                    "{} commit {} - {}",
+                    abbreviate(oid),
+                    commit.author.format_date_as("%Y-%m-%d"),
+                    commit.subject()
+                ),
+            ),
+            Ok(ParsedObject::Tree(_)) => (2, format!("{} tree", abbreviate(oid))),
+            Ok(ParsedObject::Blob(_)) => (3, format!("{} blob", abbreviate(oid))),
+            Err(_) => (4, format!("{} [bad object]", abbreviate(oid))),
+        })
+        .collect();
+    described.sort();
+    described.into_iter().map(|(_, line)| line).collect()
+}
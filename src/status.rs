@@ -0,0 +1,210 @@
+use crate::{
+    ignore::Ignore,
+    index::{self, Index},
+    objects::{self, Blob, Entry, Object},
+    refs, workspace,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, Metadata},
+    io,
+};
+
+/// How a file differs between two of HEAD, the index and the workspace
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Added,
+    Deleted,
+    Modified,
+}
+
+impl Change {
+    /// The letter git uses for this change in short status output
+    pub fn code(self) -> char {
+        match self {
+            Change::Added => 'A',
+            Change::Deleted => 'D',
+            Change::Modified => 'M',
+        }
+    }
+}
+
+/// A comparison of HEAD's tree, the index and the workspace
+#[derive(Debug)]
+pub struct Status {
+    /// Everything in HEAD's tree, by path
+    pub head_tree: BTreeMap<String, Entry>,
+    /// Changes staged in the index, compared with HEAD
+    pub index_changes: BTreeMap<String, Change>,
+    /// Changes in the workspace that aren't staged, compared with the index
+    pub workspace_changes: BTreeMap<String, Change>,
+    /// Paths left conflicted by a merge, with the stages (1 to 3) the index holds for them
+    pub conflicts: BTreeMap<String, Vec<u8>>,
+    /// Files that aren't in the index, and directories with no tracked files inside
+    /// (which end in `/`)
+    pub untracked: BTreeSet<String>,
+}
+
+impl Status {
+    /// Inspect the repository in the current directory. Files whose stat data no longer
+    /// matches the index are hashed, and the index is refreshed if it isn't locked by someone else.
+    pub fn scan() -> Result<Status, io::Error> {
+        let mut index = match Index::load_for_update() {
+            Ok(index) => index,
+            Err(_) => Index::load()?,
+        };
+
+        let head_tree = match refs::read_head() {
+            Some(head) => objects::flatten_tree(&objects::read_commit(&head)?.tree)?,
+            None => BTreeMap::new(),
+        };
+
+        let mut status = Status {
+            head_tree,
+            index_changes: BTreeMap::new(),
+            workspace_changes: BTreeMap::new(),
+            conflicts: BTreeMap::new(),
+            untracked: BTreeSet::new(),
+        };
+        status.scan_workspace(&index, "")?;
+        status.check_conflicts(&index);
+        status.check_index_against_head(&index);
+        let refreshed = status.check_index_against_workspace(&mut index)?;
+
+        if refreshed && index.is_locked() {
+            index.write_updates()?;
+        }
+        Ok(status)
+    }
+
+    /// Every path with a change between HEAD, the index or the workspace, or a conflict
+    pub fn changed_paths(&self) -> BTreeSet<&String> {
+        self.index_changes
+            .keys()
+            .chain(self.workspace_changes.keys())
+            .chain(self.conflicts.keys())
+            .collect()
+    }
+
+    /// The two letters git's short status uses for a conflicted path, saying what each side
+    /// did to it, like `UU` when both modified it or `DU` when we deleted it
+    pub fn conflict_code(&self, path: &str) -> Option<&'static str> {
+        let code = match self.conflicts.get(path)?.as_slice() {
+            [1, 2, 3] => "UU",
+            [2, 3] => "AA",
+            [1, 2] => "UD",
+            [1, 3] => "DU",
+            [2] => "AU",
+            [3] => "UA",
+            _ => "DD",
+        };
+        Some(code)
+    }
+
+    // Find untracked files, only descending into directories that hold tracked files
+    fn scan_workspace(&mut self, index: &Index, directory: &str) -> Result<(), io::Error> {
+        let ignore: Ignore = Default::default();
+        let mut entries = ignore.ignore_items(
+            fs::read_dir(workspace::root().join(directory))?
+                .map(|dir_entry| dir_entry.map(|dir_entry| dir_entry.path()))
+                .collect::<Result<Vec<_>, io::Error>>()?,
+        );
+        entries.sort();
+
+        for entry in entries {
+            let name = entry.file_name().unwrap().to_string_lossy().into_owned();
+            let path = match directory.len() {
+                0 => name,
+                _ => format!("{}/{}", directory, name),
+            };
+            let is_dir = workspace::stat(&path)?.is_dir();
+
+            if is_dir && index.is_tracked_directory(&path) {
+                self.scan_workspace(index, &path)?;
+            } else if is_dir {
+                if !workspace::list_files(&path)?.is_empty() {
+                    self.untracked.insert(format!("{}/", path));
+                }
+            } else if !index.is_tracked_file(&path) {
+                self.untracked.insert(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_conflicts(&mut self, index: &Index) {
+        for entry in index.entries().filter(|entry| entry.stage() != 0) {
+            self.conflicts
+                .entry(entry.path.to_owned())
+                .or_default()
+                .push(entry.stage());
+        }
+    }
+
+    fn check_index_against_head(&mut self, index: &Index) {
+        for entry in index.entries().filter(|entry| entry.stage() == 0) {
+            match self.head_tree.get(&entry.path) {
+                None => {
+                    self.index_changes
+                        .insert(entry.path.to_owned(), Change::Added);
+                }
+                Some(head) if head.oid != entry.oid || head.mode != entry.tree_mode() => {
+                    self.index_changes
+                        .insert(entry.path.to_owned(), Change::Modified);
+                }
+                _ => {}
+            }
+        }
+        for path in self.head_tree.keys() {
+            if !index.is_tracked_file(path) {
+                self.index_changes.insert(path.to_owned(), Change::Deleted);
+            }
+        }
+    }
+
+    // Returns whether any entry's stat data was refreshed, so the index needs writing
+    fn check_index_against_workspace(&mut self, index: &mut Index) -> Result<bool, io::Error> {
+        let mut refreshed = Vec::new();
+
+        for entry in index.entries().filter(|entry| entry.stage() == 0) {
+            let metadata = match workspace::stat(&entry.path) {
+                Ok(metadata) if !metadata.is_dir() => metadata,
+                _ => {
+                    self.workspace_changes
+                        .insert(entry.path.to_owned(), Change::Deleted);
+                    continue;
+                }
+            };
+
+            if workspace_differs(entry, &metadata) {
+                self.workspace_changes
+                    .insert(entry.path.to_owned(), Change::Modified);
+            } else if !entry.times_match(&metadata) || entry.stat_differs(&metadata) {
+                refreshed.push((entry.path.to_owned(), metadata));
+            }
+        }
+
+        for (path, metadata) in &refreshed {
+            index.update_stat(path, metadata);
+        }
+        Ok(!refreshed.is_empty())
+    }
+}
+
+/// Whether a file in the workspace has different content (or mode) from its index entry,
+/// trusting the cached stat data and only hashing the file when the timestamps disagree or the
+/// entry was smudged
+pub fn workspace_differs(entry: &index::Entry, metadata: &Metadata) -> bool {
+    if entry.size == 0 && metadata.len() != 0 {
+        // Racily clean entries are smudged by zeroing their size when the index is written,
+        // so only the mode and the file's content can say whether it changed
+        if entry.mode != u32::from_str_radix(objects::mode_for_metadata(metadata), 8).unwrap() {
+            return true;
+        }
+    } else if entry.stat_differs(metadata) {
+        return true;
+    } else if entry.times_match(metadata) {
+        return false;
+    }
+    Blob::from_file(workspace::root().join(&entry.path)).id() != entry.oid
+}
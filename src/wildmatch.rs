@@ -0,0 +1,252 @@
+// Shell-style glob matching the way git's wildmatch does it, for patterns like `v1.*`:
+// `*` matches any run of characters, `?` any one character, `[...]` any character in a set or
+// range (`[!...]` or `[^...]` any character outside it), and `\` makes the next character
+// literal. When matching paths, only `**` crosses slashes.
+
+/// Tweaks to how a pattern matches, like git's `WM_` flags
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Flags {
+    /// Treat the text as a path: `*`, `?` and brackets don't match `/`, a `**/` matches any
+    /// number of directories (including none), and a trailing `/**` anything inside one
+    pub pathname: bool,
+    /// Ignore case, for letters in ranges too
+    pub casefold: bool,
+}
+
+/// Whether `text` matches the glob `pattern` in full
+pub fn wildmatch(pattern: &str, text: &str) -> bool {
+    wildmatch_with(pattern, text, Flags::default())
+}
+
+/// Whether `text` matches the glob `pattern` in full, given some flags
+pub fn wildmatch_with(pattern: &str, text: &str, flags: Flags) -> bool {
+    Matcher {
+        pattern: pattern.as_bytes(),
+        flags,
+    }
+    .matches(0, text.as_bytes())
+}
+
+struct Matcher<'a> {
+    pattern: &'a [u8],
+    flags: Flags,
+}
+
+impl Matcher<'_> {
+    // Whether the pattern, from some position on, matches the rest of the text
+    fn matches(&self, position: usize, text: &[u8]) -> bool {
+        let pattern = &self.pattern[position..];
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some((b'*', _)) => self.star(position, text),
+            Some((b'?', _)) => match text.split_first() {
+                Some((&byte, rest)) => !self.is_separator(byte) && self.matches(position + 1, rest),
+                None => false,
+            },
+            Some((b'[', rest)) => match (text.split_first(), bracket(rest)) {
+                (Some((&byte, text)), Some((set, length))) => {
+                    !self.is_separator(byte)
+                        && set.contains(byte, self.flags.casefold)
+                        && self.matches(position + 1 + length, text)
+                }
+                (None, _) => false,
+                // An unclosed bracket is just a bracket
+                (Some((b'[', text)), None) => self.matches(position + 1, text),
+                (Some(_), None) => false,
+            },
+            Some((b'\\', rest)) if !rest.is_empty() => self.literal(position + 1, text),
+            Some(_) => self.literal(position, text),
+        }
+    }
+
+    fn literal(&self, position: usize, text: &[u8]) -> bool {
+        match text.split_first() {
+            Some((&byte, rest)) => {
+                self.same(self.pattern[position], byte) && self.matches(position + 1, rest)
+            }
+            None => false,
+        }
+    }
+
+    // A run of stars. Without `pathname` they all match anything; with it, one star stops at a
+    // slash, and `**` only crosses slashes as a whole path component.
+    fn star(&self, position: usize, text: &[u8]) -> bool {
+        let stars = self.pattern[position..]
+            .iter()
+            .take_while(|&&byte| byte == b'*')
+            .count();
+        let after = position + stars;
+        let rest = &self.pattern[after..];
+
+        let match_slash = match (self.flags.pathname, stars) {
+            (false, _) => true,
+            (true, 1) => false,
+            (true, _) => {
+                let starts_component = position == 0 || self.pattern[position - 1] == b'/';
+                let ends_component = rest.is_empty() || rest[0] == b'/' || rest.starts_with(b"\\/");
+                if starts_component && rest.first() == Some(&b'/') {
+                    // `**/` matching no directories at all
+                    if self.matches(after + 1, text) {
+                        return true;
+                    }
+                }
+                starts_component && ends_component
+            }
+        };
+
+        if rest.is_empty() {
+            return match_slash || !text.contains(&b'/');
+        }
+        (0..=text.len())
+            .take_while(|&skipped| match_slash || skipped == 0 || text[skipped - 1] != b'/')
+            .any(|skipped| self.matches(after, &text[skipped..]))
+    }
+
+    fn is_separator(&self, byte: u8) -> bool {
+        self.flags.pathname && byte == b'/'
+    }
+
+    fn same(&self, one: u8, other: u8) -> bool {
+        match self.flags.casefold {
+            true => one.eq_ignore_ascii_case(&other),
+            false => one == other,
+        }
+    }
+}
+
+// A bracket expression's characters and ranges, and whether it's negated
+struct Set<'a> {
+    negated: bool,
+    items: &'a [u8],
+}
+
+impl Set<'_> {
+    fn contains(&self, byte: u8, casefold: bool) -> bool {
+        let candidates = match casefold {
+            true => [byte.to_ascii_lowercase(), byte.to_ascii_uppercase()],
+            false => [byte, byte],
+        };
+        let mut found = false;
+        let mut index = 0;
+        while index < self.items.len() {
+            let mut low = self.items[index];
+            if low == b'\\' && index + 1 < self.items.len() {
+                index += 1;
+                low = self.items[index];
+            }
+            if index + 2 < self.items.len() && self.items[index + 1] == b'-' {
+                let high = self.items[index + 2];
+                found |= candidates.iter().any(|&c| low <= c && c <= high);
+                index += 3;
+            } else {
+                found |= candidates.contains(&low);
+                index += 1;
+            }
+        }
+        found != self.negated
+    }
+}
+
+// A bracket expression, just past its `[`, and how much of the pattern it takes up with its
+// closing `]`, or None if it's never closed. A `]` straight after the opening (or its
+// negation) is literal.
+fn bracket(pattern: &[u8]) -> Option<(Set<'_>, usize)> {
+    let (negated, start) = match pattern.first() {
+        Some(b'!') | Some(b'^') => (true, 1),
+        _ => (false, 0),
+    };
+    let items = &pattern[start..];
+    let mut index = match items.first() {
+        Some(b']') => 1,
+        _ => 0,
+    };
+    while index < items.len() {
+        match items[index] {
+            b'\\' => index += 2,
+            b']' => {
+                let set = Set {
+                    negated,
+                    items: &items[..index],
+                };
+                return Some((set, start + index + 1));
+            }
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_match(pattern: &str, text: &str) -> bool {
+        let flags = Flags {
+            pathname: true,
+            casefold: false,
+        };
+        wildmatch_with(pattern, text, flags)
+    }
+
+    #[test]
+    fn matches_stars_and_question_marks() {
+        assert!(wildmatch("v1.*", "v1.0"));
+        assert!(wildmatch("v1.*", "v1."));
+        assert!(wildmatch("*", "release/v2"));
+        assert!(wildmatch("v?.0", "v2.0"));
+        assert!(!wildmatch("v?.0", "v10.0"));
+        assert!(!wildmatch("v1.*", "v2.0"));
+        assert!(wildmatch("*rc*", "v1.0-rc1"));
+    }
+
+    #[test]
+    fn matches_bracket_expressions() {
+        assert!(wildmatch("v[0-9]", "v7"));
+        assert!(!wildmatch("v[0-9]", "vx"));
+        assert!(wildmatch("v[!0-9]", "vx"));
+        assert!(wildmatch("v[^0-9]", "vx"));
+        assert!(wildmatch("[]a]", "]"));
+        assert!(wildmatch("v[", "v["));
+        assert!(wildmatch("[a-c]x*", "bxy"));
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert!(wildmatch("v\\*", "v*"));
+        assert!(!wildmatch("v\\*", "v1"));
+    }
+
+    #[test]
+    fn keeps_single_stars_within_a_directory_for_paths() {
+        assert!(path_match("/home/*/.git", "/home/mina/.git"));
+        assert!(!path_match("/home/*/.git", "/home/mina/work/.git"));
+        assert!(!path_match("/home/?", "/home/a/"));
+        assert!(path_match("feature/*", "feature/one"));
+        assert!(!path_match("feature/*", "feature/one/two"));
+        assert!(!path_match("a[/]b", "a/b"));
+    }
+
+    #[test]
+    fn crosses_directories_with_double_stars_for_paths() {
+        assert!(path_match("**/work/**", "/home/mina/work/castle/.git"));
+        assert!(path_match("/home/**/.git", "/home/.git"));
+        assert!(path_match("/home/**/.git", "/home/mina/work/.git"));
+        assert!(path_match("feature/**", "feature/one/two"));
+        assert!(!path_match("**/work/**", "/home/mina/homework/.git"));
+        // Not a whole component, so it's just a star
+        assert!(!path_match("/home/a**/.git", "/home/ab/cd/.git"));
+        assert!(path_match("/home/a**/.git", "/home/ab/.git"));
+    }
+
+    #[test]
+    fn ignores_case_when_asked() {
+        let flags = Flags {
+            pathname: true,
+            casefold: true,
+        };
+        assert!(wildmatch_with("**/Work/**", "/home/work/x/.git", flags));
+        assert!(wildmatch_with("[A-C]x", "bx", flags));
+        assert!(wildmatch_with("[a-c]x", "BX", flags));
+        assert!(!wildmatch("[a-c]x", "BX"));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::ignore::Ignore;
+use std::{
+    env,
+    ffi::OsStr,
+    fs::{self, Metadata},
+    io,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{symlink, PermissionsExt},
+    },
+    path::{Component, Path, PathBuf},
+};
+
+/// Where the files being tracked live: the directory holding `.git`
+pub fn root() -> PathBuf {
+    env::current_dir().expect("Couldn't determine current directory")
+}
+
+/// Turn a path given by the user into the `/`-separated form used by the index and trees,
+/// relative to the root of the workspace (the root itself is an empty string). Like git, `.`
+/// and `..` are resolved without looking at the filesystem, and a path that ends up outside
+/// the workspace is an error.
+pub fn normalize(path: &Path) -> Result<String, io::Error> {
+    let root = root();
+    let mut resolved = PathBuf::new();
+    for component in root.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            component => resolved.push(component),
+        }
+    }
+
+    match resolved.strip_prefix(&root) {
+        Ok(relative) => Ok(relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<String>>()
+            .join("/")),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{}: '{}' is outside repository at '{}'",
+                path.display(),
+                path.display(),
+                root.display()
+            ),
+        )),
+    }
+}
+
+/// Find every file at or beneath a path in the workspace, skipping anything ignored and
+/// never following symlinks (which are tracked as links)
+pub fn list_files(path: &str) -> Result<Vec<String>, io::Error> {
+    let ignore: Ignore = Default::default();
+    let absolute = root().join(path);
+
+    if !stat(path)?.is_dir() {
+        return Ok(vec![path.to_string()]);
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(&absolute)?
+        .map(|dir_entry| dir_entry.map(|dir_entry| dir_entry.path()))
+        .collect::<Result<Vec<PathBuf>, io::Error>>()?;
+    entries.sort();
+
+    let mut files = Vec::new();
+    for entry in ignore.ignore_items(entries) {
+        let name = entry.file_name().unwrap().to_string_lossy().into_owned();
+        let relative = match path.len() {
+            0 => name,
+            _ => format!("{}/{}", path, name),
+        };
+        files.append(&mut list_files(&relative)?);
+    }
+    Ok(files)
+}
+
+/// Look up a file's metadata without following symlinks
+pub fn stat(path: &str) -> Result<Metadata, io::Error> {
+    fs::symlink_metadata(root().join(path))
+}
+
+/// Whether something exists at a path in the workspace, even a dangling symlink
+pub fn exists(path: &str) -> bool {
+    stat(path).is_ok()
+}
+
+/// Write a file with the content and mode (like `100755` or `120000`) it has in a tree,
+/// replacing whatever was at the path and creating parent directories as needed
+pub fn write_file(path: &str, content: &[u8], mode: &str) -> Result<(), io::Error> {
+    let absolute = root().join(path);
+    if let Some(parent) = absolute.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Ok(metadata) = fs::symlink_metadata(&absolute) {
+        match metadata.is_dir() {
+            true => fs::remove_dir_all(&absolute)?,
+            false => fs::remove_file(&absolute)?,
+        }
+    }
+
+    match mode {
+        "120000" => symlink(OsStr::from_bytes(content), &absolute),
+        _ => {
+            fs::write(&absolute, content)?;
+            let permissions = match mode {
+                "100755" => 0o755,
+                _ => 0o644,
+            };
+            fs::set_permissions(&absolute, fs::Permissions::from_mode(permissions))
+        }
+    }
+}
+
+/// Remove a file, then any directories above it that it leaves empty
+pub fn remove_file(path: &str) -> Result<(), io::Error> {
+    let absolute = root().join(path);
+    match fs::symlink_metadata(&absolute) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&absolute)?,
+        Ok(_) => fs::remove_file(&absolute)?,
+        Err(_) => {}
+    }
+
+    let root = root();
+    let mut directory = absolute.parent();
+    while let Some(parent) = directory {
+        if parent == root || fs::remove_dir(parent).is_err() {
+            break;
+        }
+        directory = parent.parent();
+    }
+    Ok(())
+}
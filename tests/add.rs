@@ -0,0 +1,142 @@
+mod common;
+
+use common::TestBed;
+use grit::{
+    commands::{add, init},
+    index::Index,
+};
+use std::{fs, path::PathBuf};
+
+// What grit staged, formatted like `git ls-files --stage`
+fn staged_in_workspace() -> String {
+    Index::load()
+        .unwrap()
+        .entries()
+        .map(|entry| {
+            format!(
+                "{} {} {}\t{}\n",
+                entry.tree_mode(),
+                entry.oid,
+                entry.stage(),
+                entry.path
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn stages_files_and_directories_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("LICENSE", "This is the license");
+    test_bed.create_file("src/main.rs", "fn main() {}");
+    test_bed.create_file("src/objects/blob.rs", "struct Blob;");
+    test_bed.create_file("notes/todo.txt", "Stage me later");
+
+    add(&[PathBuf::from("README"), PathBuf::from("src")]).unwrap();
+    test_bed.git_command(vec!["add", "README", "src"]);
+
+    assert_eq!(
+        staged_in_workspace(),
+        test_bed.git_output(vec!["ls-files", "--stage"])
+    );
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    add(&[PathBuf::from(".")]).unwrap();
+    test_bed.git_command(vec!["add", "."]);
+
+    assert_eq!(
+        staged_in_workspace(),
+        test_bed.git_output(vec!["ls-files", "--stage"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn stages_removals_and_replacements() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("nested/file.txt", "Soon to be a file");
+    add(&[PathBuf::from(".")]).unwrap();
+    test_bed.git_command(vec!["add", "."]);
+
+    for parallel in &[test_bed.workspace(), test_bed.twin()] {
+        fs::remove_file(parallel.join("README")).unwrap();
+        fs::remove_dir_all(parallel.join("nested")).unwrap();
+    }
+    test_bed.create_file("nested", "Not a directory anymore");
+
+    add(&[PathBuf::from(".")]).unwrap();
+    test_bed.git_command(vec!["add", "."]);
+
+    assert_eq!(
+        staged_in_workspace(),
+        test_bed.git_output(vec!["ls-files", "--stage"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_pathspecs_that_match_nothing() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.create_file("README", "This is the README");
+
+    let result = add(&[PathBuf::from("README"), PathBuf::from("nope")]);
+
+    assert_eq!(
+        result,
+        Err(String::from("pathspec 'nope' did not match any files"))
+    );
+    assert!(!test_bed.workspace().join(".git/index").exists());
+    assert!(!test_bed.workspace().join(".git/index.lock").exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn resolves_parent_directories_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+    test_bed.create_file("b", "Beside a");
+    test_bed.create_file("a/b", "Inside a");
+
+    add(&[PathBuf::from("a/../b"), PathBuf::from("./a/./b")]).unwrap();
+    test_bed.git_command(vec!["add", "a/../b", "./a/./b"]);
+    assert_eq!(
+        staged_in_workspace(),
+        test_bed.git_output(vec!["ls-files", "--stage"])
+    );
+
+    let root = test_bed.workspace();
+    for path in ["../x", "a/../../x"] {
+        assert_eq!(
+            add(&[PathBuf::from(path)]),
+            Err(format!(
+                "{}: '{}' is outside repository at '{}'",
+                path,
+                path,
+                root.display()
+            ))
+        );
+    }
+
+    test_bed.teardown();
+}
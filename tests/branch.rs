@@ -0,0 +1,164 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{branch, init, BranchAction, BranchError};
+use std::fs;
+
+fn create(name: &str, start_point: Option<&str>) -> Result<String, BranchError> {
+    branch(BranchAction::Create {
+        name: name.to_string(),
+        start_point: start_point.map(String::from),
+    })
+}
+
+#[test]
+fn creates_and_lists_branches_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+    let second = test_bed.commit_file("LICENSE", "Second");
+
+    create("topic", None).unwrap();
+    create("feature/nested", Some(&first)).unwrap();
+    create("from-branch", Some("feature/nested")).unwrap();
+
+    assert_eq!(
+        branch(BranchAction::List).unwrap(),
+        test_bed.workspace_git_output(vec!["branch"])
+    );
+    assert_eq!(
+        branch(BranchAction::List).unwrap(),
+        "  feature/nested\n  from-branch\n* master\n  topic\n"
+    );
+    assert_eq!(
+        fs::read_to_string(".git/refs/heads/topic").unwrap(),
+        format!("{}\n", second)
+    );
+    assert_eq!(
+        fs::read_to_string(".git/refs/heads/from-branch").unwrap(),
+        format!("{}\n", first)
+    );
+
+    assert!(
+        create("topic", None).is_err(),
+        "Replaced an existing branch"
+    );
+    assert!(
+        create("bad..name", None).is_err(),
+        "Accepted an invalid name"
+    );
+    assert!(create("nowhere", Some("no-such-branch")).is_err());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn renames_branches_and_follows_the_current_one() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    create("topic", None).unwrap();
+
+    branch(BranchAction::Rename {
+        old: Some(String::from("topic")),
+        new: String::from("subject"),
+    })
+    .unwrap();
+    branch(BranchAction::Rename {
+        old: None,
+        new: String::from("main"),
+    })
+    .unwrap();
+
+    assert_eq!(branch(BranchAction::List).unwrap(), "* main\n  subject\n");
+    assert_eq!(
+        fs::read_to_string(".git/HEAD").unwrap(),
+        "ref: refs/heads/main\n"
+    );
+    assert!(!test_bed.workspace().join(".git/refs/heads/topic").exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn deletes_only_merged_branches_unless_forced() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    create("merged", None).unwrap();
+    create("unmerged", None).unwrap();
+
+    // Commit on `unmerged` without a checkout command, by pointing HEAD at it directly
+    fs::write(".git/HEAD", "ref: refs/heads/unmerged\n").unwrap();
+    let ahead = test_bed.commit_file("LICENSE", "Ahead of master");
+    fs::write(".git/HEAD", "ref: refs/heads/master\n").unwrap();
+
+    let deleted = branch(BranchAction::Delete {
+        names: vec![String::from("merged")],
+        force: false,
+    });
+    assert!(deleted.unwrap().starts_with("Deleted branch merged (was "));
+
+    let refused = branch(BranchAction::Delete {
+        names: vec![String::from("unmerged")],
+        force: false,
+    });
+    assert!(refused
+        .unwrap_err()
+        .to_string()
+        .contains("is not fully merged"));
+
+    let forced = branch(BranchAction::Delete {
+        names: vec![String::from("unmerged")],
+        force: true,
+    });
+    assert_eq!(
+        forced.unwrap(),
+        format!("Deleted branch unmerged (was {}).\n", &ahead[..7])
+    );
+
+    let current = branch(BranchAction::Delete {
+        names: vec![String::from("master")],
+        force: true,
+    });
+    assert!(current.is_err(), "Deleted the current branch");
+    assert_eq!(branch(BranchAction::List).unwrap(), "* master\n");
+
+    test_bed.teardown();
+}
+
+#[test]
+fn deletes_every_branch_it_can_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let head = test_bed.commit_file("README", "First");
+    create("first", None).unwrap();
+    create("second", None).unwrap();
+
+    let deleted = branch(BranchAction::Delete {
+        names: vec![
+            String::from("first"),
+            String::from("missing"),
+            String::from("second"),
+        ],
+        force: false,
+    });
+    assert_eq!(
+        deleted,
+        Err(BranchError::NotDeleted {
+            output: format!(
+                "Deleted branch first (was {}).\nDeleted branch second (was {}).\n",
+                &head[..7],
+                &head[..7]
+            ),
+            errors: vec![String::from("branch 'missing' not found.")],
+        })
+    );
+    assert_eq!(branch(BranchAction::List).unwrap(), "* master\n");
+
+    test_bed.teardown();
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{cat_file, CatFileMode};
+use std::env;
+
+// Commit with real git, then ask both git and grit about the same objects
+fn twin_with_history(test_bed: &TestBed) {
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("src/main.rs", "fn main() {}");
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Written by git"]);
+    env::set_current_dir(test_bed.twin()).unwrap();
+}
+
+#[test]
+fn matches_git_for_every_mode_and_kind() {
+    let test_bed = TestBed::setup();
+    twin_with_history(&test_bed);
+
+    let modes = vec![
+        ("-t", CatFileMode::Type),
+        ("-s", CatFileMode::Size),
+        ("-p", CatFileMode::Pretty),
+    ];
+    for revision in &["HEAD", "HEAD^{tree}", "HEAD:README", "HEAD:src"] {
+        let oid = test_bed.git_output(vec!["rev-parse", revision]);
+        let oid = oid.trim();
+        for (flag, mode) in &modes {
+            let expected = test_bed.git_output(vec!["cat-file", flag, oid]);
+            let found = cat_file(*mode, oid).unwrap();
+            assert_eq!(
+                String::from_utf8(found).unwrap(),
+                expected,
+                "`cat-file {} {}` didn't match git for {}",
+                flag,
+                oid,
+                revision
+            );
+        }
+    }
+
+    test_bed.teardown();
+}
+
+#[test]
+fn checks_whether_objects_exist() {
+    let test_bed = TestBed::setup();
+    twin_with_history(&test_bed);
+
+    let oid = test_bed.git_output(vec!["rev-parse", "HEAD"]);
+    assert_eq!(cat_file(CatFileMode::Exists, oid.trim()), Ok(vec![]));
+    assert!(cat_file(
+        CatFileMode::Exists,
+        "0000000000000000000000000000000000000000"
+    )
+    .is_err());
+
+    test_bed.teardown();
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use common::{commit_everything, create_branch, read, write, TestBed};
+use grit::commands::{add, checkout, init, status, switch, StatusFormat};
+use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+// `master` and `topic` differ in every way a path can: changed, added, removed,
+// turned from a file into a directory, and made executable
+fn two_branches(test_bed: &TestBed) -> (String, String) {
+    init(Some(&test_bed.workspace()));
+    write("README", "On master");
+    write("shared.txt", "The same everywhere");
+    write("old/nested/gone.txt", "Only on master");
+    write("becomes_directory", "A file on master");
+    write("run.sh", "echo hi");
+    let master = commit_everything("On master");
+
+    create_branch("topic");
+    checkout("topic", None).unwrap();
+
+    write("README", "On topic");
+    fs::remove_dir_all("old").unwrap();
+    fs::remove_file("becomes_directory").unwrap();
+    write("becomes_directory/inside.txt", "A directory on topic");
+    write("new/file.txt", "Only on topic");
+    fs::set_permissions("run.sh", fs::Permissions::from_mode(0o755)).unwrap();
+    let topic = commit_everything("On topic");
+
+    (master, topic)
+}
+
+#[test]
+fn moves_the_workspace_index_and_head_between_branches() {
+    let test_bed = TestBed::setup();
+    let (_, topic) = two_branches(&test_bed);
+
+    assert_eq!(
+        checkout("master", None).unwrap(),
+        "Switched to branch 'master'\n"
+    );
+    assert_eq!(read(".git/HEAD"), "ref: refs/heads/master\n");
+    assert_eq!(read("README"), "On master");
+    assert_eq!(read("old/nested/gone.txt"), "Only on master");
+    assert_eq!(read("becomes_directory"), "A file on master");
+    assert!(!test_bed.workspace().join("new").exists());
+    assert_eq!(
+        fs::metadata("run.sh").unwrap().permissions().mode() & 0o111,
+        0
+    );
+    assert_eq!(status(StatusFormat::Porcelain).unwrap(), "");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["status", "--porcelain"]),
+        "",
+        "Real git sees changes after checking out master"
+    );
+
+    checkout("topic", None).unwrap();
+    assert_eq!(read("README"), "On topic");
+    assert_eq!(read("becomes_directory/inside.txt"), "A directory on topic");
+    assert!(!test_bed.workspace().join("old").exists());
+    assert_ne!(
+        fs::metadata("run.sh").unwrap().permissions().mode() & 0o111,
+        0
+    );
+    assert_eq!(status(StatusFormat::Porcelain).unwrap(), "");
+    assert_eq!(
+        test_bed
+            .workspace_git_output(vec!["rev-parse", "HEAD"])
+            .trim(),
+        topic
+    );
+
+    assert_eq!(checkout("topic", None).unwrap(), "Already on 'topic'\n");
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_to_overwrite_uncommitted_changes() {
+    let test_bed = TestBed::setup();
+    two_branches(&test_bed);
+
+    fs::write("README", "Not committed anywhere").unwrap();
+    let refused = checkout("master", None).unwrap_err();
+    assert_eq!(
+        refused,
+        "Your local changes to the following files would be overwritten by checkout:\n\
+         \tREADME\n\
+         Please commit your changes or stash them before you switch branches.\n\
+         Aborting"
+    );
+    assert_eq!(read("README"), "Not committed anywhere");
+    assert_eq!(read(".git/HEAD"), "ref: refs/heads/topic\n");
+
+    fs::write("README", "On topic").unwrap();
+    fs::remove_dir_all("becomes_directory").unwrap();
+    fs::write("becomes_directory", "Untracked, in the way").unwrap();
+    add(&[PathBuf::from("README")]).unwrap();
+    fs::create_dir_all("old/nested").unwrap();
+    fs::write("old/nested/gone.txt", "Untracked, in the way").unwrap();
+
+    assert_eq!(
+        checkout("master", None).unwrap_err(),
+        "The following untracked working tree files would be overwritten by checkout:\n\
+         \tbecomes_directory\n\
+         \told/nested/gone.txt\n\
+         Please move or remove them before you switch branches.\n\
+         Aborting"
+    );
+    assert_eq!(read("becomes_directory"), "Untracked, in the way");
+
+    test_bed.teardown();
+}
+
+#[test]
+fn carries_unrelated_changes_across() {
+    let test_bed = TestBed::setup();
+    two_branches(&test_bed);
+
+    fs::write("shared.txt", "Changed, but the same on both branches").unwrap();
+    fs::write("untracked.txt", "Not tracked at all").unwrap();
+
+    checkout("master", None).unwrap();
+
+    assert_eq!(read("shared.txt"), "Changed, but the same on both branches");
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        " M shared.txt\n?? untracked.txt\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn creates_branches_and_detaches_head() {
+    let test_bed = TestBed::setup();
+    let (master, _) = two_branches(&test_bed);
+
+    assert_eq!(
+        checkout("master", Some("fresh")).unwrap(),
+        "Switched to a new branch 'fresh'\n"
+    );
+    assert_eq!(read(".git/HEAD"), "ref: refs/heads/fresh\n");
+    assert_eq!(read(".git/refs/heads/fresh"), format!("{}\n", master));
+    assert_eq!(read("README"), "On master");
+
+    assert!(switch("topic", Some("fresh"), false).is_err());
+    assert_eq!(
+        switch(&master, None, false),
+        Err(format!("a branch is expected, got '{}'", master))
+    );
+
+    assert_eq!(
+        switch("topic", None, true).unwrap(),
+        format!(
+            "HEAD is now at {} On topic\n",
+            &read(".git/refs/heads/topic")[..7]
+        )
+    );
+    assert_eq!(read(".git/HEAD"), read(".git/refs/heads/topic"));
+    assert_eq!(read("README"), "On topic");
+
+    assert_eq!(
+        switch("master", None, false).unwrap(),
+        format!(
+            "Previous HEAD position was {} On topic\nSwitched to branch 'master'\n",
+            &read(".git/refs/heads/topic")[..7]
+        )
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn creates_a_branch_before_the_first_commit_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    assert_eq!(
+        checkout("HEAD", Some("fresh")).unwrap(),
+        "Switched to a new branch 'fresh'\n"
+    );
+    assert_eq!(read(".git/HEAD"), "ref: refs/heads/fresh\n");
+    assert!(!test_bed.workspace().join(".git/refs/heads/fresh").exists());
+
+    write("README", "First on fresh");
+    commit_everything("First");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["branch", "--show-current"]),
+        "fresh\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_to_replace_a_directory_holding_untracked_files() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    write("a/b", "Tracked on master");
+    commit_everything("Directory");
+    checkout("master", Some("other")).unwrap();
+    fs::remove_dir_all("a").unwrap();
+    write("a", "A file on other");
+    commit_everything("File");
+    checkout("master", None).unwrap();
+
+    write("a/c", "Untracked");
+    assert_eq!(
+        checkout("other", None).unwrap_err(),
+        "Updating the following directories would lose untracked files in them:\n\
+         \ta\n\
+         Aborting"
+    );
+    assert_eq!(read("a/c"), "Untracked");
+    assert_eq!(read("a/b"), "Tracked on master");
+    assert_eq!(read(".git/HEAD"), "ref: refs/heads/master\n");
+
+    fs::remove_file("a/c").unwrap();
+    checkout("other", None).unwrap();
+    assert_eq!(read("a"), "A file on other");
+
+    test_bed.teardown();
+}
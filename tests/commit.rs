@@ -2,8 +2,8 @@ mod common;
 
 use chrono::Local;
 use common::TestBed;
-use grit::commands::{commit, init};
-use std::{env, fs};
+use grit::commands::{add, commit, init};
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf};
 
 #[test]
 fn creates_objects_found_in_real_git_commit() {
@@ -20,6 +20,8 @@ fn creates_objects_found_in_real_git_commit() {
     env::set_var("GIT_AUTHOR_DATE", &right_now);
     env::set_var("GIT_COMMITTER_DATE", &right_now);
 
+    add(&[PathBuf::from(".")]).unwrap();
+
     let commit_id = commit(message).unwrap();
 
     test_bed.git_command(vec!["init"]);
@@ -32,11 +34,16 @@ fn creates_objects_found_in_real_git_commit() {
         test_bed.root
     );
 
-    let found_commit_id = fs::read_to_string(".git/HEAD").unwrap();
+    let found_commit_id = fs::read_to_string(".git/refs/heads/master").unwrap();
 
-    assert_eq!(commit_id, found_commit_id);
+    assert_eq!(format!("{}\n", commit_id), found_commit_id);
+    assert_eq!(
+        fs::read_to_string(".git/HEAD").unwrap(),
+        "ref: refs/heads/master\n"
+    );
 
     test_bed.create_file("HOWDY", "good to meet you");
+    add(&[PathBuf::from(".")]).unwrap();
     let second_commit_id = commit("Another commit").unwrap();
 
     test_bed.git_command(vec!["add", "."]);
@@ -48,9 +55,246 @@ fn creates_objects_found_in_real_git_commit() {
         test_bed.root
     );
 
-    let found_second_commit_id = fs::read_to_string(".git/HEAD").unwrap();
+    let found_second_commit_id = fs::read_to_string(".git/refs/heads/master").unwrap();
+
+    assert_eq!(format!("{}\n", second_commit_id), found_second_commit_id);
+    assert_eq!(
+        test_bed
+            .workspace_git_output(vec!["rev-parse", "HEAD"])
+            .trim(),
+        second_commit_id,
+        "Real git couldn't follow HEAD to the branch grit committed to"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn commits_binary_and_non_utf8_files_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    let png_header: Vec<u8> = vec![
+        0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0, 0, 0xff,
+    ];
+    let latin1 = b"caf\xe9 cr\xe8me br\xfbl\xe9e\n".to_vec();
+    test_bed.create_binary_file("image.png", &png_header);
+    test_bed.create_binary_file("latin1.txt", &latin1);
+
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
 
-    assert_eq!(second_commit_id, found_second_commit_id);
+    add(&[PathBuf::from(".")]).unwrap();
+
+    let commit_id = commit("Binary files").unwrap();
+
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Binary files"]);
+
+    assert_eq!(
+        test_bed.git_output(vec!["rev-parse", "HEAD"]).trim(),
+        commit_id
+    );
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn commits_nested_directories_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("src/main.rs", "fn main() {}");
+    test_bed.create_file("src/objects/blob.rs", "struct Blob;");
+    test_bed.create_file("src/objects/tree.rs", "struct Tree;");
+    test_bed.create_file("tests/deeply/nested/directory/test.rs", "#[test]");
+    test_bed.create_directory("empty");
+
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
+
+    add(&[PathBuf::from(".")]).unwrap();
+
+    let commit_id = commit("Nested directories").unwrap();
+
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Nested directories"]);
+
+    assert_eq!(
+        test_bed.git_output(vec!["rev-parse", "HEAD"]).trim(),
+        commit_id
+    );
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn orders_tree_entries_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    // Names that sort differently once `foo` is compared as the directory `foo/`
+    test_bed.create_file("foo.txt", "A file named foo.txt");
+    test_bed.create_file("foo/bar", "A file inside foo/");
+    test_bed.create_file("foo-bar", "A file named foo-bar");
+    test_bed.create_file("foo0", "A file named foo0");
+    test_bed.create_file("foo/bar.d/baz", "Nested once more");
+    test_bed.create_file("foo/bar-", "Sorts before bar.d/");
+
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
+
+    add(&[PathBuf::from(".")]).unwrap();
+
+    let commit_id = commit("Tricky ordering").unwrap();
+
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Tricky ordering"]);
+
+    assert_eq!(
+        test_bed.git_output(vec!["rev-parse", "HEAD"]).trim(),
+        commit_id
+    );
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn commits_executables_and_symlinks_like_real_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    test_bed.create_file("build.sh", "#!/bin/sh\necho building\n");
+    test_bed.make_executable("build.sh");
+    // Git only counts a file as executable when its owner may run it
+    test_bed.create_file("group_only.sh", "#!/bin/sh\necho not mine to run\n");
+    for root in &[test_bed.workspace(), test_bed.twin()] {
+        fs::set_permissions(
+            root.join("group_only.sh"),
+            fs::Permissions::from_mode(0o654),
+        )
+        .unwrap();
+    }
+    test_bed.create_file("config/shared.toml", "answer = 42\n");
+    test_bed.create_symlink("app.toml", "config/shared.toml");
+    test_bed.create_symlink("linked_config", "config");
+    test_bed.create_symlink("dangling", "does/not/exist");
+
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
+
+    add(&[PathBuf::from(".")]).unwrap();
+
+    let commit_id = commit("Modes").unwrap();
+
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Modes"]);
+
+    assert_eq!(
+        test_bed.git_output(vec!["rev-parse", "HEAD"]).trim(),
+        commit_id
+    );
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn advances_a_detached_head_without_touching_branches() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.create_file("README", "This is the README");
+    add(&[PathBuf::from(".")]).unwrap();
+    let first_commit_id = commit("On master").unwrap();
+
+    fs::write(".git/HEAD", format!("{}\n", first_commit_id)).unwrap();
+    test_bed.create_file("README", "Changed while detached");
+    add(&[PathBuf::from(".")]).unwrap();
+    let detached_commit_id = commit("Detached").unwrap();
+
+    assert_eq!(
+        fs::read_to_string(".git/HEAD").unwrap(),
+        format!("{}\n", detached_commit_id)
+    );
+    assert_eq!(
+        fs::read_to_string(".git/refs/heads/master").unwrap(),
+        format!("{}\n", first_commit_id)
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn takes_identity_from_repository_config() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    for variable in &[
+        "GIT_AUTHOR_NAME",
+        "GIT_AUTHOR_EMAIL",
+        "GIT_COMMITTER_NAME",
+        "GIT_COMMITTER_EMAIL",
+    ] {
+        env::remove_var(variable);
+    }
+    fs::write(
+        ".git/config",
+        "# Who we are\n\
+         [User]\n\
+         \tName = \"Mina  Harker\" ; quoted, so both spaces stay\n\
+         \temail = wrong@example.com\n\
+         [user]\n\
+         \tEMAIL = mina@\\\n\
+         whitby.example\n",
+    )
+    .unwrap();
+
+    test_bed.create_file("README", "Journal");
+    add(&[PathBuf::from(".")]).unwrap();
+    commit("Journal").unwrap();
+
+    let signatures = "--format=%an <%ae>%n%cn <%ce>";
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["log", "-1", signatures]),
+        "Mina  Harker <mina@whitby.example>\nMina  Harker <mina@whitby.example>\n"
+    );
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["config", "user.email"]),
+        "mina@whitby.example\n"
+    );
 
     test_bed.teardown();
 }
@@ -1,3 +1,5 @@
 mod test_bed;
 
-pub use test_bed::TestBed;
+// Not every test uses every helper
+#[allow(unused_imports)]
+pub use test_bed::{commit_everything, create_branch, numbered, read, write, TestBed};
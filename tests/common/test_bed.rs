@@ -1,18 +1,35 @@
-use std::{env, fs, path::PathBuf, process::Command};
+use std::{
+    env, fs,
+    os::unix::fs::{symlink, PermissionsExt},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Mutex, MutexGuard},
+};
 use uuid::Uuid;
 
-use grit::compression;
+use grit::{
+    commands::{add, branch, commit, BranchAction},
+    compression,
+};
+
+// Tests share one process, and with it one working directory and environment,
+// so only one TestBed may be set up at a time
+static TEST_BED_LOCK: Mutex<()> = Mutex::new(());
 
 #[derive(Debug)]
 pub struct TestBed {
     pub root: PathBuf,
+    _lock: MutexGuard<'static, ()>,
 }
 
 #[allow(dead_code)]
 impl TestBed {
     pub fn setup() -> TestBed {
+        let lock = TEST_BED_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
         let root = env::temp_dir().join(format!("grit_test/{}", Uuid::new_v4()));
-        let test_bed = TestBed { root };
+        let test_bed = TestBed { root, _lock: lock };
         fs::create_dir_all(test_bed.workspace()).expect("Couldn't create workspace directory");
         fs::create_dir_all(test_bed.twin()).expect("Couldn't create twin directory");
         env::set_current_dir(test_bed.workspace()).expect("Couldn't set workspace to CWD");
@@ -51,6 +68,38 @@ impl TestBed {
         }
     }
 
+    // Create a file in both `workspace` and `twin`, then commit everything in `workspace`
+    // with the file's contents as the message
+    pub fn commit_file(&self, relative_path: &str, contents: &str) -> String {
+        self.create_file(relative_path, contents);
+        commit_everything(contents)
+    }
+
+    // Create a file with arbitrary (not necessarily UTF-8) bytes in both `workspace` and `twin`
+    pub fn create_binary_file(&self, relative_path: &str, contents: &[u8]) {
+        for test_parallel in self.test_parallels() {
+            let path = test_parallel.join(relative_path);
+            simple_write_file(&path, contents);
+        }
+    }
+
+    // Mark an existing file as executable in both `workspace` and `twin`
+    pub fn make_executable(&self, relative_path: &str) {
+        for test_parallel in self.test_parallels() {
+            let path = test_parallel.join(relative_path);
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .expect("Couldn't make file executable");
+        }
+    }
+
+    // Create a symlink pointing at `target` in both `workspace` and `twin`
+    pub fn create_symlink(&self, relative_path: &str, target: &str) {
+        for test_parallel in self.test_parallels() {
+            let path = test_parallel.join(relative_path);
+            symlink(target, &path).expect("Couldn't create symlink");
+        }
+    }
+
     // Create a directory in both `workspace` and `twin`
     pub fn create_directory(&self, relative_path: &str) {
         for test_parallel in self.test_parallels() {
@@ -63,13 +112,33 @@ impl TestBed {
     pub fn git_command(&self, args: Vec<&str>) {
         Command::new("git")
             .args(args)
-            .current_dir(&self.twin())
+            .current_dir(self.twin())
+            .output()
+            .expect("Git command failed");
+    }
+
+    // Run a `git` command in `twin` and collect what it prints to stdout
+    pub fn git_output(&self, args: Vec<&str>) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(self.twin())
+            .output()
+            .expect("Git command failed");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    // Run a `git` command in `workspace`, to have git check up on what grit did
+    pub fn workspace_git_output(&self, args: Vec<&str>) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(self.workspace())
             .output()
             .expect("Git command failed");
+        String::from_utf8_lossy(&output.stdout).into_owned()
     }
 
     // Finds the twin version of a path in the TestBed
-    pub fn find_twin(&self, path: &PathBuf) -> PathBuf {
+    pub fn find_twin(&self, path: &Path) -> PathBuf {
         let workspace = self.workspace().to_str().unwrap().to_owned();
         let twin = self.twin().to_str().unwrap().to_owned();
 
@@ -104,7 +173,47 @@ impl TestBed {
 }
 
 // Recursively delve into directories and collect every file found
-fn descendent_files(directory: &PathBuf) -> Vec<PathBuf> {
+// Helpers for tests like checkout and merge that only happen in the workspace, where a
+// twin isn't needed
+
+// Write a file in the current directory, creating its parent directories
+#[allow(dead_code)]
+pub fn write(path: &str, contents: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).expect("Could not create parent directory for file");
+    }
+    fs::write(path, contents).expect("Couldn't write file");
+}
+
+#[allow(dead_code)]
+pub fn read(path: &str) -> String {
+    fs::read_to_string(path).expect("Couldn't read file")
+}
+
+// Add everything in the workspace and commit it with grit
+#[allow(dead_code)]
+pub fn commit_everything(message: &str) -> String {
+    add(&[PathBuf::from(".")]).unwrap();
+    commit(message).unwrap()
+}
+
+// Create a branch at HEAD with grit
+#[allow(dead_code)]
+pub fn create_branch(name: &str) {
+    branch(BranchAction::Create {
+        name: name.to_string(),
+        start_point: None,
+    })
+    .unwrap();
+}
+
+// Numbered lines, `line 1\n` and so on, to give diffs and merges something to work with
+#[allow(dead_code)]
+pub fn numbered(range: std::ops::RangeInclusive<usize>) -> String {
+    range.map(|n| format!("line {}\n", n)).collect()
+}
+
+fn descendent_files(directory: &Path) -> Vec<PathBuf> {
     let mut files: Vec<PathBuf> = Vec::new();
     let dir_entries = match directory.read_dir() {
         Ok(dir_entries) => dir_entries,
@@ -128,7 +237,7 @@ fn descendent_files(directory: &PathBuf) -> Vec<PathBuf> {
     files
 }
 
-pub fn files_are_identical(first: &PathBuf, second: &PathBuf) -> bool {
+pub fn files_are_identical(first: &Path, second: &Path) -> bool {
     let first_file = match simple_read_file(first) {
         Ok(file) => file,
         Err(_) => {
@@ -155,7 +264,7 @@ pub fn files_are_identical(first: &PathBuf, second: &PathBuf) -> bool {
     they_match
 }
 
-pub fn inspect_parallels(first: &PathBuf, second: &PathBuf) {
+pub fn inspect_parallels(first: &Path, second: &Path) {
     if first.is_file() && second.is_file() {
         println!("=== COMPARING FILES ===");
         simple_print_file(first);
@@ -177,7 +286,7 @@ pub fn inspect_parallels(first: &PathBuf, second: &PathBuf) {
     }
 }
 
-fn simple_print_file(path: &PathBuf) {
+fn simple_print_file(path: &Path) {
     let contents = match simple_read_file(path) {
         Ok(contents) => contents,
         _ => panic!("Couldnt read file {:?}", path),
@@ -191,7 +300,7 @@ fn simple_print_file(path: &PathBuf) {
 
 // Reads a file, and returns a decrompressed version if that operation is successful
 // Could be very bad if a file starts with the right (wrong) bytes, might need to rethink if problems arise
-fn simple_read_file(path: &PathBuf) -> Result<Vec<u8>, std::io::Error> {
+fn simple_read_file(path: &Path) -> Result<Vec<u8>, std::io::Error> {
     let file = fs::read(path)?;
     match compression::decompress(&file) {
         Ok(decompressed) => Ok(decompressed),
@@ -201,12 +310,12 @@ fn simple_read_file(path: &PathBuf) -> Result<Vec<u8>, std::io::Error> {
 
 // Creates a file based on path and contents
 // Will create parent directory for the file if it doesn't yet exist
-fn simple_write_file(path: &PathBuf, contents: &str) {
+fn simple_write_file<C: AsRef<[u8]>>(path: &Path, contents: C) {
     let parent_directory = path
         .parent()
         .expect("Couldn't determine parent directory from file path");
     fs::create_dir_all(parent_directory).expect("Could not create parent directory for file");
-    fs::write(path, &contents).expect("Couldn't write file");
+    fs::write(path, contents).expect("Couldn't write file");
 }
 
 #[cfg(feature = "helper_tests")]
@@ -225,7 +334,7 @@ mod tests {
     fn file_doesnt_match_twin_when_twin_doesnt_exist() {
         let test_bed = TestBed::setup();
         fs::write(test_bed.workspace().join("README"), "Hello, friend").unwrap();
-        assert_eq!(test_bed.contained_by_twin("README"), false);
+        assert!(!test_bed.contained_by_twin("README"));
         test_bed.teardown();
     }
 
@@ -234,7 +343,7 @@ mod tests {
         let test_bed = TestBed::setup();
         fs::write(test_bed.workspace().join("README"), "Hello, friend").unwrap();
         fs::write(test_bed.twin().join("README"), "Goodbye, friend").unwrap();
-        assert_eq!(test_bed.contained_by_twin("README"), false);
+        assert!(!test_bed.contained_by_twin("README"));
         test_bed.teardown();
     }
 
@@ -247,7 +356,7 @@ mod tests {
             "Hello, friend",
         )
         .unwrap();
-        assert_eq!(test_bed.contained_by_twin("something"), false);
+        assert!(!test_bed.contained_by_twin("something"));
         test_bed.teardown();
     }
 
@@ -261,7 +370,7 @@ mod tests {
         )
         .unwrap();
         fs::write(test_bed.twin().join("something/README"), "Goodbye, friend").unwrap();
-        assert_eq!(test_bed.contained_by_twin("something"), false);
+        assert!(!test_bed.contained_by_twin("something"));
         test_bed.teardown();
     }
 
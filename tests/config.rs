@@ -0,0 +1,573 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{
+    add, checkout, commit, config, diff, init, ConfigAction, ConfigError, ConfigOptions,
+    ConfigScope, ConfigType, DiffTarget,
+};
+use std::{env, fs, path::PathBuf};
+
+// Hand-written, so there's layout for edits to disturb
+const HAND_WRITTEN: &str = "[core]\n\
+    \trepositoryformatversion = 0\n\
+    \tbare = false\n\
+    # Who I am\n\
+    [user]\n\
+    \tname = Lucy Westenra ; for now\n\
+    \temail = lucy@example.com\n\
+    [alias]\n\
+    \tco = checkout\n\
+    [remote \"Origin\"]\n\
+    \turl = https://example.com/hillingham.git\n\
+    [multi]\n\
+    \tvalue = 1\n\
+    \tvalue = 2\n\
+    [last] key = 1\n\
+    \tbare\n\
+    \tsize = 2k";
+
+// The same config in a repository for git and one for grit
+fn twin_config(test_bed: &TestBed, text: &str) {
+    test_bed.git_command(vec!["init"]);
+    init(Some(&test_bed.workspace()));
+    fs::write(".git/config", text).unwrap();
+    fs::write(test_bed.twin().join(".git/config"), text).unwrap();
+}
+
+fn local() -> ConfigOptions {
+    ConfigOptions {
+        scope: Some(ConfigScope::Local),
+        ..ConfigOptions::default()
+    }
+}
+
+fn typed(kind: ConfigType) -> ConfigOptions {
+    ConfigOptions {
+        kind: Some(kind),
+        ..ConfigOptions::default()
+    }
+}
+
+fn get(key: &str) -> ConfigAction {
+    ConfigAction::Get {
+        key: key.to_string(),
+    }
+}
+
+fn set(key: &str, value: &str) -> ConfigAction {
+    ConfigAction::Set {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn unset(key: &str) -> ConfigAction {
+    ConfigAction::Unset {
+        key: key.to_string(),
+    }
+}
+
+// Identity comes from config files alone, not the environment TestBed sets up
+fn use_config_identity() {
+    for variable in &[
+        "GIT_AUTHOR_NAME",
+        "GIT_AUTHOR_EMAIL",
+        "GIT_COMMITTER_NAME",
+        "GIT_COMMITTER_EMAIL",
+    ] {
+        env::remove_var(variable);
+    }
+}
+
+// Who grit signed the latest commit as, checked against who git thinks config says
+fn last_author(test_bed: &TestBed) -> String {
+    let author = test_bed.workspace_git_output(vec!["log", "-1", "--format=%an <%ae>"]);
+    let configured = format!(
+        "{} <{}>\n",
+        test_bed
+            .workspace_git_output(vec!["config", "user.name"])
+            .trim(),
+        test_bed
+            .workspace_git_output(vec!["config", "user.email"])
+            .trim()
+    );
+    assert_eq!(author, configured);
+    author
+}
+
+#[test]
+fn follows_includes_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    use_config_identity();
+
+    test_bed.create_directory("shared");
+    fs::write(
+        ".git/config",
+        "[user]\n\
+         \tname = Nobody\n\
+         [include]\n\
+         \tpath = ../shared/identity\n\
+         \tpath = ../shared/missing\n\
+         [user]\n\
+         \temail = mina@whitby.example\n",
+    )
+    .unwrap();
+    // Relative to the including file, not to where grit runs
+    fs::write(
+        "shared/identity",
+        "[user]\n\tname = Mina Harker\n\temail = overridden@example.com\n[include]\n\tpath = nested\n",
+    )
+    .unwrap();
+    fs::write("shared/nested", "[user]\n\tname = Mina Murray\n").unwrap();
+
+    test_bed.commit_file("README", "Journal");
+    assert_eq!(
+        last_author(&test_bed),
+        "Mina Murray <mina@whitby.example>\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn includes_by_git_directory_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    use_config_identity();
+
+    let workspace = test_bed.workspace().canonicalize().unwrap();
+    let parent = workspace.parent().unwrap().display().to_string();
+    fs::write(
+        ".git/config",
+        format!(
+            "[user]\n\
+             \tname = Anyone\n\
+             \temail = anyone@example.com\n\
+             [includeIf \"gitdir:/nowhere/\"]\n\
+             \tpath = {root}/nowhere.gitconfig\n\
+             [includeIf \"gitdir:{parent}/\"]\n\
+             \tpath = {root}/work.gitconfig\n\
+             [includeIf \"gitdir/i:{upper}/**/.GIT\"]\n\
+             \tpath = {root}/shouting.gitconfig\n\
+             [include]\n\
+             \tpath = {root}/shared.gitconfig\n",
+            root = workspace.display(),
+            parent = parent,
+            upper = parent.to_uppercase(),
+        ),
+    )
+    .unwrap();
+    fs::write("nowhere.gitconfig", "[user]\n\tname = Wrong\n").unwrap();
+    fs::write("work.gitconfig", "[user]\n\tname = Jonathan Harker\n").unwrap();
+    fs::write(
+        "shouting.gitconfig",
+        "[user]\n\temail = JONATHAN@EXAMPLE.COM\n",
+    )
+    .unwrap();
+    // `./` is where the including file is, so this is any repository in the workspace
+    fs::write(
+        "shared.gitconfig",
+        "[includeIf \"gitdir:./\"]\n\tpath = local.gitconfig\n\
+         [includeIf \"gitdir:./elsewhere/\"]\n\tpath = nowhere.gitconfig\n",
+    )
+    .unwrap();
+    fs::write(
+        "local.gitconfig",
+        "[user]\n\temail = jonathan@example.com\n",
+    )
+    .unwrap();
+
+    test_bed.commit_file("README", "Journal");
+    assert_eq!(
+        last_author(&test_bed),
+        "Jonathan Harker <jonathan@example.com>\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn includes_by_branch_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    use_config_identity();
+
+    let workspace = test_bed.workspace().canonicalize().unwrap();
+    fs::write(
+        ".git/config",
+        format!(
+            "[user]\n\
+             \tname = Van Helsing\n\
+             \temail = abraham@example.com\n\
+             [includeIf \"onbranch:master\"]\n\
+             \tpath = {root}/master.gitconfig\n\
+             [includeIf \"onbranch:hunt/\"]\n\
+             \tpath = {root}/hunt.gitconfig\n",
+            root = workspace.display()
+        ),
+    )
+    .unwrap();
+    fs::write("master.gitconfig", "[user]\n\tname = Dr. Seward\n").unwrap();
+    fs::write("hunt.gitconfig", "[user]\n\tname = Quincey Morris\n").unwrap();
+
+    // An unborn branch counts, too
+    test_bed.commit_file("README", "Diary");
+    assert_eq!(last_author(&test_bed), "Dr. Seward <abraham@example.com>\n");
+
+    checkout("HEAD", Some("hunt/transylvania")).unwrap();
+    test_bed.commit_file("LICENSE", "Pursuit");
+    assert_eq!(
+        last_author(&test_bed),
+        "Quincey Morris <abraham@example.com>\n"
+    );
+
+    let head = test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]);
+    checkout(head.trim(), None).unwrap();
+    test_bed.commit_file("NOTES", "Detached");
+    assert_eq!(
+        last_author(&test_bed),
+        "Van Helsing <abraham@example.com>\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn edits_in_place_like_git() {
+    let test_bed = TestBed::setup();
+    twin_config(&test_bed, HAND_WRITTEN);
+
+    let edits: Vec<(Vec<&str>, ConfigAction, ConfigOptions)> = vec![
+        (
+            vec!["user.name", "Lucy Holmwood"],
+            set("user.name", "Lucy Holmwood"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["User.signingKey", "ABC123"],
+            set("User.signingKey", "ABC123"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--unset", "alias.co"],
+            unset("alias.co"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["remote.Origin.push", " refs/heads/*; "],
+            set("remote.Origin.push", " refs/heads/*; "),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["new.With \"quotes\".Key", "a\\b\t\"c\""],
+            set("new.With \"quotes\".Key", "a\\b\t\"c\""),
+            ConfigOptions::default(),
+        ),
+        (vec!["last.key", "2"], set("last.key", "2"), local()),
+        (
+            vec!["--type=bool", "core.bare", "yes"],
+            set("core.bare", "yes"),
+            typed(ConfigType::Bool),
+        ),
+        (
+            vec!["--type=int", "core.bigFileThreshold", "512m"],
+            set("core.bigFileThreshold", "512m"),
+            typed(ConfigType::Int),
+        ),
+        (
+            vec!["--unset", "user.email"],
+            unset("user.email"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--unset", "user.name"],
+            unset("user.name"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--unset", "user.signingkey"],
+            unset("user.signingkey"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--unset", "remote.Origin.url"],
+            unset("remote.Origin.url"),
+            ConfigOptions::default(),
+        ),
+    ];
+    for (args, action, options) in edits {
+        test_bed.git_command([vec!["config"], args.clone()].concat());
+        config(action, options).unwrap();
+        assert_eq!(
+            fs::read_to_string(".git/config").unwrap(),
+            fs::read_to_string(test_bed.twin().join(".git/config")).unwrap(),
+            "`config {}` didn't edit like git",
+            args.join(" ")
+        );
+    }
+    assert!(!PathBuf::from(".git/config.lock").exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn reads_like_git() {
+    let test_bed = TestBed::setup();
+    twin_config(&test_bed, HAND_WRITTEN);
+
+    let reads: Vec<(Vec<&str>, ConfigAction, ConfigOptions)> = vec![
+        (
+            vec!["user.name"],
+            get("user.name"),
+            ConfigOptions::default(),
+        ),
+        (vec!["--get", "last.bare"], get("last.bare"), local()),
+        (
+            vec!["--get", "multi.value"],
+            get("multi.value"),
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--get-all", "Multi.Value"],
+            ConfigAction::GetAll {
+                key: String::from("Multi.Value"),
+            },
+            ConfigOptions::default(),
+        ),
+        (
+            vec!["--list", "--local", "--show-origin"],
+            ConfigAction::List,
+            ConfigOptions {
+                show_origin: true,
+                ..local()
+            },
+        ),
+        (
+            vec!["--type=bool", "last.bare"],
+            get("last.bare"),
+            typed(ConfigType::Bool),
+        ),
+        (
+            vec!["--type=bool", "core.bare"],
+            get("core.bare"),
+            typed(ConfigType::Bool),
+        ),
+        (
+            vec!["--type=int", "last.size"],
+            get("last.size"),
+            typed(ConfigType::Int),
+        ),
+    ];
+    for (args, action, options) in reads {
+        let expected = test_bed.git_output([vec!["config"], args.clone()].concat());
+        assert_eq!(
+            config(action, options).unwrap(),
+            expected,
+            "`config {}` didn't match git",
+            args.join(" ")
+        );
+    }
+
+    fs::write(".git/config", "[paths]\n\tcache = ~/.cache/grit\n").unwrap();
+    assert_eq!(
+        config(get("paths.cache"), typed(ConfigType::Path)).unwrap(),
+        format!("{}/.cache/grit\n", env::var("HOME").unwrap())
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn fails_like_git() {
+    let test_bed = TestBed::setup();
+    twin_config(&test_bed, HAND_WRITTEN);
+
+    let failure = |message: &str, status| ConfigError {
+        message: message.to_string(),
+        status,
+    };
+    let defaults = ConfigOptions::default();
+    assert_eq!(config(get("missing.key"), defaults), Err(failure("", 1)));
+    assert_eq!(config(unset("missing.key"), defaults), Err(failure("", 5)));
+    assert_eq!(
+        config(get("nosection"), defaults),
+        Err(failure(
+            "error: key does not contain a section: nosection",
+            1
+        ))
+    );
+    assert_eq!(
+        config(set("nosection", "x"), defaults),
+        Err(failure(
+            "error: key does not contain a section: nosection",
+            2
+        ))
+    );
+    assert_eq!(
+        config(set("user.", "x"), defaults),
+        Err(failure(
+            "error: key does not contain variable name: user.",
+            2
+        ))
+    );
+    assert_eq!(
+        config(set("user.1name", "x"), defaults),
+        Err(failure("error: invalid key: user.1name", 1))
+    );
+    assert_eq!(
+        config(unset("multi.value"), defaults),
+        Err(failure("warning: multi.value has multiple values", 5))
+    );
+    assert_eq!(
+        config(set("Multi.value", "3"), defaults),
+        Err(failure(
+            "warning: multi.value has multiple values\n\
+             error: cannot overwrite multiple values with a single value\n       \
+             Use a regexp, --add or --replace-all to change Multi.value.",
+            5
+        ))
+    );
+    assert_eq!(
+        config(set("core.bare", "maybe"), typed(ConfigType::Bool)),
+        Err(failure(
+            "fatal: bad boolean config value 'maybe' for 'core.bare'",
+            128
+        ))
+    );
+    assert_eq!(
+        config(get("user.name"), typed(ConfigType::Int)),
+        Err(failure(
+            "fatal: bad numeric config value 'Lucy Westenra' for 'user.name' in file \
+             .git/config: invalid unit",
+            128
+        ))
+    );
+    assert_eq!(
+        fs::read_to_string(".git/config").unwrap(),
+        HAND_WRITTEN,
+        "A failed edit changed the file"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn stops_commands_at_a_broken_config_like_git() {
+    let test_bed = TestBed::setup();
+    twin_config(&test_bed, "[core\n");
+
+    test_bed.create_file("README", "This is the README");
+    add(&[PathBuf::from("README")]).unwrap();
+    let broken = String::from("bad config line 1 in file .git/config");
+    assert_eq!(commit("First"), Err(broken.clone()));
+    assert_eq!(diff(DiffTarget::Cached, 3, None), Err(broken));
+    assert!(fs::read_dir(".git/refs/heads").unwrap().next().is_none());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn discovers_config_files_like_git() {
+    let test_bed = TestBed::setup();
+    twin_config(&test_bed, "[core]\n\tbare = false\n");
+    let home = env::var_os("HOME");
+
+    let root = test_bed.root.clone();
+    for (directory, file, name) in &[
+        ("home", ".gitconfig", "home"),
+        ("home/.config/git", "config", "default xdg"),
+        ("xdg/git", "config", "xdg"),
+        ("elsewhere", "global", "global override"),
+        ("elsewhere", "system", "system override"),
+    ] {
+        fs::create_dir_all(root.join(directory)).unwrap();
+        fs::write(
+            root.join(directory).join(file),
+            format!("[found]\n\tin = {}\n", name),
+        )
+        .unwrap();
+    }
+    let path = |relative: &str| root.join(relative).display().to_string();
+
+    let list = ConfigOptions {
+        show_origin: true,
+        ..ConfigOptions::default()
+    };
+    let environments: Vec<Vec<(&str, String)>> = vec![
+        vec![("HOME", path("home"))],
+        vec![("XDG_CONFIG_HOME", path("xdg"))],
+        vec![("GIT_CONFIG_SYSTEM", path("elsewhere/system"))],
+        vec![("GIT_CONFIG_NOSYSTEM", String::from("yes"))],
+        vec![("GIT_CONFIG_GLOBAL", path("elsewhere/global"))],
+        vec![
+            ("GIT_CONFIG_COUNT", String::from("2")),
+            ("GIT_CONFIG_KEY_0", String::from("Found.In")),
+            ("GIT_CONFIG_VALUE_0", String::from("the command line")),
+            ("GIT_CONFIG_KEY_1", String::from("core.Bare")),
+            ("GIT_CONFIG_VALUE_1", String::from("")),
+        ],
+    ];
+    // Each environment adds to the ones before it
+    for environment in environments {
+        for (variable, value) in &environment {
+            env::set_var(variable, value);
+        }
+        let expected = test_bed.git_output(vec!["config", "--list", "--show-origin"]);
+        assert_eq!(
+            config(ConfigAction::List, list).unwrap(),
+            expected,
+            "Didn't find config like git with {:?}",
+            environment
+        );
+        let expected = test_bed.git_output(vec!["config", "--global", "--list"]);
+        let global = ConfigOptions {
+            scope: Some(ConfigScope::Global),
+            ..ConfigOptions::default()
+        };
+        assert_eq!(config(ConfigAction::List, global).unwrap(), expected);
+    }
+
+    for variable in &[
+        "XDG_CONFIG_HOME",
+        "GIT_CONFIG_SYSTEM",
+        "GIT_CONFIG_NOSYSTEM",
+        "GIT_CONFIG_GLOBAL",
+        "GIT_CONFIG_COUNT",
+        "GIT_CONFIG_KEY_0",
+        "GIT_CONFIG_VALUE_0",
+        "GIT_CONFIG_KEY_1",
+        "GIT_CONFIG_VALUE_1",
+    ] {
+        env::remove_var(variable);
+    }
+    match home {
+        Some(home) => env::set_var("HOME", home),
+        None => env::remove_var("HOME"),
+    }
+    test_bed.teardown();
+}
+
+#[test]
+fn takes_identity_from_the_environment_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    use_config_identity();
+
+    fs::write(
+        ".git/config",
+        "[user]\n\tname = Arthur Holmwood\n\temail = arthur@example.com\n",
+    )
+    .unwrap();
+    env::set_var("GIT_CONFIG_COUNT", "1");
+    env::set_var("GIT_CONFIG_KEY_0", "user.name");
+    env::set_var("GIT_CONFIG_VALUE_0", "Lord Godalming");
+    test_bed.commit_file("README", "Letter");
+    let author = last_author(&test_bed);
+    for variable in &["GIT_CONFIG_COUNT", "GIT_CONFIG_KEY_0", "GIT_CONFIG_VALUE_0"] {
+        env::remove_var(variable);
+    }
+    assert_eq!(author, "Lord Godalming <arthur@example.com>\n");
+
+    test_bed.teardown();
+}
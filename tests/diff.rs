@@ -0,0 +1,178 @@
+mod common;
+
+use common::{commit_everything, numbered, write, TestBed};
+use grit::commands::{add, diff, init, DiffAlgorithm, DiffTarget};
+use std::{env, fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+fn diff_text(target: DiffTarget) -> String {
+    String::from_utf8(diff(target, 3, None).unwrap()).unwrap()
+}
+
+// A history with edits near both ends of a long file, a deleted file, a new file, a file
+// that loses its last newline, a mode change and a binary file
+fn edit_everything() {
+    write("long.txt", &numbered(1..=30));
+    write("doomed.txt", "Going away\n");
+    write("tail.txt", "no newline soon\n");
+    write("run.sh", "echo hi\n");
+    fs::write("image.bin", b"\x00\x01\x02").unwrap();
+    write("nested/deep.txt", "Deep\n");
+}
+
+fn change_everything() {
+    write(
+        "long.txt",
+        &numbered(1..=30)
+            .replace("line 2\n", "line two\n")
+            .replace("line 25\n", "")
+            .replace("line 29\n", "line 29\nline 29.5\n"),
+    );
+    fs::remove_file("doomed.txt").unwrap();
+    write("tail.txt", "no newline soon");
+    fs::set_permissions("run.sh", fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write("image.bin", b"\x00\x01\x03").unwrap();
+    write("nested/new.txt", "Brand new\n");
+    write("nested/empty.txt", "");
+}
+
+#[test]
+fn diffs_workspace_against_index_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    edit_everything();
+    commit_everything("First");
+    change_everything();
+
+    let output = diff_text(DiffTarget::Workspace);
+    assert_eq!(output, test_bed.workspace_git_output(vec!["diff"]));
+    assert!(output.contains("@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n"));
+    assert_eq!(
+        String::from_utf8(diff(DiffTarget::Workspace, 0, None).unwrap()).unwrap(),
+        test_bed.workspace_git_output(vec!["diff", "-U0"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn diffs_index_against_head_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    edit_everything();
+    commit_everything("First");
+    change_everything();
+    add(&[PathBuf::from(".")]).unwrap();
+
+    assert_eq!(diff_text(DiffTarget::Workspace), "");
+    assert_eq!(
+        diff_text(DiffTarget::Cached),
+        test_bed.workspace_git_output(vec!["diff", "--cached"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn diffs_two_revisions_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    edit_everything();
+    commit_everything("First");
+    change_everything();
+    commit_everything("Second");
+
+    let revisions = |old: &str, new: &str| DiffTarget::Revisions(old.to_string(), new.to_string());
+    assert_eq!(
+        diff_text(revisions("HEAD~", "HEAD")),
+        test_bed.workspace_git_output(vec!["diff", "HEAD~", "HEAD"])
+    );
+    assert_eq!(
+        diff_text(revisions("HEAD", "HEAD~1")),
+        test_bed.workspace_git_output(vec!["diff", "HEAD", "HEAD~1"])
+    );
+    assert_eq!(diff_text(revisions("HEAD", "master")), "");
+    assert!(diff(revisions("HEAD", "nowhere"), 3, None).is_err());
+
+    test_bed.teardown();
+}
+
+// Each directory in `tests/fixtures/diff` holds an `old` and `new` version of a file, and what
+// `git diff --diff-algorithm=<name>` printed after committing `old` as `source` and then
+// replacing it with `new`
+fn fixtures() -> Vec<PathBuf> {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff");
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+#[test]
+fn matches_git_with_each_algorithm() {
+    let algorithms = [
+        ("myers", DiffAlgorithm::Myers),
+        ("patience", DiffAlgorithm::Patience),
+        ("histogram", DiffAlgorithm::Histogram),
+    ];
+
+    for fixture in fixtures() {
+        let test_bed = TestBed::setup();
+
+        init(Some(&test_bed.workspace()));
+        fs::copy(fixture.join("old"), "source").unwrap();
+        commit_everything("Old");
+        fs::copy(fixture.join("new"), "source").unwrap();
+
+        for (name, algorithm) in algorithms.iter() {
+            assert_eq!(
+                String::from_utf8(diff(DiffTarget::Workspace, 3, Some(*algorithm)).unwrap())
+                    .unwrap(),
+                fs::read_to_string(fixture.join(format!("{}.diff", name))).unwrap(),
+                "{} diff of {:?} didn't match git",
+                name,
+                fixture
+            );
+        }
+
+        test_bed.teardown();
+    }
+}
+
+#[test]
+fn takes_the_algorithm_from_config() {
+    let test_bed = TestBed::setup();
+    let fixture =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diff/moved_function");
+
+    init(Some(&test_bed.workspace()));
+    fs::copy(fixture.join("old"), "source").unwrap();
+    commit_everything("Old");
+    fs::copy(fixture.join("new"), "source").unwrap();
+
+    env::set_var("GIT_DIFF_ALGORITHM", "patience");
+    let configured = diff(DiffTarget::Workspace, 3, None);
+    let overridden = diff(DiffTarget::Workspace, 3, Some(DiffAlgorithm::Myers));
+    env::set_var("GIT_DIFF_ALGORITHM", "sideways");
+    let invalid = diff(DiffTarget::Workspace, 3, None);
+    env::remove_var("GIT_DIFF_ALGORITHM");
+
+    assert_eq!(
+        String::from_utf8(configured.unwrap()).unwrap(),
+        fs::read_to_string(fixture.join("patience.diff")).unwrap()
+    );
+    assert_eq!(
+        String::from_utf8(overridden.unwrap()).unwrap(),
+        fs::read_to_string(fixture.join("myers.diff")).unwrap()
+    );
+    assert_eq!(
+        invalid,
+        Err("unknown value for config 'diff.algorithm': sideways".to_string())
+    );
+
+    test_bed.teardown();
+}
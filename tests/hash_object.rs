@@ -0,0 +1,54 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{hash_object, init};
+use std::path::PathBuf;
+
+#[test]
+fn hashes_files_like_git_without_writing_them() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("LICENSE", "This is the license");
+
+    let expected = test_bed.git_output(vec!["hash-object", "README", "LICENSE"]);
+    let files = vec![PathBuf::from("README"), PathBuf::from("LICENSE")];
+    assert_eq!(hash_object(&files, None, false), Ok(expected.clone()));
+
+    let readme_id = expected.lines().next().unwrap();
+    let object_path = format!(".git/objects/{}/{}", &readme_id[..2], &readme_id[2..]);
+    assert!(!test_bed.workspace().join(object_path).exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn writes_stdin_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.create_file("README", "Hello from stdin");
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["hash-object", "-w", "README"]);
+
+    let written = hash_object(&[], Some(b"Hello from stdin"), true).unwrap();
+
+    assert_eq!(written, test_bed.git_output(vec!["hash-object", "README"]));
+    assert!(
+        test_bed.contained_by_twin(".git/objects"),
+        "Files in workspace not contained by twin, take a look:\n{:?}",
+        test_bed.root
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_missing_files() {
+    let test_bed = TestBed::setup();
+
+    assert!(hash_object(&[PathBuf::from("NOPE")], None, false).is_err());
+
+    test_bed.teardown();
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::TestBed;
+use grit::index::Index;
+use std::{
+    env, fs,
+    time::{Duration, SystemTime},
+};
+
+fn twin_with_staged_files(test_bed: &TestBed) {
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("src/main.rs", "fn main() {}");
+    test_bed.create_file("src/a-very-long-file-name-that-needs-some-padding.rs", "");
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    env::set_current_dir(test_bed.twin()).unwrap();
+}
+
+#[test]
+fn reads_an_index_written_by_real_git() {
+    let test_bed = TestBed::setup();
+    twin_with_staged_files(&test_bed);
+
+    let index = Index::load().unwrap();
+    let staged: Vec<String> = index
+        .entries()
+        .map(|entry| {
+            format!(
+                "{} {} {}\t{}\n",
+                entry.tree_mode(),
+                entry.oid,
+                entry.stage(),
+                entry.path
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        staged.concat(),
+        test_bed.git_output(vec!["ls-files", "--stage"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn writes_an_index_identical_to_real_git() {
+    let test_bed = TestBed::setup();
+    twin_with_staged_files(&test_bed);
+
+    let written_by_git = fs::read(".git/index").unwrap();
+    Index::load_for_update().unwrap().write_updates().unwrap();
+
+    assert_eq!(fs::read(".git/index").unwrap(), written_by_git);
+    assert!(!test_bed.twin().join(".git/index.lock").exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn skips_optional_extensions_written_by_real_git() {
+    let test_bed = TestBed::setup();
+    twin_with_staged_files(&test_bed);
+
+    // Committing makes git cache trees in a `TREE` extension
+    test_bed.git_command(vec!["commit", "-m", "Cache some trees"]);
+
+    let index = Index::load().unwrap();
+    assert_eq!(index.entries().count(), 3);
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_to_update_a_locked_index() {
+    let test_bed = TestBed::setup();
+    twin_with_staged_files(&test_bed);
+
+    let _locked = Index::load_for_update().unwrap();
+    assert!(Index::load_for_update().is_err());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn smudges_entries_changed_in_the_same_second_as_the_index() {
+    let test_bed = TestBed::setup();
+    twin_with_staged_files(&test_bed);
+
+    // Change README without changing its size, all within the second the index was written
+    let written = SystemTime::now() - Duration::from_secs(10);
+    fs::write("README", "This is the REDAME").unwrap();
+    for path in &["README", ".git/index"] {
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(written).unwrap();
+    }
+
+    Index::load_for_update().unwrap().write_updates().unwrap();
+
+    let sizes: Vec<(String, u32)> = Index::load()
+        .unwrap()
+        .entries()
+        .map(|entry| (entry.path.clone(), entry.size))
+        .collect();
+    assert!(sizes.contains(&("README".to_string(), 0)));
+    assert!(sizes.contains(&("src/main.rs".to_string(), 12)));
+    assert_eq!(
+        test_bed.git_output(vec!["status", "--short"]),
+        "AM README\nA  src/a-very-long-file-name-that-needs-some-padding.rs\nA  src/main.rs\n"
+    );
+
+    test_bed.teardown();
+}
@@ -2,17 +2,17 @@ mod common;
 
 use common::TestBed;
 use grit::commands::init;
+use std::fs;
 
 #[test]
 fn initializes_repository_in_existing_directory() {
     let test_bed = TestBed::setup();
-    assert_eq!(
+    assert!(
         test_bed.workspace().is_dir(),
-        true,
         "The workspace directory wasn't created as part of ::setup()"
     );
     init(Some(&test_bed.workspace()));
-    let expected_git_directories = vec![".git", ".git/refs", ".git/objects"];
+    let expected_git_directories = [".git", ".git/refs", ".git/objects"];
     assert!(
         expected_git_directories
             .iter()
@@ -26,7 +26,7 @@ fn initializes_repository_in_existing_directory() {
 fn init_defaults_to_cwd_when_directory_not_specified() {
     let test_bed = TestBed::setup();
     init(None);
-    let expected_git_directories = vec![".git", ".git/refs", ".git/objects"];
+    let expected_git_directories = [".git", ".git/refs", ".git/objects"];
     assert!(
         expected_git_directories
             .iter()
@@ -35,3 +35,20 @@ fn init_defaults_to_cwd_when_directory_not_specified() {
     );
     test_bed.teardown();
 }
+
+#[test]
+fn points_head_at_the_default_branch() {
+    let test_bed = TestBed::setup();
+    init(None);
+    assert_eq!(
+        fs::read_to_string(test_bed.workspace().join(".git/HEAD")).unwrap(),
+        "ref: refs/heads/master\n"
+    );
+    assert!(test_bed.workspace().join(".git/refs/heads").is_dir());
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["symbolic-ref", "HEAD"]),
+        "refs/heads/master\n",
+        "Real git doesn't recognize the repository"
+    );
+    test_bed.teardown();
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{branch, init, log, BranchAction, LogFormat};
+use std::fs;
+
+#[test]
+fn shows_history_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    test_bed.commit_file("LICENSE", "Second\n\nWith a body\n\nover two paragraphs");
+    let third = test_bed.commit_file("NOTES", "Third");
+
+    assert_eq!(
+        log(&[], LogFormat::Medium, None).unwrap(),
+        test_bed.workspace_git_output(vec!["log"])
+    );
+    assert_eq!(
+        log(&[], LogFormat::Oneline, None).unwrap(),
+        test_bed.workspace_git_output(vec!["log", "--oneline", "--no-decorate"])
+    );
+    assert_eq!(
+        log(&[], LogFormat::Oneline, Some(1)).unwrap(),
+        format!("{} Third\n", &third[..7])
+    );
+
+    let template = "%H|%h|%an|%ae|%ad|%s|100%%";
+    assert_eq!(
+        log(&[], LogFormat::Custom(template.to_string()), None).unwrap(),
+        test_bed.workspace_git_output(vec!["log", &format!("--format={}", template)])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn walks_several_starting_points_once() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    branch(BranchAction::Create {
+        name: "topic".to_string(),
+        start_point: None,
+    })
+    .unwrap();
+    test_bed.commit_file("LICENSE", "Second");
+
+    let revisions = vec!["topic".to_string(), "master".to_string()];
+    assert_eq!(
+        log(&revisions, LogFormat::Oneline, None).unwrap(),
+        test_bed.workspace_git_output(vec!["log", "--oneline", "--no-decorate", "topic", "master"])
+    );
+    assert!(log(&["nowhere".to_string()], LogFormat::Medium, None).is_err());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn fails_before_the_first_commit() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    assert_eq!(
+        log(&[], LogFormat::Medium, None),
+        Err("your current branch 'master' does not have any commits yet".to_string())
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn abbreviates_ids_as_far_as_git_needs_to() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    let second = test_bed.commit_file("LICENSE", "Second");
+
+    // Give the second commit's ID a neighbour sharing its first seven characters
+    let object = |id: &str| format!(".git/objects/{}/{}", &id[..2], &id[2..]);
+    let neighbour = format!("{}{}", &second[..7], "0".repeat(33));
+    fs::copy(object(&second), object(&neighbour)).unwrap();
+
+    assert_eq!(
+        log(&[], LogFormat::Oneline, None).unwrap(),
+        test_bed.workspace_git_output(vec!["log", "--oneline", "--no-decorate"])
+    );
+    assert!(log(&[], LogFormat::Oneline, Some(1))
+        .unwrap()
+        .starts_with(&second[..8]));
+    let template = "%h %s";
+    assert_eq!(
+        log(&[], LogFormat::Custom(template.to_string()), None).unwrap(),
+        test_bed.workspace_git_output(vec!["log", &format!("--format={}", template)])
+    );
+
+    test_bed.teardown();
+}
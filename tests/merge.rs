@@ -0,0 +1,300 @@
+mod common;
+
+use common::{commit_everything, create_branch, numbered, read, write, TestBed};
+use grit::{
+    commands::{checkout, commit, init, merge, status, StatusFormat},
+    objects,
+};
+use std::fs;
+
+// `master` and `topic` both change `shared.txt`, in different places, and each adds and
+// removes files of its own
+fn diverge() -> (String, String) {
+    write("shared.txt", &numbered(1..=12));
+    write("ours_removes.txt", "Removed on master\n");
+    write("theirs_removes.txt", "Removed on topic\n");
+    commit_everything("Base");
+    create_branch("topic");
+
+    write(
+        "shared.txt",
+        &numbered(1..=12).replace("line 2\n", "line two\n"),
+    );
+    fs::remove_file("ours_removes.txt").unwrap();
+    write("ours.txt", "Added on master\n");
+    let ours = commit_everything("On master");
+
+    checkout("topic", None).unwrap();
+    write(
+        "shared.txt",
+        &numbered(1..=12).replace("line 11\n", "line eleven\n"),
+    );
+    fs::remove_file("theirs_removes.txt").unwrap();
+    write("nested/theirs.txt", "Added on topic\n");
+    let theirs = commit_everything("On topic");
+
+    checkout("master", None).unwrap();
+    (ours, theirs)
+}
+
+// What git's own merge machinery makes of merging `topic` into `master`: the tree, and
+// anything it prints after that
+fn git_merge_tree(test_bed: &TestBed) -> (String, String) {
+    let output =
+        test_bed.workspace_git_output(vec!["merge-tree", "--write-tree", "master", "topic"]);
+    let (tree, rest) = output.split_at(40);
+    (tree.to_string(), rest.trim_start_matches('\n').to_string())
+}
+
+fn git_blob(test_bed: &TestBed, revision: &str) -> String {
+    test_bed.workspace_git_output(vec!["cat-file", "-p", revision])
+}
+
+#[test]
+fn fast_forwards_when_head_is_behind() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    write("README", "Hello\n");
+    let ours = commit_everything("First");
+    create_branch("topic");
+    checkout("topic", None).unwrap();
+    write("README", "Hello again\n");
+    write("new.txt", "New\n");
+    let theirs = commit_everything("Second");
+    checkout("master", None).unwrap();
+
+    let outcome = merge("topic", None).unwrap();
+    assert_eq!(
+        outcome.output,
+        format!("Updating {}..{}\nFast-forward\n", &ours[..7], &theirs[..7])
+    );
+    assert!(outcome.clean);
+    assert_eq!(read(".git/refs/heads/master"), format!("{}\n", theirs));
+    assert_eq!(read(".git/ORIG_HEAD"), format!("{}\n", ours));
+    assert_eq!(read("README"), "Hello again\n");
+    assert_eq!(read("new.txt"), "New\n");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["status", "--porcelain"]),
+        ""
+    );
+
+    assert_eq!(
+        merge("topic", None).unwrap().output,
+        "Already up to date.\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn commits_a_clean_merge_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    let (ours, theirs) = diverge();
+    let (git_tree, _) = git_merge_tree(&test_bed);
+
+    let outcome = merge("topic", None).unwrap();
+    assert_eq!(
+        outcome.output,
+        "Auto-merging shared.txt\nMerge made by the 'ort' strategy.\n"
+    );
+    assert!(outcome.clean);
+
+    let head = test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]);
+    let merge_commit = objects::read_commit(head.trim()).unwrap();
+    assert_eq!(merge_commit.tree, git_tree);
+    assert_eq!(merge_commit.parents, vec![ours.clone(), theirs]);
+    assert_eq!(merge_commit.message, "Merge branch 'topic'\n");
+    assert_eq!(read(".git/ORIG_HEAD"), format!("{}\n", ours));
+    assert!(read("shared.txt").contains("line two\n"));
+    assert!(read("shared.txt").contains("line eleven\n"));
+    assert!(!test_bed.workspace().join("theirs_removes.txt").exists());
+    assert_eq!(read("nested/theirs.txt"), "Added on topic\n");
+    assert!(!test_bed.workspace().join(".git/MERGE_HEAD").exists());
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["status", "--porcelain"]),
+        ""
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn names_the_branch_merged_into_unless_its_master() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+    diverge();
+    checkout("master", Some("feature")).unwrap();
+
+    merge("topic", None).unwrap();
+    let head = test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]);
+    assert_eq!(
+        objects::read_commit(head.trim()).unwrap().message,
+        "Merge branch 'topic' into feature\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn leaves_conflicts_for_resolving_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    write("kept.txt", &numbered(1..=5));
+    write("gone.txt", "Base\n");
+    commit_everything("Base");
+    create_branch("topic");
+
+    write(
+        "kept.txt",
+        &numbered(1..=5).replace("line 3\n", "line three\n"),
+    );
+    write("gone.txt", "Changed on master\n");
+    write("added.txt", "Added on master\n");
+    let ours = commit_everything("On master");
+
+    checkout("topic", None).unwrap();
+    write(
+        "kept.txt",
+        &numbered(1..=5).replace("line 3\n", "line 3!\n"),
+    );
+    fs::remove_file("gone.txt").unwrap();
+    write("added.txt", "Added on topic\n");
+    let theirs = commit_everything("On topic");
+    checkout("master", None).unwrap();
+
+    let (git_tree, git_conflicts) = git_merge_tree(&test_bed);
+    let outcome = merge("topic", None).unwrap();
+    assert_eq!(
+        outcome.output,
+        "Auto-merging added.txt\n\
+CONFLICT (add/add): Merge conflict in added.txt\n\
+CONFLICT (modify/delete): gone.txt deleted in topic and modified in HEAD.  Version HEAD of gone.txt left in tree.\n\
+Auto-merging kept.txt\n\
+CONFLICT (content): Merge conflict in kept.txt\n\
+Automatic merge failed; fix conflicts and then commit the result.\n"
+    );
+    assert!(!outcome.clean);
+
+    // git labels our side with the branch name, where a merge into HEAD says HEAD
+    for path in &["added.txt", "kept.txt"] {
+        let expected = git_blob(&test_bed, &format!("{}:{}", git_tree, path))
+            .replace("<<<<<<< master", "<<<<<<< HEAD");
+        assert_eq!(read(path), expected);
+    }
+    assert_eq!(
+        read("kept.txt"),
+        "line 1\nline 2\n<<<<<<< HEAD\nline three\n=======\nline 3!\n>>>>>>> topic\nline 4\nline 5\n"
+    );
+    assert_eq!(read("gone.txt"), "Changed on master\n");
+    assert_eq!(
+        git_conflicts.split("\n\n").next().unwrap().to_string() + "\n",
+        test_bed.workspace_git_output(vec!["ls-files", "--stage", "--unmerged"])
+    );
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        "AA added.txt\nUD gone.txt\nUU kept.txt\n"
+    );
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        test_bed.workspace_git_output(vec!["status", "--porcelain"])
+    );
+    assert_eq!(read(".git/MERGE_HEAD"), format!("{}\n", theirs));
+    assert_eq!(
+        read(".git/MERGE_MSG"),
+        "Merge branch 'topic'\n\n# Conflicts:\n#\tadded.txt\n#\tgone.txt\n#\tkept.txt\n"
+    );
+
+    assert_eq!(
+        merge("topic", None).unwrap_err(),
+        "You have not concluded your merge (MERGE_HEAD exists)."
+    );
+    assert_eq!(
+        commit("").unwrap_err(),
+        "Committing is not possible because you have unmerged files."
+    );
+
+    write("kept.txt", "Resolved\n");
+    write("added.txt", "Resolved\n");
+    commit_everything("");
+    let head = test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]);
+    let merge_commit = objects::read_commit(head.trim()).unwrap();
+    assert_eq!(merge_commit.parents, vec![ours, theirs]);
+    assert_eq!(merge_commit.message, "Merge branch 'topic'\n");
+    assert!(!test_bed.workspace().join(".git/MERGE_HEAD").exists());
+    assert!(!test_bed.workspace().join(".git/MERGE_MSG").exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn moves_files_out_of_the_way_of_directories_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    write("base.txt", "Base\n");
+    commit_everything("Base");
+    create_branch("topic");
+    write("a", "A file on master\n");
+    commit_everything("On master");
+    checkout("topic", None).unwrap();
+    write("a/b", "A directory on topic\n");
+    commit_everything("On topic");
+    checkout("master", None).unwrap();
+
+    let outcome = merge("topic", None).unwrap();
+    assert_eq!(
+        outcome.output,
+        "CONFLICT (file/directory): directory in the way of a from HEAD; moving it to a~HEAD instead.\n\
+Automatic merge failed; fix conflicts and then commit the result.\n"
+    );
+    assert!(!outcome.clean);
+    assert_eq!(read("a~HEAD"), "A file on master\n");
+    assert_eq!(read("a/b"), "A directory on topic\n");
+
+    let stages: Vec<String> = test_bed
+        .workspace_git_output(vec!["ls-files", "--stage"])
+        .lines()
+        .map(|line| line[48..].to_string())
+        .collect();
+    assert_eq!(stages, vec!["0\ta/b", "2\ta~HEAD", "0\tbase.txt"]);
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        "D  a\nA  a/b\nAU a~HEAD\n"
+    );
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        test_bed.workspace_git_output(vec!["status", "--porcelain"])
+    );
+    assert_eq!(
+        read(".git/MERGE_MSG"),
+        "Merge branch 'topic'\n\n# Conflicts:\n#\ta~HEAD\n"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refuses_to_merge_unrelated_histories() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    write("README", "Hello\n");
+    commit_everything("First");
+    let tree = test_bed.workspace_git_output(vec!["rev-parse", "HEAD^{tree}"]);
+    let orphan = test_bed.workspace_git_output(vec!["commit-tree", tree.trim(), "-m", "Orphan"]);
+    test_bed.workspace_git_output(vec!["update-ref", "refs/heads/orphan", orphan.trim()]);
+
+    assert_eq!(
+        merge("orphan", None).unwrap_err(),
+        "refusing to merge unrelated histories"
+    );
+    assert_eq!(
+        merge("nowhere", None).unwrap_err(),
+        "nowhere - not something we can merge"
+    );
+
+    test_bed.teardown();
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::TestBed;
+use grit::{
+    commands::{branch, checkout, init, is_ancestor, merge, merge_base},
+    commands::{BranchAction, MergeBaseMode},
+    history,
+};
+use std::env;
+
+// Every commit gets its own timestamp, so "newest first" is never a tie
+fn commit_file_at(test_bed: &TestBed, name: &str, message: &str, time: u32) -> String {
+    let date = format!("{} +0000", 1_600_000_000 + time);
+    env::set_var("GIT_AUTHOR_DATE", &date);
+    env::set_var("GIT_COMMITTER_DATE", &date);
+    test_bed.commit_file(name, message)
+}
+
+fn create_branch_at(name: &str, start_point: &str) {
+    branch(BranchAction::Create {
+        name: String::from(name),
+        start_point: Some(String::from(start_point)),
+    })
+    .unwrap();
+}
+
+fn strings(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn sorted_lines(output: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = output.lines().collect();
+    lines.sort();
+    lines
+}
+
+// `left` and `right` each merge the other's first commit, so they have two best common
+// ancestors, neither of which is an ancestor of the other
+fn criss_cross(test_bed: &TestBed) -> (String, String) {
+    init(Some(&test_bed.workspace()));
+    commit_file_at(test_bed, "base.txt", "Base", 0);
+    create_branch_at("left", "master");
+    create_branch_at("right", "master");
+
+    checkout("left", None).unwrap();
+    let left = commit_file_at(test_bed, "left.txt", "Left", 1);
+    checkout("right", None).unwrap();
+    let right = commit_file_at(test_bed, "right.txt", "Right", 2);
+
+    env::set_var("GIT_COMMITTER_DATE", "1600000003 +0000");
+    merge(&left, Some("Right takes left")).unwrap();
+    checkout("left", None).unwrap();
+    env::set_var("GIT_COMMITTER_DATE", "1600000004 +0000");
+    merge(&right, Some("Left takes right")).unwrap();
+    (left, right)
+}
+
+#[test]
+fn finds_the_best_common_ancestor_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    let base = commit_file_at(&test_bed, "README", "Base", 0);
+    create_branch_at("topic", "master");
+    commit_file_at(&test_bed, "master.txt", "On master", 1);
+    checkout("topic", None).unwrap();
+    commit_file_at(&test_bed, "topic.txt", "On topic", 2);
+
+    let output = merge_base(
+        &strings(&["master", "topic"]),
+        MergeBaseMode::Default,
+        false,
+    );
+    assert_eq!(output.unwrap(), format!("{}\n", base));
+    assert_eq!(
+        merge_base(&strings(&["master", "topic"]), MergeBaseMode::Default, true).unwrap(),
+        test_bed.workspace_git_output(vec!["merge-base", "--all", "master", "topic"])
+    );
+    assert_eq!(
+        merge_base(
+            &strings(&["master", "master~1"]),
+            MergeBaseMode::Default,
+            false
+        )
+        .unwrap(),
+        format!("{}\n", base)
+    );
+    assert_eq!(
+        merge_base(
+            &strings(&["master", "nowhere"]),
+            MergeBaseMode::Default,
+            false
+        )
+        .unwrap_err(),
+        "Not a valid object name nowhere"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn finds_every_base_of_criss_crossed_history_like_git() {
+    let test_bed = TestBed::setup();
+    let (left, right) = criss_cross(&test_bed);
+
+    let all = merge_base(&strings(&["left", "right"]), MergeBaseMode::Default, true).unwrap();
+    assert_eq!(sorted_lines(&all), {
+        let mut expected = vec![left.as_str(), right.as_str()];
+        expected.sort();
+        expected
+    });
+    assert_eq!(
+        all,
+        test_bed.workspace_git_output(vec!["merge-base", "--all", "left", "right"])
+    );
+    assert_eq!(
+        merge_base(&strings(&["left", "right"]), MergeBaseMode::Default, false).unwrap(),
+        test_bed.workspace_git_output(vec!["merge-base", "left", "right"])
+    );
+
+    let left_oid = test_bed.workspace_git_output(vec!["rev-parse", "left"]);
+    let right_oid = test_bed.workspace_git_output(vec!["rev-parse", "right"]);
+    assert_eq!(
+        history::merge_bases(left_oid.trim(), right_oid.trim()).unwrap(),
+        all.lines().map(String::from).collect::<Vec<String>>()
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn finds_bases_for_more_than_two_commits_like_git() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    let base = commit_file_at(&test_bed, "README", "Base", 0);
+    create_branch_at("one", "master");
+    create_branch_at("two", "master");
+    let shared = commit_file_at(&test_bed, "master.txt", "On master", 1);
+    create_branch_at("three", "master");
+    commit_file_at(&test_bed, "more.txt", "More on master", 2);
+    checkout("one", None).unwrap();
+    commit_file_at(&test_bed, "one.txt", "On one", 3);
+    checkout("two", None).unwrap();
+    commit_file_at(&test_bed, "two.txt", "On two", 4);
+    checkout("three", None).unwrap();
+    commit_file_at(&test_bed, "three.txt", "On three", 5);
+
+    // Between master and a merge of the others, `three` shares more with master than the rest
+    let commits = strings(&["master", "one", "two", "three"]);
+    assert_eq!(
+        merge_base(&commits, MergeBaseMode::Default, true).unwrap(),
+        format!("{}\n", shared)
+    );
+    assert_eq!(
+        merge_base(&commits, MergeBaseMode::Default, true).unwrap(),
+        test_bed.workspace_git_output(vec!["merge-base", "--all", "master", "one", "two", "three"])
+    );
+
+    // Between all of them at once, only the root is shared
+    assert_eq!(
+        merge_base(&commits, MergeBaseMode::Octopus, false).unwrap(),
+        format!("{}\n", base)
+    );
+    assert_eq!(
+        merge_base(&commits, MergeBaseMode::Octopus, true).unwrap(),
+        test_bed.workspace_git_output(vec![
+            "merge-base",
+            "--octopus",
+            "master",
+            "one",
+            "two",
+            "three"
+        ])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn checks_ancestry() {
+    let test_bed = TestBed::setup();
+    init(Some(&test_bed.workspace()));
+
+    commit_file_at(&test_bed, "README", "First", 0);
+    create_branch_at("topic", "master");
+    commit_file_at(&test_bed, "LICENSE", "Second", 1);
+
+    assert!(is_ancestor("topic", "master").unwrap());
+    assert!(is_ancestor("master", "master").unwrap());
+    assert!(!is_ancestor("master", "topic").unwrap());
+    let master = test_bed.workspace_git_output(vec!["rev-parse", "master"]);
+    let topic = test_bed.workspace_git_output(vec!["rev-parse", "topic"]);
+    let heads = strings(&[topic.trim(), master.trim(), topic.trim()]);
+    assert_eq!(
+        history::independent(&heads).unwrap(),
+        vec![master.trim().to_string()]
+    );
+    assert_eq!(
+        is_ancestor("master", "nowhere").unwrap_err(),
+        "Not a valid object name nowhere"
+    );
+
+    test_bed.teardown();
+}
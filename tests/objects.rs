@@ -0,0 +1,92 @@
+mod common;
+
+use chrono::Local;
+use common::TestBed;
+use grit::{
+    commands::{add, commit, init},
+    objects::{self, Kind, Object, ParsedObject},
+};
+use std::{env, path::PathBuf};
+
+#[test]
+fn reads_back_objects_written_by_real_git() {
+    let test_bed = TestBed::setup();
+
+    test_bed.create_file("README", "This is the README");
+    test_bed.git_command(vec!["init"]);
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Written by git"]);
+
+    let commit_id = test_bed.git_output(vec!["rev-parse", "HEAD"]);
+    let tree_id = test_bed.git_output(vec!["rev-parse", "HEAD^{tree}"]);
+    let blob_id = test_bed.git_output(vec!["rev-parse", "HEAD:README"]);
+
+    env::set_current_dir(test_bed.twin()).unwrap();
+
+    let commit = match objects::read(commit_id.trim()).unwrap() {
+        ParsedObject::Commit(commit) => commit,
+        other => panic!("Expected a commit, found {:?}", other.kind()),
+    };
+    assert_eq!(commit.id(), commit_id.trim());
+    assert_eq!(commit.tree, tree_id.trim());
+    assert!(commit.parents.is_empty());
+    assert_eq!(commit.author.name, "Count Dracula");
+    assert_eq!(commit.committer.email, "count@dracula");
+    assert_eq!(commit.message, "Written by git\n");
+
+    let tree = match objects::read(&commit.tree).unwrap() {
+        ParsedObject::Tree(tree) => tree,
+        other => panic!("Expected a tree, found {:?}", other.kind()),
+    };
+    assert_eq!(tree.entries.len(), 1);
+    assert_eq!(tree.entries[0].mode, "100644");
+    assert_eq!(tree.entries[0].name, "README");
+    assert_eq!(tree.entries[0].oid, blob_id.trim());
+
+    let blob = objects::read(&tree.entries[0].oid).unwrap();
+    assert_eq!(blob.kind(), &Kind::Blob);
+    assert_eq!(blob.content(), b"This is the README");
+
+    test_bed.teardown();
+}
+
+#[test]
+fn reads_back_a_commit_written_by_grit() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.create_file("README", "This is the README");
+
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
+
+    add(&[PathBuf::from(".")]).unwrap();
+    let commit_id = commit("Written by grit").unwrap();
+
+    let commit = match objects::read(&commit_id).unwrap() {
+        ParsedObject::Commit(commit) => commit,
+        other => panic!("Expected a commit, found {:?}", other.kind()),
+    };
+    assert_eq!(commit.id(), commit_id);
+    assert_eq!(commit.author.date, right_now);
+    assert_eq!(commit.message, "Written by grit\n");
+    assert_eq!(
+        objects::read(&commit.tree).unwrap().kind(),
+        &Kind::Tree,
+        "The commit's tree wasn't found in the database"
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn fails_to_read_a_missing_object() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    assert!(objects::read("4b825dc642cb6eb9a060e54bf8d69288fbee4904").is_err());
+    assert!(objects::read("not-an-id").is_err());
+
+    test_bed.teardown();
+}
@@ -0,0 +1,232 @@
+mod common;
+
+use common::{create_branch, TestBed};
+use grit::commands::{branch, checkout, init, pack_refs, rev_parse, tag, BranchAction, TagAction};
+use std::fs;
+
+fn create_tag(name: &str, message: Option<&str>) {
+    tag(TagAction::Create {
+        name: name.to_string(),
+        target: None,
+        message: message.map(String::from),
+    })
+    .unwrap();
+}
+
+fn list_tags() -> String {
+    tag(TagAction::List { patterns: vec![] }).unwrap()
+}
+
+#[test]
+fn reads_refs_git_packed() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+    create_branch("feature/old");
+    create_tag("v1.0", Some("The first release"));
+    test_bed.workspace_git_output(vec!["pack-refs", "--all"]);
+    assert!(!test_bed.workspace().join(".git/refs/tags/v1.0").exists());
+
+    // A loose ref written after packing wins over the packed one
+    let second = test_bed.commit_file("LICENSE", "Second");
+
+    assert_eq!(
+        branch(BranchAction::List).unwrap(),
+        test_bed.workspace_git_output(vec!["branch"])
+    );
+    assert_eq!(list_tags(), "v1.0\n");
+    assert_eq!(
+        rev_parse(
+            &[
+                "master".to_string(),
+                "feature/old".to_string(),
+                "v1.0".to_string(),
+                "v1.0^{}".to_string()
+            ],
+            false
+        )
+        .unwrap(),
+        test_bed.workspace_git_output(vec![
+            "rev-parse",
+            "master",
+            "feature/old",
+            "v1.0",
+            "v1.0^{}"
+        ])
+    );
+    assert_eq!(
+        rev_parse(&["master".to_string()], false).unwrap(),
+        format!("{}\n", second)
+    );
+
+    checkout("feature/old", None).unwrap();
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]),
+        format!("{}\n", first)
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn packs_refs_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let oid = test_bed.commit_file("README", "First");
+    create_branch("feature/one");
+    create_tag("v1.0", None);
+    create_tag("v2.0", Some("The second release"));
+    let tag_oid = fs::read_to_string(".git/refs/tags/v2.0").unwrap();
+
+    // Without --all, only tags are packed
+    pack_refs(false).unwrap();
+    assert!(!test_bed.workspace().join(".git/refs/tags/v1.0").exists());
+    assert!(test_bed.workspace().join(".git/refs/heads/master").exists());
+
+    pack_refs(true).unwrap();
+    let packed = fs::read_to_string(".git/packed-refs").unwrap();
+    assert_eq!(
+        packed,
+        format!(
+            "# pack-refs with: peeled fully-peeled sorted \n\
+             {oid} refs/heads/feature/one\n\
+             {oid} refs/heads/master\n\
+             {oid} refs/tags/v1.0\n\
+             {tag} refs/tags/v2.0\n\
+             ^{oid}\n",
+            oid = oid,
+            tag = tag_oid.trim()
+        )
+    );
+    assert!(!test_bed
+        .workspace()
+        .join(".git/refs/heads/feature")
+        .exists());
+    assert!(test_bed.workspace().join(".git/refs/heads").is_dir());
+    assert!(test_bed.workspace().join(".git/refs/tags").is_dir());
+    assert_eq!(
+        fs::read_to_string(".git/HEAD").unwrap(),
+        "ref: refs/heads/master\n"
+    );
+
+    // Git has nothing left to pack, and writes the same file
+    test_bed.workspace_git_output(vec!["pack-refs", "--all"]);
+    assert_eq!(fs::read_to_string(".git/packed-refs").unwrap(), packed);
+
+    // Committing moves the branch into a loose ref again
+    let second = test_bed.commit_file("LICENSE", "Second");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["rev-parse", "master"]),
+        format!("{}\n", second)
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn deletes_packed_refs() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    create_branch("topic");
+    create_tag("v1.0", Some("The first release"));
+    create_tag("v2.0", None);
+    pack_refs(true).unwrap();
+
+    tag(TagAction::Delete {
+        names: vec!["v1.0".to_string()],
+    })
+    .unwrap();
+    branch(BranchAction::Delete {
+        names: vec!["topic".to_string()],
+        force: false,
+    })
+    .unwrap();
+
+    assert_eq!(list_tags(), "v2.0\n");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["for-each-ref", "--format=%(refname)"]),
+        "refs/heads/master\nrefs/tags/v2.0\n"
+    );
+    assert!(!fs::read_to_string(".git/packed-refs")
+        .unwrap()
+        .contains("v1.0"));
+    assert_eq!(
+        tag(TagAction::Delete {
+            names: vec!["v1.0".to_string()]
+        })
+        .unwrap_err(),
+        "tag 'v1.0' not found."
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn deletes_packed_refs_in_directories_git_removed() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    create_branch("feature/x");
+    create_tag("release/v1", None);
+    test_bed.workspace_git_output(vec!["pack-refs", "--all"]);
+    assert!(!test_bed.workspace().join(".git/refs/tags/release").exists());
+
+    tag(TagAction::Delete {
+        names: vec!["release/v1".to_string()],
+    })
+    .unwrap();
+    branch(BranchAction::Delete {
+        names: vec!["feature/x".to_string()],
+        force: true,
+    })
+    .unwrap();
+
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["for-each-ref", "--format=%(refname)"]),
+        "refs/heads/master\n"
+    );
+    assert!(!test_bed.workspace().join(".git/refs/tags/release").exists());
+    assert!(!test_bed
+        .workspace()
+        .join(".git/refs/heads/feature")
+        .exists());
+
+    test_bed.teardown();
+}
+
+#[test]
+fn packs_on_top_of_refs_git_packed_in_directories() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    create_tag("release/v2", Some("The second release"));
+    create_branch("feature/y");
+    test_bed.workspace_git_output(vec!["pack-refs", "--all"]);
+    assert!(!test_bed.workspace().join(".git/refs/tags/release").exists());
+
+    create_tag("v3", None);
+    pack_refs(false).unwrap();
+    create_branch("topic");
+    pack_refs(true).unwrap();
+    let packed = fs::read_to_string(".git/packed-refs").unwrap();
+
+    assert!(!test_bed.workspace().join(".git/refs/tags/release").exists());
+    assert!(!test_bed.workspace().join(".git/refs/tags/v3").exists());
+    assert!(!test_bed.workspace().join(".git/refs/heads/topic").exists());
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["for-each-ref", "--format=%(refname)"]),
+        "refs/heads/feature/y\nrefs/heads/master\nrefs/heads/topic\nrefs/tags/release/v2\nrefs/tags/v3\n"
+    );
+
+    // Git has nothing left to pack, and writes the same sorted file
+    test_bed.workspace_git_output(vec!["pack-refs", "--all"]);
+    assert_eq!(fs::read_to_string(".git/packed-refs").unwrap(), packed);
+
+    test_bed.teardown();
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use common::TestBed;
+use grit::{
+    commands::{init, rev_parse},
+    objects::{self, Blob, Object},
+};
+use std::{collections::HashMap, fs, process::Command};
+
+fn parse(revision: &str) -> Result<String, String> {
+    rev_parse(&[revision.to_string()], false)
+}
+
+// Have git record a merge of two commits on master, since grit can't merge yet
+fn git_merge(test_bed: &TestBed, first: &str, second: &str) -> String {
+    let tree = test_bed.workspace_git_output(vec!["rev-parse", &format!("{}^{{tree}}", first)]);
+    let merge = test_bed.workspace_git_output(vec![
+        "commit-tree",
+        tree.trim(),
+        "-p",
+        first,
+        "-p",
+        second,
+        "-m",
+        "Merge",
+    ]);
+    let merge = merge.trim().to_string();
+    test_bed.workspace_git_output(vec!["update-ref", "refs/heads/master", &merge]);
+    merge
+}
+
+#[test]
+fn resolves_revisions_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+    test_bed.commit_file("docs/guide.md", "Second");
+    let third = test_bed.commit_file("LICENSE", "Third");
+    git_merge(&test_bed, &third, &first);
+
+    let revisions = vec![
+        "HEAD",
+        "@",
+        "master",
+        "heads/master",
+        "refs/heads/master",
+        "HEAD^",
+        "HEAD^1",
+        "HEAD^2",
+        "@~2",
+        "HEAD~3",
+        "master^^^",
+        "HEAD^0",
+        "HEAD^{tree}",
+        "HEAD~2^{commit}",
+        "HEAD:README",
+        "HEAD:docs",
+        "HEAD:docs/guide.md",
+        "HEAD~1:",
+        ":README",
+        &first[..4],
+        &third[..10],
+    ];
+    for revision in revisions {
+        assert_eq!(
+            parse(revision).unwrap(),
+            test_bed.workspace_git_output(vec!["rev-parse", revision]),
+            "Resolved {} differently",
+            revision
+        );
+    }
+    assert_eq!(
+        rev_parse(&["HEAD~3".to_string()], true).unwrap(),
+        format!("{}\n", &first[..7])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn reports_what_it_cannot_resolve() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+
+    for revision in [
+        "nowhere",
+        "HEAD~1",
+        "HEAD^2",
+        "HEAD^{tree}^{commit}",
+        "HEAD^{nonsense}",
+    ] {
+        assert_eq!(
+            parse(revision),
+            Err(format!(
+                "ambiguous argument '{}': unknown revision or path not in the working tree.",
+                revision
+            ))
+        );
+    }
+    assert_eq!(
+        parse("HEAD:missing"),
+        Err("path 'missing' does not exist in 'HEAD'".to_string())
+    );
+    test_bed.create_file("untracked", "Not in the index");
+    assert_eq!(
+        parse(":untracked"),
+        Err("path 'untracked' exists on disk, but not in the index".to_string())
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn rejects_characters_after_a_suffix() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.commit_file("README", "First");
+    test_bed.commit_file("LICENSE", "Second");
+
+    for revision in ["HEAD^{commit}x", "HEAD^1é", "HEAD~é"] {
+        assert_eq!(
+            parse(revision),
+            Err(format!(
+                "ambiguous argument '{}': unknown revision or path not in the working tree.",
+                revision
+            ))
+        );
+        assert!(test_bed
+            .workspace_git_output(vec!["rev-parse", "--verify", "-q", revision])
+            .is_empty());
+    }
+
+    test_bed.teardown();
+}
+
+#[test]
+fn gives_up_on_symbolic_refs_that_go_too_deep() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let head = test_bed.commit_file("README", "First");
+
+    fs::write(".git/refs/heads/loop", "ref: refs/heads/loop\n").unwrap();
+    let mut target = String::from("refs/heads/master");
+    for depth in 1..=5 {
+        let name = format!("refs/heads/chain{}", depth);
+        fs::write(format!(".git/{}", name), format!("ref: {}\n", target)).unwrap();
+        target = name;
+    }
+
+    // Git reads at most five refs, so four symbolic refs in a row still resolve
+    assert_eq!(parse("chain4"), Ok(format!("{}\n", head)));
+    for revision in ["loop", "chain5"] {
+        assert_eq!(
+            parse(revision),
+            Err(format!(
+                "ambiguous argument '{}': unknown revision or path not in the working tree.",
+                revision
+            ))
+        );
+        assert!(test_bed
+            .workspace_git_output(vec!["rev-parse", "--verify", "-q", revision])
+            .is_empty());
+    }
+
+    test_bed.teardown();
+}
+
+#[test]
+fn lists_candidates_for_ambiguous_abbreviations() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    // Hash blobs until two share a four character prefix
+    let mut prefixes = HashMap::new();
+    let (prefix, pair) = (0..)
+        .find_map(|n| {
+            let blob = Blob::from_bytes(format!("Blob {}", n).into_bytes());
+            let prefix = blob.id()[..4].to_string();
+            prefixes.insert(prefix.to_owned(), blob).map(|earlier| {
+                (
+                    prefix.to_owned(),
+                    (earlier, prefixes.remove(&prefix).unwrap()),
+                )
+            })
+        })
+        .unwrap();
+    objects::write(&pair.0).unwrap();
+    objects::write(&pair.1).unwrap();
+
+    let mut ids = [pair.0.id(), pair.1.id()];
+    ids.sort();
+    assert_eq!(
+        parse(&prefix),
+        Err(format!(
+            "short object ID {} is ambiguous\nhint: The candidates are:\nhint:   {} blob\nhint:   {} blob",
+            prefix,
+            &ids[0][..7],
+            &ids[1][..7]
+        ))
+    );
+    let git = Command::new("git")
+        .args(["rev-parse", &prefix])
+        .current_dir(test_bed.workspace())
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&git.stderr).contains(&format!("{} blob", &ids[0][..7])));
+
+    assert_eq!(parse(&ids[0][..5]).unwrap(), format!("{}\n", ids[0]));
+
+    test_bed.teardown();
+}
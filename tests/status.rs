@@ -0,0 +1,155 @@
+mod common;
+
+use chrono::Local;
+use common::TestBed;
+use grit::{
+    commands::{add, commit, init, status, StatusFormat},
+    index::Index,
+};
+use std::{env, fs, os::unix::fs::MetadataExt, path::PathBuf, thread, time::Duration};
+
+// Commit the same files with grit in the workspace and git in the twin
+fn commit_in_both(test_bed: &TestBed) {
+    let right_now = Local::now().format("%s %z").to_string();
+    env::set_var("GIT_AUTHOR_DATE", &right_now);
+    env::set_var("GIT_COMMITTER_DATE", &right_now);
+
+    add(&[PathBuf::from(".")]).unwrap();
+    commit("Initial commit").unwrap();
+    test_bed.git_command(vec!["add", "."]);
+    test_bed.git_command(vec!["commit", "-m", "Initial commit"]);
+}
+
+fn remove_in_both(test_bed: &TestBed, relative_path: &str) {
+    fs::remove_file(test_bed.workspace().join(relative_path)).unwrap();
+    fs::remove_file(test_bed.twin().join(relative_path)).unwrap();
+}
+
+#[test]
+fn reports_changes_like_git_porcelain() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+
+    test_bed.create_file("README", "This is the README");
+    test_bed.create_file("LICENSE", "This is the license");
+    test_bed.create_file("src/main.rs", "fn main() {}");
+    test_bed.create_file("src/lib.rs", "pub mod things;");
+    test_bed.create_file("src/deleted.rs", "gone soon");
+    test_bed.create_file("staged_then_changed", "first");
+    commit_in_both(&test_bed);
+
+    // Let a second pass, so rewrites of the same size get new timestamps
+    thread::sleep(Duration::from_millis(1100));
+
+    test_bed.create_file("README", "This is the README, changed");
+    test_bed.create_file("LICENSE", "This is the LICENSE");
+    remove_in_both(&test_bed, "src/deleted.rs");
+    test_bed.create_file("src/new.rs", "untracked in a tracked directory");
+    test_bed.create_file("docs/guide/intro.md", "an untracked directory");
+    test_bed.create_file("has space.txt", "needs quoting");
+    test_bed.create_directory("empty");
+
+    test_bed.create_file("staged_new", "added to the index");
+    test_bed.create_file("src/lib.rs", "pub mod other_things;");
+    test_bed.create_file("staged_then_changed", "second");
+    remove_in_both(&test_bed, "src/main.rs");
+    for path in &[
+        "staged_new",
+        "src/lib.rs",
+        "staged_then_changed",
+        "src/main.rs",
+    ] {
+        add(&[PathBuf::from(path)]).unwrap();
+        test_bed.git_command(vec!["add", path]);
+    }
+    test_bed.create_file("staged_then_changed", "third!");
+
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        test_bed.git_output(vec!["status", "--porcelain"])
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn colors_only_the_short_format_when_config_says_to() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+    test_bed.create_file("README", "This is the README");
+    commit_in_both(&test_bed);
+
+    test_bed.create_file("README", "This is the README, changed");
+    test_bed.create_file("staged_new", "added to the index");
+    test_bed.create_file("untracked", "not added");
+    add(&[PathBuf::from("staged_new")]).unwrap();
+    test_bed.git_command(vec!["add", "staged_new"]);
+    test_bed.workspace_git_output(vec!["config", "color.status", "always"]);
+    test_bed.git_command(vec!["config", "color.status", "always"]);
+
+    assert_eq!(
+        status(StatusFormat::Short).unwrap(),
+        test_bed.git_output(vec!["status", "--short"])
+    );
+    assert_eq!(
+        status(StatusFormat::Porcelain).unwrap(),
+        test_bed.git_output(vec!["status", "--porcelain"])
+    );
+    assert!(!status(StatusFormat::Porcelain).unwrap().contains('\x1b'));
+
+    test_bed.teardown();
+}
+
+#[test]
+fn refreshes_stat_data_for_files_that_only_look_changed() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    test_bed.git_command(vec!["init"]);
+    test_bed.create_file("README", "This is the README");
+    commit_in_both(&test_bed);
+
+    thread::sleep(Duration::from_millis(1100));
+    test_bed.create_file("README", "This is the README");
+    let metadata = fs::metadata("README").unwrap();
+
+    assert_eq!(status(StatusFormat::Porcelain).unwrap(), "");
+
+    let index = Index::load().unwrap();
+    let entry = index.entry("README").unwrap();
+    assert_eq!(entry.mtime, metadata.mtime() as u32);
+    assert_eq!(entry.mtime_nsec, metadata.mtime_nsec() as u32);
+
+    test_bed.teardown();
+}
+
+#[test]
+fn leaves_changes_made_right_after_staging_visible_to_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+
+    // Stage a file and change it within the same second, trying again if the clock ticks over
+    loop {
+        fs::write("f", "aaa\n").unwrap();
+        test_bed.workspace_git_output(vec!["add", "f"]);
+        fs::write("f", "bbb\n").unwrap();
+        let staged = fs::metadata(".git/index").unwrap().mtime();
+        if fs::metadata("f").unwrap().mtime() == staged {
+            break;
+        }
+    }
+    thread::sleep(Duration::from_millis(1100));
+
+    assert_eq!(status(StatusFormat::Porcelain).unwrap(), "AM f\n");
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["status", "--short"]),
+        "AM f\n"
+    );
+
+    test_bed.teardown();
+}
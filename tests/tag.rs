@@ -0,0 +1,173 @@
+mod common;
+
+use common::TestBed;
+use grit::commands::{cat_file, checkout, init, rev_parse, tag, CatFileMode, TagAction};
+use std::fs;
+
+fn create(name: &str, target: Option<&str>, message: Option<&str>) -> Result<String, String> {
+    tag(TagAction::Create {
+        name: name.to_string(),
+        target: target.map(String::from),
+        message: message.map(String::from),
+    })
+}
+
+fn list(patterns: &[&str]) -> String {
+    tag(TagAction::List {
+        patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+    })
+    .unwrap()
+}
+
+#[test]
+fn creates_lightweight_tags_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+    let second = test_bed.commit_file("LICENSE", "Second");
+
+    create("v1.0", Some(&first), None).unwrap();
+    create("v2.0", None, None).unwrap();
+    assert_eq!(
+        fs::read_to_string(".git/refs/tags/v1.0").unwrap(),
+        format!("{}\n", first)
+    );
+    assert_eq!(
+        fs::read_to_string(".git/refs/tags/v2.0").unwrap(),
+        format!("{}\n", second)
+    );
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["rev-parse", "v1.0"]),
+        format!("{}\n", first)
+    );
+
+    assert_eq!(
+        create("v1.0", None, None).unwrap_err(),
+        "tag 'v1.0' already exists"
+    );
+    assert_eq!(
+        create("bad..name", None, None).unwrap_err(),
+        "'bad..name' is not a valid tag name."
+    );
+    assert_eq!(
+        create("v3.0", Some("nowhere"), None).unwrap_err(),
+        "Failed to resolve 'nowhere' as a valid ref."
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn creates_annotated_tags_git_can_read() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+    test_bed.commit_file("LICENSE", "Second");
+
+    create("v1.0", Some("HEAD~1"), Some("The first release")).unwrap();
+    let tag_oid = fs::read_to_string(".git/refs/tags/v1.0").unwrap();
+    assert_ne!(tag_oid, format!("{}\n", first));
+
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["cat-file", "-t", "v1.0"]),
+        "tag\n"
+    );
+    assert_eq!(
+        String::from_utf8(cat_file(CatFileMode::Pretty, "v1.0").unwrap()).unwrap(),
+        test_bed.workspace_git_output(vec!["cat-file", "-p", "v1.0"])
+    );
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["tag", "-l", "-n1"]),
+        "v1.0            The first release\n"
+    );
+
+    // Tags peel down to what they tag
+    assert_eq!(
+        rev_parse(&["v1.0^{}".to_string()], false).unwrap(),
+        format!("{}\n", first)
+    );
+    assert_eq!(
+        rev_parse(
+            &["v1.0^{commit}".to_string(), "v1.0^{tree}".to_string()],
+            false
+        )
+        .unwrap(),
+        test_bed.workspace_git_output(vec!["rev-parse", "v1.0^{commit}", "v1.0^{tree}"])
+    );
+    assert_eq!(rev_parse(&["v1.0".to_string()], false).unwrap(), tag_oid);
+
+    // A tag of a tag peels all the way down, too
+    create("v1.0-again", Some("v1.0"), Some("Still the first release")).unwrap();
+    assert_eq!(
+        rev_parse(&["v1.0-again^{commit}".to_string()], false).unwrap(),
+        format!("{}\n", first)
+    );
+    assert_eq!(
+        test_bed
+            .workspace_git_output(vec!["cat-file", "-p", "v1.0-again"])
+            .lines()
+            .next(),
+        Some(format!("object {}", tag_oid.trim()).as_str())
+    );
+
+    checkout("v1.0", None).unwrap();
+    assert_eq!(
+        test_bed.workspace_git_output(vec!["rev-parse", "HEAD"]),
+        format!("{}\n", first)
+    );
+
+    test_bed.teardown();
+}
+
+#[test]
+fn lists_and_deletes_tags_like_git() {
+    let test_bed = TestBed::setup();
+
+    init(Some(&test_bed.workspace()));
+    let first = test_bed.commit_file("README", "First");
+
+    for name in &["v1.0", "v1.1", "v2.0-rc1", "v2.0", "nightly/1"] {
+        create(name, None, None).unwrap();
+    }
+    create("v1.2", None, Some("Annotated")).unwrap();
+
+    assert_eq!(list(&[]), test_bed.workspace_git_output(vec!["tag"]));
+    assert_eq!(
+        list(&["v1.*"]),
+        test_bed.workspace_git_output(vec!["tag", "-l", "v1.*"])
+    );
+    assert_eq!(list(&["v1.*"]), "v1.0\nv1.1\nv1.2\n");
+    assert_eq!(
+        list(&["*rc*", "nightly/*"]),
+        test_bed.workspace_git_output(vec!["tag", "-l", "*rc*", "nightly/*"])
+    );
+    assert_eq!(
+        list(&["v[0-1].?"]),
+        test_bed.workspace_git_output(vec!["tag", "-l", "v[0-1].?"])
+    );
+
+    let deleted = tag(TagAction::Delete {
+        names: vec!["v1.0".to_string(), "nightly/1".to_string()],
+    })
+    .unwrap();
+    let abbreviated = &first[..7];
+    assert_eq!(
+        deleted,
+        format!(
+            "Deleted tag 'v1.0' (was {})\nDeleted tag 'nightly/1' (was {})\n",
+            abbreviated, abbreviated
+        )
+    );
+    assert_eq!(list(&[]), test_bed.workspace_git_output(vec!["tag"]));
+    assert_eq!(
+        tag(TagAction::Delete {
+            names: vec!["v1.0".to_string()]
+        })
+        .unwrap_err(),
+        "tag 'v1.0' not found."
+    );
+
+    test_bed.teardown();
+}